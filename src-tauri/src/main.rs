@@ -1,7 +1,7 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use ghostie::plugins::{chat, mcp, node, plugin_fs};
+use ghostie::plugins::{approval, chat, mcp, node, plugin_fs};
 use ghostie::utils;
 use tauri::{
     menu::{Menu, MenuItem},
@@ -10,9 +10,27 @@ use tauri::{
 };
 use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
 
+/// 以 MCP stdio 服务方式运行，不启动界面
+const MCP_STDIO_FLAG: &str = "--mcp-stdio";
+
 /* 主函数 */
 #[tokio::main]
 async fn main() {
+    if std::env::args().any(|arg| arg == MCP_STDIO_FLAG) {
+        // 没有界面可以询问，只按审批策略放行
+        if let Err(e) = approval::init().await {
+            eprintln!("初始化工具审批策略失败: {:#}", e);
+        }
+        if let Err(e) = node::init().await {
+            eprintln!("初始化 Node 插件管理器失败: {}", e);
+        }
+        if let Err(e) = mcp::host::serve_stdio().await {
+            eprintln!("MCP 服务异常退出: {:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_notification::init())
@@ -52,11 +70,23 @@ async fn main() {
                 node::set_app_handle(app_handle).await;
             });
 
+            // 初始化工具审批策略
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = approval::init().await {
+                    eprintln!("初始化工具审批策略失败: {:#}", e);
+                }
+                approval::set_app_handle(app_handle).await;
+            });
+
             // 初始化 MCP 插件管理器
+            let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 if let Err(e) = mcp::init().await {
                     eprintln!("初始化 MCP 插件管理器失败: {}", e);
                 }
+                mcp::set_app_handle(app_handle).await;
+                mcp::autostart().await;
             });
 
             // 仅在桌面平台启用自动更新功能
@@ -133,6 +163,7 @@ async fn main() {
             chat::image_result,
             chat::image_generate,
             chat::chat_json,
+            chat::audio::audio_stream,
             utils::file::open_files_path,
             utils::file::open_file,
             utils::file::save_file,
@@ -142,25 +173,71 @@ async fn main() {
             utils::file::write_file,
             utils::window::open_config_dir,
             node::plugin_execute,
+            node::plugin_cancel,
+            node::plugin_export,
+            node::plugin_import_preview,
+            node::plugin_import,
             node::env_list,
             node::env_save,
             node::node_install,
             node::node_check,
+            node::node_set_path,
             node::node_list_dependencies,
             node::node_install_dependency,
             node::node_update_dependencies,
             node::node_uninstall_dependency,
             utils::window::open_url,
             utils::window::notify,
+            utils::window::open_window,
+            utils::window::hide_window,
+            utils::window::show_window,
+            utils::window::close_window,
+            utils::window::list_windows,
             mcp::start_service,
             mcp::stop_service,
             mcp::get_service_info,
             mcp::call_tool,
+            mcp::cancel_tool_call,
+            mcp::resolve_blob,
+            mcp::get_service_status,
+            mcp::list_resources,
+            mcp::list_resource_templates,
+            mcp::read_resource,
+            mcp::subscribe_resource,
+            mcp::unsubscribe_resource,
+            mcp::list_prompts,
+            mcp::get_prompt,
+            mcp::mcp_client_respond,
+            mcp::mcp_server_list,
+            mcp::mcp_server_save,
+            mcp::mcp_server_remove,
+            mcp::mcp_server_import,
+            mcp::mcp_server_export,
+            mcp::mcp_host_start,
+            mcp::mcp_host_stop,
+            mcp::mcp_host_status,
+            mcp::mcp_host_respond,
             node::code_plugins,
+            approval::approval_respond,
+            approval::approval_policy_get,
+            approval::approval_policy_save,
+            approval::approval_audit,
             plugin_fs::plugin_save_content,
             plugin_fs::plugin_get_content,
             plugin_fs::plugin_delete,
             plugin_fs::plugin_list,
+            plugin_fs::plugin_list_detailed,
+            plugin_fs::plugin_manifest,
+            plugin_fs::plugin_parse_content,
+            plugin_fs::plugin_revisions,
+            plugin_fs::plugin_revision_content,
+            plugin_fs::plugin_revision_diff,
+            plugin_fs::plugin_revision_restore,
+            plugin_fs::plugin_revision_limit,
+            plugin_fs::plugin_revision_limit_set,
+            plugin_fs::plugin_trash_list,
+            plugin_fs::plugin_trash_restore,
+            plugin_fs::plugin_trash_empty,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 审计日志文件名，位于配置目录下，每行一条 JSON 记录
+const AUDIT_FILE: &str = "approval_audit.jsonl";
+
+// 串行写入，避免并发追加时行交错
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 审批结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Allowed,
+    Denied,
+}
+
+/// 做出决定的一方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DecidedBy {
+    /// 策略规则或默认处理方式
+    Policy,
+    /// 用户在界面上确认
+    User,
+    /// 等待用户确认超时
+    Timeout,
+    /// 以 stdio 方式运行，没有界面可以询问
+    Headless,
+}
+
+/// 一条审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// 记录时间（RFC 3339）
+    pub time: String,
+    pub tool: String,
+    pub args: Value,
+    pub decision: Decision,
+    pub decided_by: DecidedBy,
+    /// 生效的规则序号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<usize>,
+}
+
+fn audit_path() -> Result<PathBuf> {
+    Ok(crate::utils::file::get_config_dir()
+        .context("无法获取配置目录")?
+        .join(AUDIT_FILE))
+}
+
+/// 追加一条记录
+pub fn record(entry: &AuditEntry) -> Result<()> {
+    let path = audit_path()?;
+    let line = serde_json::to_string(entry)?;
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("打开 {} 失败", path.display()))?;
+    writeln!(file, "{}", line).with_context(|| format!("写入 {} 失败", path.display()))?;
+    Ok(())
+}
+
+/// 查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    /// 工具标识，可用 `*` 通配
+    pub tool: Option<String>,
+    pub decision: Option<Decision>,
+    /// 只返回此时间（RFC 3339）之后的记录
+    pub since: Option<String>,
+    /// 最多返回的条数，默认 100
+    pub limit: Option<usize>,
+}
+
+/// 按条件查询记录，最新的在前
+pub fn query(query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+    let path = audit_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let since = query
+        .since
+        .as_deref()
+        .map(chrono::DateTime::parse_from_rfc3339)
+        .transpose()
+        .context("since 不是有效的 RFC 3339 时间")?;
+
+    let file = fs::File::open(&path).with_context(|| format!("读取 {} 失败", path.display()))?;
+    let mut entries: Vec<AuditEntry> = BufReader::new(file)
+        .lines()
+        .map_while(|line| line.ok())
+        // 跳过写入中断留下的不完整行
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(&line).ok())
+        .filter(|entry| {
+            !matches!(query.tool.as_deref(), Some(tool) if !super::policy::wildcard_match(tool, &entry.tool))
+        })
+        .filter(|entry| !matches!(query.decision, Some(d) if d != entry.decision))
+        .filter(|entry| {
+            !matches!(since, Some(since) if !chrono::DateTime::parse_from_rfc3339(&entry.time)
+                .is_ok_and(|time| time >= since))
+        })
+        .collect();
+    entries.reverse();
+    entries.truncate(query.limit.unwrap_or(100));
+    Ok(entries)
+}
@@ -0,0 +1,218 @@
+pub mod audit;
+pub mod policy;
+
+use crate::plugins::approval::audit::{AuditEntry, AuditQuery, DecidedBy, Decision};
+use crate::plugins::approval::policy::{PolicyAction, PolicyFile, PolicyStore};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{oneshot, Mutex};
+
+/// 审批请求事件名，前端处理后调用 `approval_respond` 回复
+pub const APPROVAL_EVENT: &str = "tool-approval-request";
+
+/// 等待用户确认的最长时间，超时视为拒绝
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(120);
+
+static APP_HANDLE: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+static POLICY_STORE: Lazy<Mutex<Option<PolicyStore>>> = Lazy::new(|| Mutex::new(None));
+// 等待用户确认的请求
+static PENDING: Lazy<std::sync::Mutex<HashMap<String, oneshot::Sender<bool>>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// 审批请求的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalKind {
+    /// 工具调用，`args` 为调用参数
+    Tool,
+    /// 插件申请权限，`tool` 为插件 id，`args` 为申请的权限
+    Permission,
+}
+
+/// 发送给前端的审批请求
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    /// 回复时使用的 id
+    pub request_id: String,
+    pub kind: ApprovalKind,
+    pub tool: String,
+    pub args: Value,
+    /// 等待确认的毫秒数，超时自动拒绝
+    pub timeout_ms: u64,
+}
+
+/// 初始化策略
+pub async fn init() -> Result<()> {
+    let mut store = POLICY_STORE.lock().await;
+    if store.is_none() {
+        *store = Some(PolicyStore::load()?);
+    }
+    Ok(())
+}
+
+/// 设置 AppHandle，用于发送审批请求
+pub async fn set_app_handle(handle: AppHandle) {
+    let mut app_handle = APP_HANDLE.lock().await;
+    *app_handle = Some(handle);
+}
+
+/// 记录审计日志，写入失败不影响调用
+fn audit(tool: &str, args: &Value, decision: Decision, decided_by: DecidedBy, rule: Option<usize>) {
+    let entry = AuditEntry {
+        time: chrono::Local::now().to_rfc3339(),
+        tool: tool.to_string(),
+        args: args.clone(),
+        decision,
+        decided_by,
+        rule,
+    };
+    if let Err(e) = audit::record(&entry) {
+        eprintln!("写入审批日志失败: {:#}", e);
+    }
+}
+
+/// 询问用户，返回是否同意；没有界面或超时时视为拒绝
+async fn ask_user(kind: ApprovalKind, tool: &str, args: &Value) -> (bool, DecidedBy) {
+    let Some(app) = APP_HANDLE.lock().await.clone() else {
+        return (false, DecidedBy::Headless);
+    };
+
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = oneshot::channel();
+    PENDING.lock().unwrap().insert(request_id.clone(), tx);
+
+    let request = ApprovalRequest {
+        request_id: request_id.clone(),
+        kind,
+        tool: tool.to_string(),
+        args: args.clone(),
+        timeout_ms: APPROVAL_TIMEOUT.as_millis() as u64,
+    };
+    if let Err(e) = app.emit(APPROVAL_EVENT, request) {
+        eprintln!("Failed to emit {} event: {}", APPROVAL_EVENT, e);
+    }
+
+    let reply = tokio::time::timeout(APPROVAL_TIMEOUT, rx).await;
+    PENDING.lock().unwrap().remove(&request_id);
+    match reply {
+        Ok(Ok(approved)) => (approved, DecidedBy::User),
+        Ok(Err(_)) => (false, DecidedBy::User),
+        Err(_) => (false, DecidedBy::Timeout),
+    }
+}
+
+/// 工具调用前的审批
+///
+/// `tool` 为 `mcp:{服务}/{工具}` 或 `plugin:{插件}/{函数}`。按策略放行、拒绝或询问用户，
+/// 每次决定都写入审计日志。被拒绝时返回原因。
+/// 没有界面时（stdio 模式）需要询问的调用一律拒绝，只有策略明确允许的调用可以执行。
+pub async fn check(tool: &str, args: &Value) -> Result<(), String> {
+    let evaluation = match POLICY_STORE.lock().await.as_ref() {
+        Some(store) => store.get().evaluate(tool, args),
+        // 策略未加载时一律询问
+        None => PolicyFile::default().evaluate(tool, args),
+    };
+
+    let (approved, decided_by) = match evaluation.action {
+        PolicyAction::Allow => (true, DecidedBy::Policy),
+        PolicyAction::Deny => (false, DecidedBy::Policy),
+        PolicyAction::Ask => ask_user(ApprovalKind::Tool, tool, args).await,
+    };
+    let decision = if approved {
+        Decision::Allowed
+    } else {
+        Decision::Denied
+    };
+    audit(tool, args, decision, decided_by, evaluation.rule);
+
+    if approved {
+        return Ok(());
+    }
+    Err(match decided_by {
+        DecidedBy::Policy => format!("工具 {} 的调用被策略拒绝", tool),
+        DecidedBy::User => format!("用户拒绝了工具 {} 的调用", tool),
+        DecidedBy::Timeout => format!("等待确认工具 {} 的调用超时", tool),
+        DecidedBy::Headless => format!(
+            "工具 {} 的调用需要确认，但当前没有界面可以询问，请在审批策略中添加允许规则",
+            tool
+        ),
+    })
+}
+
+/// 询问用户是否授予插件申请的权限，不经过审批策略
+///
+/// 审计日志中的工具标识为 `permission:{插件}`。被拒绝时返回原因。
+pub async fn confirm_permissions(plugin_id: &str, permissions: &Value) -> Result<(), String> {
+    let (approved, decided_by) = ask_user(ApprovalKind::Permission, plugin_id, permissions).await;
+    let decision = if approved {
+        Decision::Allowed
+    } else {
+        Decision::Denied
+    };
+    audit(
+        &format!("permission:{}", plugin_id),
+        permissions,
+        decision,
+        decided_by,
+        None,
+    );
+
+    if approved {
+        return Ok(());
+    }
+    Err(match decided_by {
+        DecidedBy::Timeout => format!("等待确认插件 {} 的权限超时", plugin_id),
+        DecidedBy::Headless => format!(
+            "插件 {} 申请了新的权限，当前没有界面可以询问，请先在应用中授予",
+            plugin_id
+        ),
+        _ => format!("插件 {} 申请的权限未被授予", plugin_id),
+    })
+}
+
+/// 回复审批请求
+#[tauri::command]
+pub async fn approval_respond(request_id: String, approved: bool) -> Result<(), String> {
+    let sender = PENDING
+        .lock()
+        .unwrap()
+        .remove(&request_id)
+        .ok_or_else(|| format!("审批请求 {} 不存在或已超时", request_id))?;
+    let _ = sender.send(approved);
+    Ok(())
+}
+
+/// 获取审批策略
+#[tauri::command]
+pub async fn approval_policy_get() -> Result<PolicyFile, String> {
+    let store = POLICY_STORE.lock().await;
+    match store.as_ref() {
+        Some(store) => Ok(store.get().clone()),
+        None => Err("审批策略未初始化".to_string()),
+    }
+}
+
+/// 保存审批策略
+#[tauri::command]
+pub async fn approval_policy_save(policy: PolicyFile) -> Result<(), String> {
+    let mut store = POLICY_STORE.lock().await;
+    let store = store
+        .as_mut()
+        .ok_or_else(|| "审批策略未初始化".to_string())?;
+    store.set(policy).map_err(|e| format!("{:#}", e))
+}
+
+/// 查询审批日志
+#[tauri::command]
+pub async fn approval_audit(query: Option<AuditQuery>) -> Result<Vec<AuditEntry>, String> {
+    let query = query.unwrap_or_default();
+    tokio::task::spawn_blocking(move || audit::query(&query))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("{:#}", e))
+}
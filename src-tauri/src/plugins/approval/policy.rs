@@ -0,0 +1,217 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// 策略文件名，位于配置目录下
+const POLICY_FILE: &str = "approval.json";
+
+/// 规则对工具调用的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    /// 直接放行
+    Allow,
+    /// 每次询问用户
+    #[default]
+    Ask,
+    /// 直接拒绝
+    Deny,
+}
+
+/// 参数的匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Equals,
+    Prefix,
+    /// 路径前缀，比较前先规范化 `.`、`..` 和分隔符，按完整的路径段匹配
+    PathPrefix,
+}
+
+/// 参数条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArgPattern {
+    /// 参数路径，嵌套字段用 `.` 分隔，如 `options.path`
+    pub arg: String,
+    pub kind: MatchKind,
+    pub value: String,
+}
+
+/// 一条策略规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyRule {
+    /// 工具标识，`mcp:{服务}/{工具}` 或 `plugin:{插件}/{函数}`，可用 `*` 通配
+    pub tool: String,
+    pub action: PolicyAction,
+    /// 参数条件，全部满足时规则才生效
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<ArgPattern>,
+}
+
+/// 策略文件的结构
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyFile {
+    /// 没有规则匹配时的处理方式
+    #[serde(default)]
+    pub default_action: PolicyAction,
+    /// 按顺序匹配，第一条匹配的规则生效
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+/// 匹配结果
+#[derive(Debug, Clone, Copy)]
+pub struct Evaluation {
+    pub action: PolicyAction,
+    /// 生效的规则序号，使用默认处理方式时为空
+    pub rule: Option<usize>,
+}
+
+/// `*` 通配匹配
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if text.len() < first.len() + last.len() || !text.starts_with(first) || !text.ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// 把路径拆分为规范化后的路径段，`..` 超出根（或相对路径的起点）时返回 `None`
+fn path_segments(path: &str) -> Option<Vec<String>> {
+    let path = path.replace('\\', "/");
+    let mut segments: Vec<String> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment if cfg!(windows) => segments.push(segment.to_lowercase()),
+            segment => segments.push(segment.to_string()),
+        }
+    }
+    Some(segments)
+}
+
+impl ArgPattern {
+    fn matches_str(&self, value: &str) -> bool {
+        match self.kind {
+            MatchKind::Equals => value == self.value,
+            MatchKind::Prefix => value.starts_with(&self.value),
+            MatchKind::PathPrefix => {
+                // 绝对路径和相对路径不能互相匹配，超出根的路径不匹配任何前缀
+                let absolute = |p: &str| p.starts_with(['/', '\\']) || p.contains(':');
+                match (path_segments(&self.value), path_segments(value)) {
+                    (Some(prefix), Some(path)) => {
+                        absolute(&self.value) == absolute(value) && path.starts_with(&prefix)
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    /// 参数为数组时要求每个元素都满足条件，参数缺失时不满足
+    fn matches(&self, args: &Value) -> bool {
+        let value = self
+            .arg
+            .split('.')
+            .try_fold(args, |value, key| value.get(key));
+        match value {
+            Some(Value::String(value)) => self.matches_str(value),
+            Some(Value::Number(value)) => self.matches_str(&value.to_string()),
+            Some(Value::Bool(value)) => self.matches_str(&value.to_string()),
+            Some(Value::Array(values)) => {
+                !values.is_empty()
+                    && values
+                        .iter()
+                        .all(|value| value.as_str().is_some_and(|v| self.matches_str(v)))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl PolicyFile {
+    /// 找出对工具调用生效的规则
+    pub fn evaluate(&self, tool: &str, args: &Value) -> Evaluation {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| {
+                wildcard_match(&rule.tool, tool) && rule.args.iter().all(|arg| arg.matches(args))
+            })
+            .map(|(index, rule)| Evaluation {
+                action: rule.action,
+                rule: Some(index),
+            })
+            .unwrap_or(Evaluation {
+                action: self.default_action,
+                rule: None,
+            })
+    }
+}
+
+/// 持久化的审批策略，保存在配置目录下的 `approval.json`
+pub struct PolicyStore {
+    path: PathBuf,
+    file: PolicyFile,
+}
+
+impl PolicyStore {
+    /// 从配置目录加载，文件不存在时使用默认策略（全部询问）
+    pub fn load() -> Result<Self> {
+        let path = crate::utils::file::get_config_dir()
+            .context("无法获取配置目录")?
+            .join(POLICY_FILE);
+        let file = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("读取 {} 失败", path.display()))?;
+            if content.trim().is_empty() {
+                PolicyFile::default()
+            } else {
+                serde_json::from_str(&content)
+                    .with_context(|| format!("解析 {} 失败", path.display()))?
+            }
+        } else {
+            PolicyFile::default()
+        };
+        Ok(Self { path, file })
+    }
+
+    pub fn get(&self) -> &PolicyFile {
+        &self.file
+    }
+
+    /// 替换全部策略并写回文件，先写临时文件再替换
+    pub fn set(&mut self, file: PolicyFile) -> Result<()> {
+        for rule in &file.rules {
+            if rule.tool.trim().is_empty() {
+                anyhow::bail!("规则的工具标识不能为空");
+            }
+        }
+        let content = serde_json::to_string_pretty(&file)?;
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, content).with_context(|| format!("写入 {} 失败", temp.display()))?;
+        fs::rename(&temp, &self.path)
+            .with_context(|| format!("写入 {} 失败", self.path.display()))?;
+        self.file = file;
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use base64::Engine;
+use futures_util::StreamExt;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{Emitter, Runtime};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::oneshot;
+
+use super::provider::{self, ProviderKind};
+use super::retry::{self, RetryPolicy};
+use super::{emit_retry, CANCEL_CHANNELS, HTTP_CLIENT};
+
+/// 音频分片事件
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioChunk {
+    /// 分片序号，从 0 开始
+    pub index: u64,
+    /// base64 编码的音频数据
+    pub data: String,
+}
+
+/// 音频流结束时的汇总信息
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioStreamResult {
+    /// 响应的 Content-Type
+    pub content_type: Option<String>,
+    /// 音频总字节数
+    pub total_bytes: u64,
+    /// 保存的文件路径（如果启用了保存），被取消时不保存
+    pub path: Option<String>,
+    /// 是否被取消
+    pub cancelled: bool,
+}
+
+/// 根据 Content-Type 推断音频文件扩展名
+fn audio_extension(content_type: Option<&str>) -> &'static str {
+    let mime = content_type
+        .and_then(|t| t.split(';').next())
+        .unwrap_or("")
+        .trim();
+    match mime {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/ogg" | "audio/opus" => "opus",
+        "audio/aac" => "aac",
+        "audio/flac" => "flac",
+        "audio/pcm" | "audio/l16" => "pcm",
+        _ => "mp3",
+    }
+}
+
+/// 计算保存路径，只保留文件名部分，防止写到音频目录之外
+fn audio_file_path(
+    file_name: Option<&str>,
+    request_id: &str,
+    content_type: Option<&str>,
+) -> Result<PathBuf, String> {
+    let audio_dir = crate::utils::file::get_audio_dir().ok_or("无法获取音频目录")?;
+    let name = file_name
+        .and_then(|name| Path::new(name).file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| format!("{}.{}", request_id, audio_extension(content_type)));
+    Ok(audio_dir.join(name))
+}
+
+/// 文本转语音，流式返回音频数据
+///
+/// 音频分片以 base64 发送到 `audio-stream-{id}`，出错时发送到 `audio-stream-error-{id}`，
+/// 结束时发送汇总信息到 `audio-stream-done-{id}`。
+/// `save` 为 true 时完整音频会保存到配置目录下的 audio 目录，`file_name` 可指定文件名；
+/// 被取消时返回的 `cancelled` 为 true，已写入的部分文件会被删除。
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn audio_stream<R: Runtime>(
+    window: tauri::Window<R>,
+    api_url: String,
+    api_key: String,
+    request_id: String,
+    request_body: serde_json::Value,
+    provider: Option<ProviderKind>,
+    save: Option<bool>,
+    file_name: Option<String>,
+    retry: Option<RetryPolicy>,
+) -> Result<AudioStreamResult, String> {
+    let client = &*HTTP_CLIENT;
+    let headers = provider::provider_for(provider.unwrap_or_default()).headers(&api_key)?;
+    let error_channel = format!("audio-stream-error-{}", request_id);
+
+    // 存储取消通道，与 cancel_stream 共用
+    let (cancel_tx, mut cancel_rx) = oneshot::channel();
+    CANCEL_CHANNELS
+        .lock()
+        .unwrap()
+        .insert(request_id.clone(), cancel_tx);
+
+    eprintln!("正在发送语音合成请求到: {}", api_url);
+    let response = retry::send_with_retry(
+        &retry.unwrap_or_default(),
+        false,
+        || {
+            Ok(client
+                .post(&api_url)
+                .headers(headers.clone())
+                .json(&request_body)
+                .timeout(Duration::from_secs(120)))
+        },
+        |event| emit_retry(&window, Some(&request_id), event),
+        Some(&mut cancel_rx),
+    )
+    .await;
+
+    let response = match response {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            CANCEL_CHANNELS.lock().unwrap().remove(&request_id);
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            eprintln!(
+                "语音合成请求失败，状态码: {}, 错误信息: {}",
+                status, error_text
+            );
+            let message = format!("请求失败: {} - {}", status, error_text);
+            let _ = window.emit(&error_channel, &message);
+            return Err(message);
+        }
+        Err(e) => {
+            CANCEL_CHANNELS.lock().unwrap().remove(&request_id);
+            eprintln!("语音合成请求发送失败: {}", e);
+            let _ = window.emit(&error_channel, &e);
+            return Err(e);
+        }
+    };
+
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    // 需要保存时边接收边写入文件
+    let (path, mut file) = if save.unwrap_or(false) {
+        let created = async {
+            let path = audio_file_path(file_name.as_deref(), &request_id, content_type.as_deref())?;
+            let file = tokio::fs::File::create(&path)
+                .await
+                .map_err(|e| format!("创建音频文件失败: {}", e))?;
+            Ok::<_, String>((path, file))
+        }
+        .await;
+        match created {
+            Ok((path, file)) => (Some(path), Some(file)),
+            Err(e) => {
+                CANCEL_CHANNELS.lock().unwrap().remove(&request_id);
+                let _ = window.emit(&error_channel, &e);
+                return Err(e);
+            }
+        }
+    } else {
+        (None, None)
+    };
+
+    let mut stream = response.bytes_stream();
+    let mut index = 0;
+    let mut total_bytes = 0;
+    let mut cancelled = false;
+    let mut error = None;
+
+    loop {
+        let chunk = tokio::select! {
+            chunk = stream.next() => chunk,
+            _ = &mut cancel_rx => {
+                cancelled = true;
+                None
+            }
+        };
+        let chunk = match chunk {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(e)) => {
+                error = Some(e.to_string());
+                break;
+            }
+            None => break,
+        };
+
+        if let Some(file) = file.as_mut() {
+            if let Err(e) = file.write_all(&chunk).await {
+                error = Some(format!("写入音频文件失败: {}", e));
+                break;
+            }
+        }
+
+        let payload = AudioChunk {
+            index,
+            data: base64::engine::general_purpose::STANDARD.encode(&chunk),
+        };
+        if let Err(e) = window.emit(&format!("audio-stream-{}", request_id), payload) {
+            eprintln!("Failed to emit audio event: {}", e);
+        }
+        index += 1;
+        total_bytes += chunk.len() as u64;
+    }
+
+    // 清理取消通道
+    CANCEL_CHANNELS.lock().unwrap().remove(&request_id);
+
+    if let Some(file) = file.as_mut() {
+        let _ = file.flush().await;
+    }
+
+    if let Some(e) = error {
+        let _ = window.emit(&error_channel, &e);
+        // 不完整的音频文件没有意义，直接删除
+        if let Some(path) = &path {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        return Err(e);
+    }
+
+    // 被取消时同样删除不完整的文件
+    let path = match path {
+        Some(path) if cancelled => {
+            drop(file);
+            let _ = tokio::fs::remove_file(&path).await;
+            None
+        }
+        path => path,
+    };
+
+    let result = AudioStreamResult {
+        content_type,
+        total_bytes,
+        path: path.map(|p| p.to_string_lossy().to_string()),
+        cancelled,
+    };
+    if let Err(e) = window.emit(&format!("audio-stream-done-{}", request_id), &result) {
+        eprintln!("Failed to emit audio done event: {}", e);
+    }
+    Ok(result)
+}
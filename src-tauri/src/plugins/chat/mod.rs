@@ -1,13 +1,22 @@
+pub mod audio;
+pub mod provider;
+pub mod retry;
+pub mod sse;
+
 use base64::Engine;
 use futures_util::StreamExt;
 use once_cell::sync::Lazy;
-use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::header::HeaderValue;
 use std::collections::HashMap;
 use std::sync::Mutex;
 use std::time::Duration;
 use tauri::{Emitter, Runtime};
 use tokio::sync::oneshot;
 
+use provider::{ChatProvider, ProviderKind, StreamFormat};
+use retry::{RetryEvent, RetryPolicy};
+use sse::{ChatStreamEvent, NdjsonDecoder, SseDecoder};
+
 static CANCEL_CHANNELS: Lazy<Mutex<HashMap<String, oneshot::Sender<()>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
@@ -28,37 +37,52 @@ pub async fn chat_stream<R: Runtime>(
     api_key: String,
     request_id: String,
     request_body: serde_json::Value,
+    provider: Option<ProviderKind>,
+    retry: Option<RetryPolicy>,
 ) -> Result<(), String> {
     // 使用全局客户端而不是每次创建新的
     let client = &*HTTP_CLIENT;
+    let provider = provider::provider_for(provider.unwrap_or_default());
+    let policy = retry.unwrap_or_default();
 
-    // 构建请求头
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", api_key)).map_err(|e| e.to_string())?,
-    );
-
-    // 预先构建请求，可以提前开始DNS解析和连接建立
-    let request_builder = client
-        .post(&api_url)
-        .headers(headers)
-        .json(&request_body)
-        .timeout(Duration::from_secs(60)); // 为这个特定请求设置更长的超时
+    // 存储取消通道，重试等待期间也可以取消
+    let (cancel_tx, mut cancel_rx) = oneshot::channel();
+    CANCEL_CHANNELS
+        .lock()
+        .unwrap()
+        .insert(request_id.clone(), cancel_tx);
 
     println!("正在发送请求到: {}", api_url);
-    let response = request_builder.send().await.map_err(|e| {
+    let response = retry::send_with_retry(
+        &policy,
+        false,
+        || {
+            Ok(provider::build_request(
+                client,
+                provider.as_ref(),
+                &api_url,
+                &api_key,
+                &request_body,
+                true,
+            )?
+            .timeout(Duration::from_secs(60))) // 为这个特定请求设置更长的超时
+        },
+        |event| emit_retry(&window, Some(&request_id), event),
+        Some(&mut cancel_rx),
+    )
+    .await
+    .map_err(|e| {
         println!("请求发送失败: {}", e);
-        window
-            .clone()
-            .emit(&format!("chat-stream-error-{}", request_id), e.to_string())
-            .unwrap();
-        e.to_string()
+        CANCEL_CHANNELS.lock().unwrap().remove(&request_id);
+        if let Err(emit_err) = window.emit(&format!("chat-stream-error-{}", request_id), &e) {
+            eprintln!("Failed to emit error event: {}", emit_err);
+        }
+        e
     })?;
 
     // 检查响应状态
     if !response.status().is_success() {
+        CANCEL_CHANNELS.lock().unwrap().remove(&request_id);
         let status = response.status();
         let error_text = response.text().await.map_err(|e| e.to_string())?;
         println!("请求失败，状态码: {}, 错误信息: {}", status, error_text);
@@ -66,16 +90,10 @@ pub async fn chat_stream<R: Runtime>(
     }
 
     let mut stream = response.bytes_stream();
-    let (cancel_tx, mut cancel_rx) = oneshot::channel();
-
-    // 存储取消通道
-    CANCEL_CHANNELS
-        .lock()
-        .unwrap()
-        .insert(request_id.clone(), cancel_tx);
 
-    // 使用缓冲处理以提高性能
-    let mut buffer = Vec::new();
+    // 流解码器，负责拆分事件并保证多字节字符不被截断
+    let mut decoder = StreamDecoder::new(provider.stream_format());
+    let mut done = false;
 
     // 直接处理流数据
     while let Some(chunk_result) = tokio::select! {
@@ -84,27 +102,13 @@ pub async fn chat_stream<R: Runtime>(
     } {
         match chunk_result {
             Ok(chunk) => {
-                // 积累数据到缓冲区
-                buffer.extend_from_slice(&chunk);
-
-                // 找到所有完整的行
-                let mut processed = 0;
-                let text = String::from_utf8_lossy(&buffer);
-                let lines: Vec<&str> = text.split('\n').collect();
-
-                // 处理所有完整的行，除了最后一行（可能不完整）
-                for line in lines.iter().take(lines.len() - 1) {
-                    if !line.is_empty() {
-                        if let Err(e) = window.emit(&format!("chat-stream-{}", request_id), line) {
-                            eprintln!("Failed to emit event: {}", e);
-                        }
+                for data in decoder.feed(&chunk) {
+                    if emit_stream_data(&window, &request_id, provider.as_ref(), &data) {
+                        done = true;
                     }
-                    processed += line.len() + 1; // +1 for the newline
                 }
-
-                // 保留未处理的数据
-                if processed > 0 {
-                    buffer.drain(0..processed);
+                if done {
+                    break;
                 }
             }
             Err(e) => {
@@ -118,14 +122,14 @@ pub async fn chat_stream<R: Runtime>(
         }
     }
 
-    // 处理缓冲区中的最后一行（如果有）
-    if !buffer.is_empty() {
-        let last_line = String::from_utf8_lossy(&buffer);
-        if !last_line.is_empty() {
-            if let Err(e) = window.emit(&format!("chat-stream-{}", request_id), last_line.as_ref())
-            {
-                eprintln!("Failed to emit event: {}", e);
-            }
+    // 处理没有以空行结尾的最后一个事件
+    if !done {
+        let finished = decoder
+            .finish()
+            .map(|data| emit_stream_data(&window, &request_id, provider.as_ref(), &data))
+            .unwrap_or(false);
+        if !finished {
+            emit_delta(&window, &request_id, &ChatStreamEvent::Done);
         }
     }
 
@@ -134,6 +138,76 @@ pub async fn chat_stream<R: Runtime>(
     Ok(())
 }
 
+/// 按服务商的分帧格式解码流
+enum StreamDecoder {
+    Sse(SseDecoder),
+    Ndjson(NdjsonDecoder),
+}
+
+impl StreamDecoder {
+    fn new(format: StreamFormat) -> Self {
+        match format {
+            StreamFormat::Sse => StreamDecoder::Sse(SseDecoder::new()),
+            StreamFormat::Ndjson => StreamDecoder::Ndjson(NdjsonDecoder::new()),
+        }
+    }
+
+    /// 输入一段字节，返回其中完整的数据
+    fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        match self {
+            StreamDecoder::Sse(decoder) => decoder
+                .feed(chunk)
+                .into_iter()
+                .map(|event| event.data)
+                .collect(),
+            StreamDecoder::Ndjson(decoder) => decoder.feed(chunk),
+        }
+    }
+
+    /// 流结束时取出剩余的数据
+    fn finish(&mut self) -> Option<String> {
+        match self {
+            StreamDecoder::Sse(decoder) => decoder.finish().map(|event| event.data),
+            StreamDecoder::Ndjson(decoder) => decoder.finish(),
+        }
+    }
+}
+
+/// 解析一条流数据并发送到 `chat-delta-{id}`，返回流是否已结束
+fn emit_stream_data<R: Runtime>(
+    window: &tauri::Window<R>,
+    request_id: &str,
+    provider: &dyn ChatProvider,
+    data: &str,
+) -> bool {
+    let mut done = false;
+    for delta in provider.parse_stream(data) {
+        done |= delta == ChatStreamEvent::Done;
+        emit_delta(window, request_id, &delta);
+    }
+    done
+}
+
+/// 发送重试事件
+///
+/// 有请求 ID 时发送到 `request-retry-{id}`，否则发送到 `request-retry`。
+fn emit_retry<R: Runtime>(window: &tauri::Window<R>, request_id: Option<&str>, event: &RetryEvent) {
+    let channel = match request_id {
+        Some(id) => format!("request-retry-{}", id),
+        None => "request-retry".to_string(),
+    };
+    if let Err(e) = window.emit(&channel, event) {
+        eprintln!("Failed to emit retry event: {}", e);
+    }
+}
+
+/// 发送结构化流事件
+fn emit_delta<R: Runtime>(window: &tauri::Window<R>, request_id: &str, event: &ChatStreamEvent) {
+    if let Err(e) = window.emit(&format!("chat-delta-{}", request_id), event) {
+        eprintln!("Failed to emit delta event: {}", e);
+    }
+}
+
 #[tauri::command]
 pub async fn cancel_stream(request_id: String) -> Result<(), String> {
     if let Some(cancel_tx) = CANCEL_CHANNELS.lock().unwrap().remove(&request_id) {
@@ -143,33 +217,39 @@ pub async fn cancel_stream(request_id: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn image_generate(
+pub async fn image_generate<R: Runtime>(
+    window: tauri::Window<R>,
     api_url: String,
     api_key: String,
     request_body: serde_json::Value,
+    provider: Option<ProviderKind>,
+    request_id: Option<String>,
+    retry: Option<RetryPolicy>,
 ) -> Result<serde_json::Value, String> {
     // 使用全局客户端
     let client = &*HTTP_CLIENT;
 
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", api_key)).map_err(|e| e.to_string())?,
-    );
+    let mut headers = provider::provider_for(provider.unwrap_or_default()).headers(&api_key)?;
     headers.insert("X-DashScope-Async", HeaderValue::from_static("enable"));
 
     println!("正在发送图像生成请求到: {}", api_url);
-    let response = client
-        .post(&api_url)
-        .headers(headers)
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| {
-            println!("图像生成请求发送失败: {}", e);
-            e.to_string()
-        })?;
+    let response = retry::send_with_retry(
+        &retry.unwrap_or_default(),
+        false,
+        || {
+            Ok(client
+                .post(&api_url)
+                .headers(headers.clone())
+                .json(&request_body))
+        },
+        |event| emit_retry(&window, request_id.as_deref(), event),
+        None,
+    )
+    .await
+    .map_err(|e| {
+        println!("图像生成请求发送失败: {}", e);
+        e
+    })?;
 
     if !response.status().is_success() {
         let status = response.status();
@@ -186,27 +266,32 @@ pub async fn image_generate(
 }
 
 #[tauri::command]
-pub async fn image_result(api_url: String, api_key: String) -> Result<serde_json::Value, String> {
+pub async fn image_result<R: Runtime>(
+    window: tauri::Window<R>,
+    api_url: String,
+    api_key: String,
+    provider: Option<ProviderKind>,
+    request_id: Option<String>,
+    retry: Option<RetryPolicy>,
+) -> Result<serde_json::Value, String> {
     // 使用全局客户端
     let client = &*HTTP_CLIENT;
 
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", api_key)).map_err(|e| e.to_string())?,
-    );
+    let headers = provider::provider_for(provider.unwrap_or_default()).headers(&api_key)?;
 
     println!("正在获取图像生成结果: {}", api_url);
-    let response = client
-        .get(&api_url)
-        .headers(headers)
-        .send()
-        .await
-        .map_err(|e| {
-            println!("获取图像生成结果失败: {}", e);
-            e.to_string()
-        })?;
+    let response = retry::send_with_retry(
+        &retry.unwrap_or_default(),
+        true,
+        || Ok(client.get(&api_url).headers(headers.clone())),
+        |event| emit_retry(&window, request_id.as_deref(), event),
+        None,
+    )
+    .await
+    .map_err(|e| {
+        println!("获取图像生成结果失败: {}", e);
+        e
+    })?;
 
     if !response.status().is_success() {
         let status = response.status();
@@ -266,33 +351,41 @@ pub async fn image_result(api_url: String, api_key: String) -> Result<serde_json
 }
 
 #[tauri::command]
-pub async fn chat_json(
+pub async fn chat_json<R: Runtime>(
+    window: tauri::Window<R>,
     api_url: String,
     api_key: String,
     request_body: serde_json::Value,
+    provider: Option<ProviderKind>,
+    request_id: Option<String>,
+    retry: Option<RetryPolicy>,
 ) -> Result<String, String> {
     // 使用全局客户端
     let client = &*HTTP_CLIENT;
-
-    // 构建请求头
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", api_key)).map_err(|e| e.to_string())?,
-    );
-
-    // 构建请求
-    let request_builder = client
-        .post(&api_url)
-        .headers(headers)
-        .json(&request_body)
-        .timeout(Duration::from_secs(60));
+    let provider = provider::provider_for(provider.unwrap_or_default());
 
     println!("[chat_json] 正在发送请求到: {}", api_url);
-    let response = request_builder.send().await.map_err(|e| {
+    let response = retry::send_with_retry(
+        &retry.unwrap_or_default(),
+        false,
+        || {
+            Ok(provider::build_request(
+                client,
+                provider.as_ref(),
+                &api_url,
+                &api_key,
+                &request_body,
+                false,
+            )?
+            .timeout(Duration::from_secs(60)))
+        },
+        |event| emit_retry(&window, request_id.as_deref(), event),
+        None,
+    )
+    .await
+    .map_err(|e| {
         println!("[chat_json] 请求发送失败: {}", e);
-        e.to_string()
+        e
     })?;
 
     // 检查响应状态
@@ -306,7 +399,7 @@ pub async fn chat_json(
         return Err(format!("请求失败: {} - {}", status, error_text));
     }
 
-    // 直接返回完整响应体
-    let response_text = response.text().await.map_err(|e| e.to_string())?;
-    Ok(response_text)
+    // 转换为 OpenAI 兼容的响应体
+    let response_json: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    Ok(provider.parse_response(response_json).to_string())
 }
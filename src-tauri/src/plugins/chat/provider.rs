@@ -0,0 +1,1167 @@
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::sse::{self, ChatStreamEvent};
+
+/// Anthropic API 版本
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Anthropic 要求必须指定 max_tokens，未指定时使用该默认值
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u64 = 4096;
+
+/// 模型服务商类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// OpenAI 兼容接口（默认）
+    #[default]
+    OpenAI,
+    /// Anthropic Messages API
+    Anthropic,
+    /// Google Gemini API
+    Gemini,
+    /// 本地 Ollama
+    Ollama,
+}
+
+/// 流式响应的分帧格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// Server-Sent Events
+    Sse,
+    /// 每行一个 JSON
+    Ndjson,
+}
+
+/// 模型服务商适配器
+///
+/// 统一的请求格式为 OpenAI Chat Completions 请求体，
+/// 统一的响应格式为 OpenAI 兼容的 chat.completion 和 [`ChatStreamEvent`]。
+pub trait ChatProvider: Send + Sync {
+    /// 流式响应的分帧格式
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Sse
+    }
+
+    /// 构建鉴权及协议相关的请求头
+    fn headers(&self, api_key: &str) -> Result<HeaderMap, String>;
+
+    /// 计算实际请求地址
+    fn endpoint(&self, api_url: &str, _body: &Value, _stream: bool) -> String {
+        api_url.to_string()
+    }
+
+    /// 将统一请求体转换为服务商请求体
+    fn request_body(&self, body: &Value, stream: bool) -> Value;
+
+    /// 将一条流数据解析为统一事件
+    fn parse_stream(&self, data: &str) -> Vec<ChatStreamEvent>;
+
+    /// 将非流式响应转换为 OpenAI 兼容的响应
+    fn parse_response(&self, value: Value) -> Value;
+}
+
+/// 根据服务商类型获取适配器
+pub fn provider_for(kind: ProviderKind) -> Box<dyn ChatProvider> {
+    match kind {
+        ProviderKind::OpenAI => Box::new(OpenAIProvider),
+        ProviderKind::Anthropic => Box::new(AnthropicProvider),
+        ProviderKind::Gemini => Box::new(GeminiProvider::default()),
+        ProviderKind::Ollama => Box::new(OllamaProvider::default()),
+    }
+}
+
+/// 使用适配器构建请求
+pub fn build_request(
+    client: &reqwest::Client,
+    provider: &dyn ChatProvider,
+    api_url: &str,
+    api_key: &str,
+    body: &Value,
+    stream: bool,
+) -> Result<reqwest::RequestBuilder, String> {
+    Ok(client
+        .post(provider.endpoint(api_url, body, stream))
+        .headers(provider.headers(api_key)?)
+        .json(&provider.request_body(body, stream)))
+}
+
+/// Bearer 鉴权请求头
+fn bearer_headers(api_key: &str) -> Result<HeaderMap, String> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", api_key)).map_err(|e| e.to_string())?,
+    );
+    Ok(headers)
+}
+
+/// 工具调用
+struct ToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// 组装 OpenAI 兼容的 chat.completion 响应
+fn completion_response(
+    model: Option<&Value>,
+    content: String,
+    reasoning: String,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<String>,
+    usage: Option<(u64, u64)>,
+) -> Value {
+    let mut message = Map::new();
+    message.insert("role".to_string(), json!("assistant"));
+    message.insert("content".to_string(), json!(content));
+    if !reasoning.is_empty() {
+        message.insert("reasoning_content".to_string(), json!(reasoning));
+    }
+    if !tool_calls.is_empty() {
+        let calls: Vec<Value> = tool_calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| {
+                json!({
+                    "index": index,
+                    "id": call.id,
+                    "type": "function",
+                    "function": { "name": call.name, "arguments": call.arguments },
+                })
+            })
+            .collect();
+        message.insert("tool_calls".to_string(), Value::Array(calls));
+    }
+
+    let mut response = json!({
+        "object": "chat.completion",
+        "model": model.cloned().unwrap_or(Value::Null),
+        "choices": [{
+            "index": 0,
+            "message": message,
+            "finish_reason": finish_reason,
+        }],
+    });
+    if let Some((prompt_tokens, completion_tokens)) = usage {
+        response["usage"] = json!({
+            "prompt_tokens": prompt_tokens,
+            "completion_tokens": completion_tokens,
+            "total_tokens": prompt_tokens + completion_tokens,
+        });
+    }
+    response
+}
+
+/// 生成工具调用 id（部分服务商不返回 id）
+fn new_call_id() -> String {
+    format!("call_{}", uuid::Uuid::new_v4().simple())
+}
+
+/// 提取 OpenAI 消息中的纯文本内容
+fn message_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// 将 `data:` URL 拆分为 (mime, base64 数据)
+fn split_data_url(url: &str) -> Option<(&str, &str)> {
+    let rest = url.strip_prefix("data:")?;
+    let (meta, data) = rest.split_once(',')?;
+    let mime = meta.strip_suffix(";base64")?;
+    Some((mime, data))
+}
+
+/// 解析工具调用参数字符串，失败时返回空对象
+fn parse_arguments(arguments: Option<&Value>) -> Value {
+    match arguments {
+        Some(Value::String(text)) => {
+            serde_json::from_str(text).unwrap_or_else(|_| Value::Object(Map::new()))
+        }
+        Some(value @ Value::Object(_)) => value.clone(),
+        _ => Value::Object(Map::new()),
+    }
+}
+
+/// OpenAI 兼容接口
+pub struct OpenAIProvider;
+
+impl ChatProvider for OpenAIProvider {
+    fn headers(&self, api_key: &str) -> Result<HeaderMap, String> {
+        bearer_headers(api_key)
+    }
+
+    fn request_body(&self, body: &Value, _stream: bool) -> Value {
+        body.clone()
+    }
+
+    fn parse_stream(&self, data: &str) -> Vec<ChatStreamEvent> {
+        if sse::is_done(data) {
+            return vec![ChatStreamEvent::Done];
+        }
+        sse::parse_openai_chunk(data)
+    }
+
+    fn parse_response(&self, value: Value) -> Value {
+        value
+    }
+}
+
+/// Anthropic Messages API
+pub struct AnthropicProvider;
+
+impl AnthropicProvider {
+    /// 将 OpenAI 消息内容转换为 Anthropic 内容块
+    fn content_blocks(content: Option<&Value>) -> Vec<Value> {
+        match content {
+            Some(Value::String(text)) if !text.is_empty() => {
+                vec![json!({ "type": "text", "text": text })]
+            }
+            Some(Value::Array(parts)) => parts
+                .iter()
+                .filter_map(|part| match part.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => Some(json!({ "type": "text", "text": part["text"] })),
+                    Some("image_url") => {
+                        let url = part
+                            .get("image_url")
+                            .and_then(|i| i.get("url"))
+                            .and_then(|u| u.as_str())?;
+                        let source = match split_data_url(url) {
+                            Some((mime, data)) => {
+                                json!({ "type": "base64", "media_type": mime, "data": data })
+                            }
+                            None => json!({ "type": "url", "url": url }),
+                        };
+                        Some(json!({ "type": "image", "source": source }))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl ChatProvider for AnthropicProvider {
+    fn headers(&self, api_key: &str) -> Result<HeaderMap, String> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(
+            "x-api-key",
+            HeaderValue::from_str(api_key).map_err(|e| e.to_string())?,
+        );
+        headers.insert(
+            "anthropic-version",
+            HeaderValue::from_static(ANTHROPIC_VERSION),
+        );
+        Ok(headers)
+    }
+
+    fn request_body(&self, body: &Value, stream: bool) -> Value {
+        let mut system = Vec::new();
+        let mut messages: Vec<Value> = Vec::new();
+
+        for message in body["messages"].as_array().into_iter().flatten() {
+            match message["role"].as_str().unwrap_or("user") {
+                "system" => system.push(message_text(message.get("content"))),
+                "assistant" => {
+                    let mut blocks = Self::content_blocks(message.get("content"));
+                    for call in message["tool_calls"].as_array().into_iter().flatten() {
+                        blocks.push(json!({
+                            "type": "tool_use",
+                            "id": call["id"],
+                            "name": call["function"]["name"],
+                            "input": parse_arguments(call["function"].get("arguments")),
+                        }));
+                    }
+                    messages.push(json!({ "role": "assistant", "content": blocks }));
+                }
+                "tool" => {
+                    let result = json!({
+                        "type": "tool_result",
+                        "tool_use_id": message["tool_call_id"],
+                        "content": message_text(message.get("content")),
+                    });
+                    // 连续的工具结果需要合并到同一条 user 消息中
+                    let merged = messages.last_mut().and_then(|last| {
+                        let is_tool_result =
+                            last["role"] == "user" && last["content"][0]["type"] == "tool_result";
+                        if is_tool_result {
+                            last["content"].as_array_mut()
+                        } else {
+                            None
+                        }
+                    });
+                    match merged {
+                        Some(blocks) => blocks.push(result),
+                        None => messages.push(json!({ "role": "user", "content": [result] })),
+                    }
+                }
+                _ => {
+                    messages.push(json!({
+                        "role": "user",
+                        "content": Self::content_blocks(message.get("content")),
+                    }));
+                }
+            }
+        }
+
+        let mut request = json!({
+            "model": body["model"],
+            "messages": messages,
+            "max_tokens": body
+                .get("max_tokens")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(ANTHROPIC_DEFAULT_MAX_TOKENS),
+            "stream": stream,
+        });
+        if !system.is_empty() {
+            request["system"] = json!(system.join("\n\n"));
+        }
+        if let Some(temperature) = body.get("temperature").filter(|v| !v.is_null()) {
+            request["temperature"] = temperature.clone();
+        }
+        if let Some(tools) = body["tools"].as_array().filter(|t| !t.is_empty()) {
+            let tools: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool["function"]["name"],
+                        "description": tool["function"]["description"],
+                        "input_schema": tool["function"]
+                            .get("parameters")
+                            .cloned()
+                            .unwrap_or_else(|| json!({ "type": "object" })),
+                    })
+                })
+                .collect();
+            request["tools"] = Value::Array(tools);
+        }
+        request
+    }
+
+    fn parse_stream(&self, data: &str) -> Vec<ChatStreamEvent> {
+        let value: Value = match serde_json::from_str(data) {
+            Ok(value) => value,
+            Err(_) => return Vec::new(),
+        };
+        let index = value["index"].as_u64().unwrap_or(0);
+
+        match value["type"].as_str().unwrap_or("") {
+            "message_start" => {
+                let usage = &value["message"]["usage"];
+                let prompt_tokens = usage["input_tokens"].as_u64().unwrap_or(0);
+                vec![ChatStreamEvent::Usage {
+                    prompt_tokens,
+                    completion_tokens: 0,
+                    total_tokens: prompt_tokens,
+                }]
+            }
+            "content_block_start" => {
+                let block = &value["content_block"];
+                match block["type"].as_str() {
+                    Some("tool_use") => vec![ChatStreamEvent::ToolCall {
+                        index,
+                        id: block["id"].as_str().map(|s| s.to_string()),
+                        name: block["name"].as_str().map(|s| s.to_string()),
+                        arguments: String::new(),
+                    }],
+                    _ => Vec::new(),
+                }
+            }
+            "content_block_delta" => {
+                let delta = &value["delta"];
+                match delta["type"].as_str() {
+                    Some("text_delta") => vec![ChatStreamEvent::Content {
+                        content: delta["text"].as_str().unwrap_or("").to_string(),
+                    }],
+                    Some("thinking_delta") => vec![ChatStreamEvent::Reasoning {
+                        content: delta["thinking"].as_str().unwrap_or("").to_string(),
+                    }],
+                    Some("input_json_delta") => vec![ChatStreamEvent::ToolCall {
+                        index,
+                        id: None,
+                        name: None,
+                        arguments: delta["partial_json"].as_str().unwrap_or("").to_string(),
+                    }],
+                    _ => Vec::new(),
+                }
+            }
+            "message_delta" => {
+                let mut events = Vec::new();
+                if let Some(reason) = value["delta"]["stop_reason"].as_str() {
+                    events.push(ChatStreamEvent::Finish {
+                        reason: reason.to_string(),
+                    });
+                }
+                if let Some(output_tokens) = value["usage"]["output_tokens"].as_u64() {
+                    events.push(ChatStreamEvent::Usage {
+                        prompt_tokens: 0,
+                        completion_tokens: output_tokens,
+                        total_tokens: output_tokens,
+                    });
+                }
+                events
+            }
+            "message_stop" => vec![ChatStreamEvent::Done],
+            _ => Vec::new(),
+        }
+    }
+
+    fn parse_response(&self, value: Value) -> Value {
+        let mut content = String::new();
+        let mut reasoning = String::new();
+        let mut tool_calls = Vec::new();
+
+        for block in value["content"].as_array().into_iter().flatten() {
+            match block["type"].as_str() {
+                Some("text") => content.push_str(block["text"].as_str().unwrap_or("")),
+                Some("thinking") => reasoning.push_str(block["thinking"].as_str().unwrap_or("")),
+                Some("tool_use") => tool_calls.push(ToolCall {
+                    id: block["id"]
+                        .as_str()
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(new_call_id),
+                    name: block["name"].as_str().unwrap_or("").to_string(),
+                    arguments: block["input"].to_string(),
+                }),
+                _ => {}
+            }
+        }
+
+        let usage = value.get("usage").map(|usage| {
+            (
+                usage["input_tokens"].as_u64().unwrap_or(0),
+                usage["output_tokens"].as_u64().unwrap_or(0),
+            )
+        });
+        completion_response(
+            value.get("model"),
+            content,
+            reasoning,
+            tool_calls,
+            value["stop_reason"].as_str().map(|s| s.to_string()),
+            usage,
+        )
+    }
+}
+
+/// Google Gemini API
+///
+/// 每个流式响应使用一个新的实例，函数调用的序号在整个流中递增。
+#[derive(Default)]
+pub struct GeminiProvider {
+    /// 已解析出的函数调用数，作为下一个调用的 index
+    tool_calls: AtomicU64,
+}
+
+impl GeminiProvider {
+    /// 将 Gemini 的结束原因映射为 OpenAI 的结束原因
+    fn finish_reason(reason: &str) -> String {
+        match reason {
+            "STOP" => "stop".to_string(),
+            "MAX_TOKENS" => "length".to_string(),
+            "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" => {
+                "content_filter".to_string()
+            }
+            other => other.to_lowercase(),
+        }
+    }
+
+    /// 将 OpenAI 消息内容转换为 Gemini parts
+    fn parts(content: Option<&Value>) -> Vec<Value> {
+        match content {
+            Some(Value::String(text)) if !text.is_empty() => vec![json!({ "text": text })],
+            Some(Value::Array(parts)) => parts
+                .iter()
+                .filter_map(|part| match part.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => Some(json!({ "text": part["text"] })),
+                    Some("image_url") => {
+                        let url = part
+                            .get("image_url")
+                            .and_then(|i| i.get("url"))
+                            .and_then(|u| u.as_str())?;
+                        match split_data_url(url) {
+                            Some((mime, data)) => {
+                                Some(json!({ "inlineData": { "mimeType": mime, "data": data } }))
+                            }
+                            None => Some(json!({ "fileData": { "fileUri": url } })),
+                        }
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl ChatProvider for GeminiProvider {
+    fn headers(&self, api_key: &str) -> Result<HeaderMap, String> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(
+            "x-goog-api-key",
+            HeaderValue::from_str(api_key).map_err(|e| e.to_string())?,
+        );
+        Ok(headers)
+    }
+
+    fn endpoint(&self, api_url: &str, body: &Value, stream: bool) -> String {
+        let method = if stream {
+            "streamGenerateContent"
+        } else {
+            "generateContent"
+        };
+
+        // 已经是完整的方法地址时，只根据是否流式切换方法
+        let url =
+            if api_url.contains(":generateContent") || api_url.contains(":streamGenerateContent") {
+                api_url
+                    .replace(":streamGenerateContent", ":generateContent")
+                    .replace(":generateContent", &format!(":{}", method))
+            } else {
+                format!(
+                    "{}/models/{}:{}",
+                    api_url.trim_end_matches('/'),
+                    body["model"].as_str().unwrap_or(""),
+                    method
+                )
+            };
+
+        if stream && !url.contains("alt=sse") {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            format!("{}{}alt=sse", url, separator)
+        } else {
+            url
+        }
+    }
+
+    fn request_body(&self, body: &Value, _stream: bool) -> Value {
+        let mut system = Vec::new();
+        let mut contents: Vec<Value> = Vec::new();
+        // 工具结果只携带 tool_call_id，需要从之前的调用中找回函数名
+        let mut call_names: HashMap<String, String> = HashMap::new();
+
+        for message in body["messages"].as_array().into_iter().flatten() {
+            match message["role"].as_str().unwrap_or("user") {
+                "system" => system.push(json!({ "text": message_text(message.get("content")) })),
+                "assistant" => {
+                    let mut parts = Self::parts(message.get("content"));
+                    for call in message["tool_calls"].as_array().into_iter().flatten() {
+                        let name = call["function"]["name"].as_str().unwrap_or("").to_string();
+                        if let Some(id) = call["id"].as_str() {
+                            call_names.insert(id.to_string(), name.clone());
+                        }
+                        parts.push(json!({
+                            "functionCall": {
+                                "name": name,
+                                "args": parse_arguments(call["function"].get("arguments")),
+                            }
+                        }));
+                    }
+                    contents.push(json!({ "role": "model", "parts": parts }));
+                }
+                "tool" => {
+                    let name = message["tool_call_id"]
+                        .as_str()
+                        .and_then(|id| call_names.get(id).cloned())
+                        .or_else(|| message["name"].as_str().map(|s| s.to_string()))
+                        .unwrap_or_default();
+                    let text = message_text(message.get("content"));
+                    let response = serde_json::from_str::<Value>(&text)
+                        .ok()
+                        .filter(|v| v.is_object())
+                        .unwrap_or_else(|| json!({ "content": text }));
+                    contents.push(json!({
+                        "role": "user",
+                        "parts": [{ "functionResponse": { "name": name, "response": response } }],
+                    }));
+                }
+                _ => contents.push(json!({
+                    "role": "user",
+                    "parts": Self::parts(message.get("content")),
+                })),
+            }
+        }
+
+        let mut request = json!({ "contents": contents });
+        if !system.is_empty() {
+            request["systemInstruction"] = json!({ "parts": system });
+        }
+
+        let mut generation_config = Map::new();
+        if let Some(temperature) = body.get("temperature").filter(|v| !v.is_null()) {
+            generation_config.insert("temperature".to_string(), temperature.clone());
+        }
+        if let Some(max_tokens) = body.get("max_tokens").filter(|v| !v.is_null()) {
+            generation_config.insert("maxOutputTokens".to_string(), max_tokens.clone());
+        }
+        if !generation_config.is_empty() {
+            request["generationConfig"] = Value::Object(generation_config);
+        }
+
+        if let Some(tools) = body["tools"].as_array().filter(|t| !t.is_empty()) {
+            let declarations: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    let mut declaration = json!({
+                        "name": tool["function"]["name"],
+                        "description": tool["function"]["description"],
+                    });
+                    if let Some(parameters) = tool["function"].get("parameters") {
+                        declaration["parameters"] = parameters.clone();
+                    }
+                    declaration
+                })
+                .collect();
+            request["tools"] = json!([{ "functionDeclarations": declarations }]);
+        }
+        request
+    }
+
+    fn parse_stream(&self, data: &str) -> Vec<ChatStreamEvent> {
+        let value: Value = match serde_json::from_str(data) {
+            Ok(value) => value,
+            Err(_) => return Vec::new(),
+        };
+        let mut events = Vec::new();
+        let candidate = &value["candidates"][0];
+
+        for part in candidate["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            if let Some(text) = part["text"].as_str() {
+                if part["thought"].as_bool().unwrap_or(false) {
+                    events.push(ChatStreamEvent::Reasoning {
+                        content: text.to_string(),
+                    });
+                } else if !text.is_empty() {
+                    events.push(ChatStreamEvent::Content {
+                        content: text.to_string(),
+                    });
+                }
+            }
+            if let Some(call) = part.get("functionCall") {
+                // Gemini 一次性返回完整的函数调用，不同数据块中的调用也要有不同的 index
+                events.push(ChatStreamEvent::ToolCall {
+                    index: self.tool_calls.fetch_add(1, Ordering::Relaxed),
+                    id: Some(new_call_id()),
+                    name: call["name"].as_str().map(|s| s.to_string()),
+                    arguments: call
+                        .get("args")
+                        .cloned()
+                        .unwrap_or_else(|| json!({}))
+                        .to_string(),
+                });
+            }
+        }
+
+        if let Some(reason) = candidate["finishReason"].as_str() {
+            events.push(ChatStreamEvent::Finish {
+                reason: Self::finish_reason(reason),
+            });
+        }
+        if let Some(usage) = value.get("usageMetadata") {
+            let prompt_tokens = usage["promptTokenCount"].as_u64().unwrap_or(0);
+            let completion_tokens = usage["candidatesTokenCount"].as_u64().unwrap_or(0);
+            events.push(ChatStreamEvent::Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: usage["totalTokenCount"]
+                    .as_u64()
+                    .unwrap_or(prompt_tokens + completion_tokens),
+            });
+        }
+        events
+    }
+
+    fn parse_response(&self, value: Value) -> Value {
+        let mut content = String::new();
+        let mut reasoning = String::new();
+        let mut tool_calls = Vec::new();
+        let candidate = &value["candidates"][0];
+
+        for part in candidate["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            if let Some(text) = part["text"].as_str() {
+                if part["thought"].as_bool().unwrap_or(false) {
+                    reasoning.push_str(text);
+                } else {
+                    content.push_str(text);
+                }
+            }
+            if let Some(call) = part.get("functionCall") {
+                tool_calls.push(ToolCall {
+                    id: new_call_id(),
+                    name: call["name"].as_str().unwrap_or("").to_string(),
+                    arguments: call
+                        .get("args")
+                        .cloned()
+                        .unwrap_or_else(|| json!({}))
+                        .to_string(),
+                });
+            }
+        }
+
+        let usage = value.get("usageMetadata").map(|usage| {
+            (
+                usage["promptTokenCount"].as_u64().unwrap_or(0),
+                usage["candidatesTokenCount"].as_u64().unwrap_or(0),
+            )
+        });
+        completion_response(
+            value.get("modelVersion"),
+            content,
+            reasoning,
+            tool_calls,
+            candidate["finishReason"].as_str().map(Self::finish_reason),
+            usage,
+        )
+    }
+}
+
+/// 本地 Ollama
+///
+/// 每个流式响应使用一个新的实例，工具调用的序号在整个流中递增。
+#[derive(Default)]
+pub struct OllamaProvider {
+    /// 已解析出的工具调用数，作为下一个调用的 index
+    tool_calls: AtomicU64,
+}
+
+impl OllamaProvider {
+    /// 从 Ollama 的 message 中提取工具调用
+    fn tool_calls(message: &Value) -> Vec<ToolCall> {
+        message["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|call| ToolCall {
+                id: call["id"]
+                    .as_str()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(new_call_id),
+                name: call["function"]["name"].as_str().unwrap_or("").to_string(),
+                arguments: match &call["function"]["arguments"] {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                },
+            })
+            .collect()
+    }
+}
+
+impl ChatProvider for OllamaProvider {
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Ndjson
+    }
+
+    fn headers(&self, api_key: &str) -> Result<HeaderMap, String> {
+        // 本地服务无需鉴权，通过反向代理访问时才携带 Bearer
+        if api_key.is_empty() {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            Ok(headers)
+        } else {
+            bearer_headers(api_key)
+        }
+    }
+
+    fn request_body(&self, body: &Value, stream: bool) -> Value {
+        let messages: Vec<Value> = body["messages"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|message| {
+                let mut converted = json!({
+                    "role": message["role"],
+                    "content": message_text(message.get("content")),
+                });
+                // 图片以不带前缀的 base64 传递
+                let images: Vec<&str> = message["content"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|part| part["image_url"]["url"].as_str())
+                    .filter_map(|url| split_data_url(url).map(|(_, data)| data))
+                    .collect();
+                if !images.is_empty() {
+                    converted["images"] = json!(images);
+                }
+                if let Some(calls) = message["tool_calls"].as_array() {
+                    let calls: Vec<Value> = calls
+                        .iter()
+                        .map(|call| {
+                            json!({
+                                "function": {
+                                    "name": call["function"]["name"],
+                                    "arguments": parse_arguments(call["function"].get("arguments")),
+                                }
+                            })
+                        })
+                        .collect();
+                    converted["tool_calls"] = Value::Array(calls);
+                }
+                converted
+            })
+            .collect();
+
+        let mut request = json!({
+            "model": body["model"],
+            "messages": messages,
+            "stream": stream,
+        });
+        if let Some(tools) = body["tools"].as_array().filter(|t| !t.is_empty()) {
+            request["tools"] = Value::Array(tools.clone());
+        }
+        let mut options = Map::new();
+        if let Some(temperature) = body.get("temperature").filter(|v| !v.is_null()) {
+            options.insert("temperature".to_string(), temperature.clone());
+        }
+        if let Some(max_tokens) = body.get("max_tokens").filter(|v| !v.is_null()) {
+            options.insert("num_predict".to_string(), max_tokens.clone());
+        }
+        if !options.is_empty() {
+            request["options"] = Value::Object(options);
+        }
+        request
+    }
+
+    fn parse_stream(&self, data: &str) -> Vec<ChatStreamEvent> {
+        let value: Value = match serde_json::from_str(data) {
+            Ok(value) => value,
+            Err(_) => return Vec::new(),
+        };
+        let mut events = Vec::new();
+        let message = &value["message"];
+
+        if let Some(thinking) = message["thinking"].as_str().filter(|s| !s.is_empty()) {
+            events.push(ChatStreamEvent::Reasoning {
+                content: thinking.to_string(),
+            });
+        }
+        if let Some(content) = message["content"].as_str().filter(|s| !s.is_empty()) {
+            events.push(ChatStreamEvent::Content {
+                content: content.to_string(),
+            });
+        }
+        // 每个数据块中的工具调用都是完整的，不同数据块中的调用也要有不同的 index
+        for call in Self::tool_calls(message) {
+            events.push(ChatStreamEvent::ToolCall {
+                index: self.tool_calls.fetch_add(1, Ordering::Relaxed),
+                id: Some(call.id),
+                name: Some(call.name),
+                arguments: call.arguments,
+            });
+        }
+
+        if value["done"].as_bool().unwrap_or(false) {
+            events.push(ChatStreamEvent::Finish {
+                reason: value["done_reason"].as_str().unwrap_or("stop").to_string(),
+            });
+            let prompt_tokens = value["prompt_eval_count"].as_u64().unwrap_or(0);
+            let completion_tokens = value["eval_count"].as_u64().unwrap_or(0);
+            events.push(ChatStreamEvent::Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            });
+            events.push(ChatStreamEvent::Done);
+        }
+        events
+    }
+
+    fn parse_response(&self, value: Value) -> Value {
+        let message = &value["message"];
+        let usage = value.get("eval_count").map(|_| {
+            (
+                value["prompt_eval_count"].as_u64().unwrap_or(0),
+                value["eval_count"].as_u64().unwrap_or(0),
+            )
+        });
+        completion_response(
+            value.get("model"),
+            message["content"].as_str().unwrap_or("").to_string(),
+            message["thinking"].as_str().unwrap_or("").to_string(),
+            Self::tool_calls(message),
+            value["done_reason"].as_str().map(|s| s.to_string()),
+            usage,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::chat::sse::{NdjsonDecoder, SseDecoder};
+    use futures::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 启动只处理一个请求的本地服务，响应体按 `chunks` 分多次写出
+    ///
+    /// 返回服务地址和收到的请求头。
+    async fn serve(
+        content_type: &'static str,
+        chunks: Vec<&'static str>,
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // 读完请求头和请求体
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nConnection: close\r\n\r\n",
+                content_type
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            for chunk in chunks {
+                socket.write_all(chunk.as_bytes()).await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            socket.shutdown().await.ok();
+            String::from_utf8_lossy(&request).to_string()
+        });
+        (format!("http://{}", addr), handle)
+    }
+
+    /// 通过适配器请求本地服务，按分帧格式解码并解析出全部事件
+    async fn stream_events(
+        provider: &dyn ChatProvider,
+        api_url: &str,
+        body: &Value,
+    ) -> Vec<ChatStreamEvent> {
+        let client = reqwest::Client::new();
+        let response = build_request(&client, provider, api_url, "key", body, true)
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        let mut stream = response.bytes_stream();
+        let mut sse = SseDecoder::new();
+        let mut ndjson = NdjsonDecoder::new();
+        let mut events = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            let data: Vec<String> = match provider.stream_format() {
+                StreamFormat::Sse => sse.feed(&chunk).into_iter().map(|e| e.data).collect(),
+                StreamFormat::Ndjson => ndjson.feed(&chunk),
+            };
+            for data in data {
+                events.extend(provider.parse_stream(&data));
+            }
+        }
+        let rest = match provider.stream_format() {
+            StreamFormat::Sse => sse.finish().map(|e| e.data),
+            StreamFormat::Ndjson => ndjson.finish(),
+        };
+        if let Some(data) = rest {
+            events.extend(provider.parse_stream(&data));
+        }
+        events
+    }
+
+    fn body() -> Value {
+        json!({
+            "model": "test-model",
+            "messages": [{ "role": "user", "content": "你好" }],
+        })
+    }
+
+    fn content(events: &[ChatStreamEvent]) -> String {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ChatStreamEvent::Content { content } => Some(content.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn tool_calls(events: &[ChatStreamEvent]) -> Vec<(u64, Option<String>, String)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ChatStreamEvent::ToolCall {
+                    index,
+                    name,
+                    arguments,
+                    ..
+                } => Some((*index, name.clone(), arguments.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn openai_stream() {
+        // 多字节字符和事件都被拆在不同的 TCP 分段中
+        let (url, server) = serve(
+            "text/event-stream",
+            vec![
+                "data: {\"choices\":[{\"delta\":{\"content\":\"你",
+                "好\"}}]}\n\ndata: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"c1\",\"function\":{\"name\":\"f\",\"arguments\":\"{\\\"a\\\"\"}}]}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\":1}\"}}]},\"finish_reason\":\"tool_calls\"}]}\n\n",
+                "data: [DONE]\n\n",
+            ],
+        )
+        .await;
+        let events = stream_events(&OpenAIProvider, &url, &body()).await;
+        let request = server.await.unwrap();
+
+        assert!(request.to_lowercase().contains("authorization: bearer key"));
+        assert_eq!(content(&events), "你好");
+        assert_eq!(
+            tool_calls(&events),
+            vec![
+                (0, Some("f".to_string()), "{\"a\"".to_string()),
+                (0, None, ":1}".to_string()),
+            ]
+        );
+        assert!(events.contains(&ChatStreamEvent::Finish {
+            reason: "tool_calls".to_string()
+        }));
+        assert_eq!(events.last(), Some(&ChatStreamEvent::Done));
+    }
+
+    #[tokio::test]
+    async fn anthropic_stream() {
+        let (url, server) = serve(
+            "text/event-stream",
+            vec![
+                "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":5}}}\n\n",
+                "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"想\"}}\n\n",
+                "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"text_delta\",\"text\":\"好\"}}\n\n",
+                "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":2,\"content_block\":{\"type\":\"tool_use\",\"id\":\"t1\",\"name\":\"f\"}}\n\n",
+                "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":2,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{}\"}}\n\n",
+                "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":7}}\n\n",
+                "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+            ],
+        )
+        .await;
+        let events = stream_events(&AnthropicProvider, &url, &body()).await;
+        let request = server.await.unwrap().to_lowercase();
+
+        assert!(request.contains("x-api-key: key"));
+        assert!(request.contains(&format!("anthropic-version: {}", ANTHROPIC_VERSION)));
+        assert!(events.contains(&ChatStreamEvent::Reasoning {
+            content: "想".to_string()
+        }));
+        assert_eq!(content(&events), "好");
+        assert_eq!(
+            tool_calls(&events),
+            vec![
+                (2, Some("f".to_string()), String::new()),
+                (2, None, "{}".to_string()),
+            ]
+        );
+        assert_eq!(events.last(), Some(&ChatStreamEvent::Done));
+    }
+
+    #[tokio::test]
+    async fn gemini_stream() {
+        // 两个数据块各带一个完整的函数调用，index 需要在整个流中递增
+        let (url, server) = serve(
+            "text/event-stream",
+            vec![
+                "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"好\"},{\"functionCall\":{\"name\":\"f\",\"args\":{\"a\":1}}}]}}]}\r\n\r\n",
+                "data: {\"candidates\":[{\"content\":{\"parts\":[{\"functionCall\":{\"name\":\"g\",\"args\":{}}}]},\"finishReason\":\"STOP\"}],\"usageMetadata\":{\"promptTokenCount\":3,\"candidatesTokenCount\":4}}\r\n\r\n",
+            ],
+        )
+        .await;
+        let provider = GeminiProvider::default();
+        let events = stream_events(&provider, &url, &body()).await;
+        let request = server.await.unwrap();
+
+        assert!(request.starts_with("POST /models/test-model:streamGenerateContent?alt=sse "));
+        assert_eq!(content(&events), "好");
+        assert_eq!(
+            tool_calls(&events),
+            vec![
+                (0, Some("f".to_string()), "{\"a\":1}".to_string()),
+                (1, Some("g".to_string()), "{}".to_string()),
+            ]
+        );
+        assert!(events.contains(&ChatStreamEvent::Usage {
+            prompt_tokens: 3,
+            completion_tokens: 4,
+            total_tokens: 7,
+        }));
+    }
+
+    #[tokio::test]
+    async fn ollama_stream() {
+        // 最后一行没有换行，由 finish 取出
+        let (url, server) = serve(
+            "application/x-ndjson",
+            vec![
+                "{\"message\":{\"content\":\"你\"},\"done\":false}\n{\"message\":{\"content\":\"",
+                "好\",\"tool_calls\":[{\"function\":{\"name\":\"f\",\"arguments\":{\"a\":1}}}]},\"done\":false}\n",
+                "{\"message\":{\"content\":\"\",\"tool_calls\":[{\"function\":{\"name\":\"g\",\"arguments\":{}}}]},\"done\":false}\n",
+                "{\"message\":{\"content\":\"\"},\"done\":true,\"done_reason\":\"stop\",\"prompt_eval_count\":2,\"eval_count\":3}",
+            ],
+        )
+        .await;
+        let provider = OllamaProvider::default();
+        let events = stream_events(&provider, &url, &body()).await;
+        let request = server.await.unwrap().to_lowercase();
+
+        // 配置了密钥时按反向代理处理，携带 Bearer
+        assert!(request.contains("authorization: bearer key"));
+        assert_eq!(content(&events), "你好");
+        assert_eq!(
+            tool_calls(&events),
+            vec![
+                (0, Some("f".to_string()), "{\"a\":1}".to_string()),
+                (1, Some("g".to_string()), "{}".to_string()),
+            ]
+        );
+        assert!(events.contains(&ChatStreamEvent::Usage {
+            prompt_tokens: 2,
+            completion_tokens: 3,
+            total_tokens: 5,
+        }));
+        assert_eq!(events.last(), Some(&ChatStreamEvent::Done));
+    }
+}
@@ -0,0 +1,182 @@
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Retry-After 允许的最大等待时间，避免服务端返回过大的值导致长时间挂起
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+/// 重试策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（包含第一次请求）
+    pub max_attempts: u32,
+    /// 首次重试前的等待时间（毫秒）
+    pub initial_delay_ms: u64,
+    /// 单次等待时间上限（毫秒）
+    pub max_delay_ms: u64,
+    /// 每次重试等待时间的增长倍数
+    pub multiplier: f64,
+    /// 是否为等待时间加入随机抖动
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `attempt` 次失败后的退避时间（attempt 从 1 开始）
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1) as i32;
+        let delay = (self.initial_delay_ms as f64 * self.multiplier.max(1.0).powi(exponent))
+            .min(self.max_delay_ms as f64);
+        let delay = if self.jitter {
+            // 在 [delay/2, delay] 之间随机，避免多个请求同时重试
+            delay * rand::thread_rng().gen_range(0.5..=1.0)
+        } else {
+            delay
+        };
+        Duration::from_millis(delay as u64)
+    }
+}
+
+/// 发送给前端的重试事件
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryEvent {
+    /// 即将进行的尝试序号（从 2 开始）
+    pub attempt: u32,
+    /// 最大尝试次数
+    pub max_attempts: u32,
+    /// 本次重试前的等待时间（毫秒）
+    pub delay_ms: u64,
+    /// 触发重试的原因
+    pub reason: String,
+}
+
+/// 判断状态码是否值得重试
+///
+/// 幂等的请求在超时、限流和服务端错误时都可以重试。非幂等的请求（如创建生成任务）
+/// 在 5xx 时服务端可能已经处理并计费，只在服务端明确表示没有处理时重试：
+/// 429，或带 Retry-After 的 503。
+fn is_retryable_status(status: StatusCode, has_retry_after: bool, idempotent: bool) -> bool {
+    if !idempotent {
+        return status == StatusCode::TOO_MANY_REQUESTS
+            || (status == StatusCode::SERVICE_UNAVAILABLE && has_retry_after);
+    }
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// 判断请求错误是否值得重试
+///
+/// 连接失败时请求还没有发出，总是可以重试。超时可能发生在请求已经发出、
+/// 服务端正在处理的时候，只对幂等的请求重试。
+fn is_retryable_error(error: &reqwest::Error, idempotent: bool) -> bool {
+    error.is_connect() || (idempotent && error.is_timeout())
+}
+
+/// 解析 Retry-After 头，支持秒数和 HTTP 日期两种格式
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+                .to_std()
+                .unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+/// 按策略发送请求，失败时自动重试
+///
+/// `idempotent` 表示重复发送请求是否安全，流式对话和创建任务等请求应传 `false`，
+/// 此时只重试服务端没有处理的失败。
+/// `build` 每次尝试都会被调用以构建新的请求；`on_retry` 在每次重试前调用；
+/// 传入 `cancel` 时，等待期间收到取消信号会立即返回。
+/// 返回的响应状态码可能仍然不是成功状态（不可重试或重试次数用尽），由调用方处理。
+pub async fn send_with_retry<B, F>(
+    policy: &RetryPolicy,
+    idempotent: bool,
+    build: B,
+    mut on_retry: F,
+    mut cancel: Option<&mut oneshot::Receiver<()>>,
+) -> Result<Response, String>
+where
+    B: Fn() -> Result<RequestBuilder, String>,
+    F: FnMut(&RetryEvent),
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+
+    loop {
+        let result = build()?.send().await;
+
+        let (delay, reason) = match result {
+            Ok(response) => {
+                let status = response.status();
+                let after = retry_after(&response);
+                if status.is_success()
+                    || !is_retryable_status(status, after.is_some(), idempotent)
+                    || attempt >= max_attempts
+                {
+                    return Ok(response);
+                }
+                let delay = after.unwrap_or_else(|| policy.backoff(attempt));
+                (delay, format!("请求失败: {}", status))
+            }
+            Err(e) => {
+                if !is_retryable_error(&e, idempotent) || attempt >= max_attempts {
+                    return Err(e.to_string());
+                }
+                (policy.backoff(attempt), e.to_string())
+            }
+        };
+
+        attempt += 1;
+        println!(
+            "请求失败，{}ms 后进行第 {}/{} 次尝试: {}",
+            delay.as_millis(),
+            attempt,
+            max_attempts,
+            reason
+        );
+        on_retry(&RetryEvent {
+            attempt,
+            max_attempts,
+            delay_ms: delay.as_millis() as u64,
+            reason,
+        });
+
+        match cancel.as_mut() {
+            Some(cancel_rx) => {
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = &mut **cancel_rx => return Err("请求已取消".to_string()),
+                }
+            }
+            None => tokio::time::sleep(delay).await,
+        }
+    }
+}
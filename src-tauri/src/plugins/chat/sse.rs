@@ -0,0 +1,309 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// 一条完整的 SSE 事件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SseEvent {
+    /// `event:` 字段，未指定时为 None（即默认的 message）
+    pub event: Option<String>,
+    /// 所有 `data:` 行按换行拼接后的内容
+    pub data: String,
+    /// `id:` 字段
+    pub id: Option<String>,
+    /// `retry:` 字段（毫秒）
+    pub retry: Option<u64>,
+}
+
+/// SSE 解码器
+///
+/// 按字节缓冲，只在遇到完整的行之后才做 UTF-8 解码，
+/// 因此多字节字符被拆分到两个网络分片时不会出现乱码。
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    /// 尚未构成完整行的字节
+    buffer: Vec<u8>,
+    /// 当前事件累积的 data 行
+    data: Vec<String>,
+    /// 当前事件的类型
+    event: Option<String>,
+    /// 当前事件的 id
+    id: Option<String>,
+    /// 当前事件的重连间隔
+    retry: Option<u64>,
+    /// 上一个字节是否为 `\r`，用于处理跨分片的 `\r\n`
+    pending_cr: bool,
+    /// 是否已经处理过流开头（用于去除 BOM）
+    started: bool,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一段字节，返回其中已经完整的事件
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        let mut bytes = chunk;
+
+        // 上一个分片以 \r 结尾，而这个分片以 \n 开头，说明是同一个 \r\n
+        if self.pending_cr {
+            self.pending_cr = false;
+            if bytes.first() == Some(&b'\n') {
+                bytes = &bytes[1..];
+            }
+        }
+
+        let mut start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\n' | b'\r' => {
+                    self.buffer.extend_from_slice(&bytes[start..i]);
+                    let line = std::mem::take(&mut self.buffer);
+                    if let Some(event) = self.process_line(&line) {
+                        events.push(event);
+                    }
+
+                    if bytes[i] == b'\r' {
+                        if i + 1 < bytes.len() {
+                            if bytes[i + 1] == b'\n' {
+                                i += 1;
+                            }
+                        } else {
+                            self.pending_cr = true;
+                        }
+                    }
+                    start = i + 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        self.buffer.extend_from_slice(&bytes[start..]);
+
+        events
+    }
+
+    /// 流结束时调用，处理没有以空行结尾的最后一个事件
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        if !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            if let Some(event) = self.process_line(&line) {
+                return Some(event);
+            }
+        }
+        self.dispatch()
+    }
+
+    /// 处理单行，遇到空行时派发事件
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        let mut line = line;
+        if !self.started {
+            self.started = true;
+            // 去除 UTF-8 BOM
+            if line.starts_with(&[0xEF, 0xBB, 0xBF]) {
+                line = &line[3..];
+            }
+        }
+
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        // 以冒号开头的是注释行（常用作 keep-alive）
+        if line[0] == b':' {
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        let (field, value) = match line.find(':') {
+            Some(pos) => {
+                let value = &line[pos + 1..];
+                (&line[..pos], value.strip_prefix(' ').unwrap_or(value))
+            }
+            None => (line.as_ref(), ""),
+        };
+
+        match field {
+            "data" => self.data.push(value.to_string()),
+            "event" => self.event = Some(value.to_string()),
+            // 规范要求包含 NULL 的 id 被忽略
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(retry) = value.parse::<u64>() {
+                    self.retry = Some(retry);
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// 将当前累积的字段组装为事件并重置
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        if self.data.is_empty() {
+            // 没有 data 的事件不派发，但 retry 需要保留到下一个事件
+            self.event = None;
+            return None;
+        }
+
+        let event = SseEvent {
+            event: self.event.take(),
+            data: std::mem::take(&mut self.data).join("\n"),
+            id: self.id.clone(),
+            retry: self.retry,
+        };
+        Some(event)
+    }
+}
+
+/// 发送给前端的结构化流事件
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatStreamEvent {
+    /// 正文增量
+    Content { content: String },
+    /// 推理过程增量
+    Reasoning { content: String },
+    /// 工具调用增量
+    ToolCall {
+        index: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        arguments: String,
+    },
+    /// token 用量
+    Usage {
+        prompt_tokens: u64,
+        completion_tokens: u64,
+        total_tokens: u64,
+    },
+    /// 结束原因
+    Finish { reason: String },
+    /// 流结束
+    Done,
+}
+
+/// 判断 data 是否为流结束标记
+pub fn is_done(data: &str) -> bool {
+    data.trim() == "[DONE]"
+}
+
+/// 按 OpenAI 兼容格式解析一条 data，得到结构化事件
+pub fn parse_openai_chunk(data: &str) -> Vec<ChatStreamEvent> {
+    let mut events = Vec::new();
+    let value: Value = match serde_json::from_str(data) {
+        Ok(value) => value,
+        Err(_) => return events,
+    };
+
+    if let Some(choice) = value.get("choices").and_then(|c| c.get(0)) {
+        if let Some(delta) = choice.get("delta") {
+            // 推理内容，不同服务商字段名不同
+            for key in ["reasoning_content", "reasoning"] {
+                if let Some(content) = delta.get(key).and_then(|v| v.as_str()) {
+                    if !content.is_empty() {
+                        events.push(ChatStreamEvent::Reasoning {
+                            content: content.to_string(),
+                        });
+                    }
+                }
+            }
+
+            if let Some(content) = delta.get("content").and_then(|v| v.as_str()) {
+                if !content.is_empty() {
+                    events.push(ChatStreamEvent::Content {
+                        content: content.to_string(),
+                    });
+                }
+            }
+
+            if let Some(tool_calls) = delta.get("tool_calls").and_then(|v| v.as_array()) {
+                for (position, call) in tool_calls.iter().enumerate() {
+                    let function = call.get("function");
+                    events.push(ChatStreamEvent::ToolCall {
+                        index: call
+                            .get("index")
+                            .and_then(|v| v.as_u64())
+                            .unwrap_or(position as u64),
+                        id: call
+                            .get("id")
+                            .and_then(|v| v.as_str())
+                            .filter(|s| !s.is_empty())
+                            .map(|s| s.to_string()),
+                        name: function
+                            .and_then(|f| f.get("name"))
+                            .and_then(|v| v.as_str())
+                            .filter(|s| !s.is_empty())
+                            .map(|s| s.to_string()),
+                        arguments: function
+                            .and_then(|f| f.get("arguments"))
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                    });
+                }
+            }
+        }
+
+        if let Some(reason) = choice.get("finish_reason").and_then(|v| v.as_str()) {
+            events.push(ChatStreamEvent::Finish {
+                reason: reason.to_string(),
+            });
+        }
+    }
+
+    if let Some(usage) = value.get("usage").filter(|u| u.is_object()) {
+        let field = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        events.push(ChatStreamEvent::Usage {
+            prompt_tokens: field("prompt_tokens"),
+            completion_tokens: field("completion_tokens"),
+            total_tokens: field("total_tokens"),
+        });
+    }
+
+    events
+}
+
+/// NDJSON 解码器（如 Ollama 的流式响应），每行一个 JSON
+#[derive(Debug, Default)]
+pub struct NdjsonDecoder {
+    buffer: Vec<u8>,
+}
+
+impl NdjsonDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一段字节，返回其中已经完整的非空行
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if !line.is_empty() {
+                lines.push(line.to_string());
+            }
+        }
+        lines
+    }
+
+    /// 流结束时调用，返回最后一行（如果有）
+    pub fn finish(&mut self) -> Option<String> {
+        let line = std::mem::take(&mut self.buffer);
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim();
+        if line.is_empty() {
+            None
+        } else {
+            Some(line.to_string())
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use once_cell::sync::Lazy;
+use rmcp::model::{
+    ClientCapabilities, ClientInfo, CreateMessageRequestParam, CreateMessageResult, ErrorData,
+    Implementation, ListRootsResult, LoggingMessageNotificationParam, ProgressNotificationParam,
+    ProtocolVersion, RequestId, ResourceUpdatedNotificationParam, RootsCapabilities,
+};
+use rmcp::service::{RequestContext, RoleClient};
+use rmcp::{ClientHandler, Peer};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+
+/// 服务端请求事件名，前端处理后调用 `mcp_client_respond` 回复
+pub const CLIENT_REQUEST_EVENT: &str = "mcp-client-request";
+/// 服务端通知事件名
+pub const NOTIFICATION_EVENT: &str = "mcp-notification";
+/// 工具调用进度事件名
+pub const PROGRESS_EVENT: &str = "mcp-tool-progress";
+
+/// 等待前端审批的最长时间
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+type Reply = Result<Value, String>;
+
+// 等待前端回复的服务端请求
+static PENDING_REQUESTS: Lazy<Mutex<HashMap<String, oneshot::Sender<Reply>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 发送给前端的服务端请求
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientRequestEvent {
+    /// 回复时使用的 id
+    pub request_id: String,
+    pub service_id: String,
+    /// `sampling` 或 `roots`
+    pub kind: &'static str,
+    pub params: Value,
+}
+
+/// 发送给前端的服务端通知
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationEvent {
+    pub service_id: String,
+    /// `tools_list_changed`、`resources_list_changed`、`prompts_list_changed`、`resource_updated` 或 `logging`
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+/// 工具调用进度
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub service_id: String,
+    /// 调用 `call_tool` 时指定的调用 id
+    pub call_id: String,
+    pub progress: u32,
+    pub total: Option<u32>,
+}
+
+/// 进行中的工具调用
+pub struct PendingCall {
+    /// JSON-RPC 请求 id，同时作为进度通知的 progressToken
+    pub request_id: RequestId,
+    /// 取消后等待结果的调用立即返回
+    pub ct: CancellationToken,
+}
+
+/// 服务中进行中的工具调用，以调用 id 为键
+pub type PendingCalls = Arc<Mutex<HashMap<String, PendingCall>>>;
+
+/// 前端对服务端请求的回复，`error` 不为空表示拒绝
+pub fn respond(
+    request_id: &str,
+    result: Option<Value>,
+    error: Option<String>,
+) -> Result<(), String> {
+    let sender = PENDING_REQUESTS
+        .lock()
+        .unwrap()
+        .remove(request_id)
+        .ok_or_else(|| format!("请求 {} 不存在或已超时", request_id))?;
+    let reply = match error {
+        Some(error) => Err(error),
+        None => Ok(result.unwrap_or(Value::Null)),
+    };
+    let _ = sender.send(reply);
+    Ok(())
+}
+
+/// MCP 客户端处理器
+///
+/// 服务端发起的采样、根目录请求转发给前端由用户审批，服务端通知和工具调用进度转发为窗口事件。
+#[derive(Clone)]
+pub struct GhostieClient {
+    service_id: String,
+    calls: PendingCalls,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl GhostieClient {
+    pub fn new(service_id: &str, calls: PendingCalls) -> Self {
+        Self {
+            service_id: service_id.to_string(),
+            calls,
+            peer: None,
+        }
+    }
+
+    /// 把请求发给前端并等待回复
+    async fn ask_frontend(
+        &self,
+        kind: &'static str,
+        params: Value,
+        context: &RequestContext<RoleClient>,
+    ) -> Result<Value, ErrorData> {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let (tx, rx) = oneshot::channel();
+        PENDING_REQUESTS
+            .lock()
+            .unwrap()
+            .insert(request_id.clone(), tx);
+
+        super::emit(
+            CLIENT_REQUEST_EVENT,
+            ClientRequestEvent {
+                request_id: request_id.clone(),
+                service_id: self.service_id.clone(),
+                kind,
+                params,
+            },
+        )
+        .await;
+
+        let reply = tokio::select! {
+            reply = tokio::time::timeout(APPROVAL_TIMEOUT, rx) => match reply {
+                Ok(Ok(reply)) => reply,
+                Ok(Err(_)) => Err("请求已被丢弃".to_string()),
+                Err(_) => Err("等待用户确认超时".to_string()),
+            },
+            _ = context.ct.cancelled() => Err("服务端已取消请求".to_string()),
+        };
+        PENDING_REQUESTS.lock().unwrap().remove(&request_id);
+
+        reply.map_err(|e| ErrorData::invalid_request(e, None))
+    }
+
+    async fn notify(&self, kind: &'static str, params: Option<Value>) {
+        super::emit(
+            NOTIFICATION_EVENT,
+            NotificationEvent {
+                service_id: self.service_id.clone(),
+                kind,
+                params,
+            },
+        )
+        .await;
+    }
+}
+
+impl ClientHandler for GhostieClient {
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+        context: RequestContext<RoleClient>,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        let params = serde_json::to_value(params)
+            .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+        let result = self.ask_frontend("sampling", params, &context).await?;
+        serde_json::from_value(result)
+            .map_err(|e| ErrorData::internal_error(format!("采样结果格式错误: {}", e), None))
+    }
+
+    async fn list_roots(
+        &self,
+        context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, ErrorData> {
+        let result = self.ask_frontend("roots", Value::Null, &context).await?;
+        serde_json::from_value(result)
+            .map_err(|e| ErrorData::internal_error(format!("根目录格式错误: {}", e), None))
+    }
+
+    async fn on_progress(&self, params: ProgressNotificationParam) {
+        let call_id = self
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, call)| call.request_id == params.progress_token)
+            .map(|(call_id, _)| call_id.clone());
+        let Some(call_id) = call_id else {
+            return;
+        };
+        super::emit(
+            PROGRESS_EVENT,
+            ProgressEvent {
+                service_id: self.service_id.clone(),
+                call_id,
+                progress: params.progress,
+                total: params.total,
+            },
+        )
+        .await;
+    }
+
+    async fn on_logging_message(&self, params: LoggingMessageNotificationParam) {
+        tracing::debug!("服务 {} 日志: {:?}", self.service_id, params);
+        self.notify("logging", serde_json::to_value(params).ok())
+            .await;
+    }
+
+    async fn on_resource_updated(&self, params: ResourceUpdatedNotificationParam) {
+        self.notify("resource_updated", serde_json::to_value(params).ok())
+            .await;
+    }
+
+    async fn on_resource_list_changed(&self) {
+        self.notify("resources_list_changed", None).await;
+    }
+
+    async fn on_tool_list_changed(&self) {
+        self.notify("tools_list_changed", None).await;
+    }
+
+    async fn on_prompt_list_changed(&self) {
+        self.notify("prompts_list_changed", None).await;
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            protocol_version: ProtocolVersion::default(),
+            capabilities: ClientCapabilities {
+                experimental: None,
+                roots: Some(RootsCapabilities {
+                    list_changed: Some(false),
+                }),
+                sampling: Some(Default::default()),
+            },
+            client_info: Implementation {
+                name: "ghostie".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::plugins::chat::retry::RetryPolicy;
+use crate::plugins::mcp::transport::RemoteOptions;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// 工具调用的默认超时时间
+const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// MCP 服务的传输方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportKind {
+    /// 启动子进程，通过标准输入输出通信
+    #[default]
+    Stdio,
+    /// 旧版 HTTP + SSE 传输
+    Sse,
+    /// Streamable HTTP 传输
+    StreamableHttp,
+}
+
+/// MCP 服务的启动配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpServerConfig {
+    /// 可执行文件，如 `npx`、`uvx`、`python`、`docker` 或本地程序路径
+    pub command: String,
+    /// 命令参数
+    pub args: Vec<String>,
+    /// 工作目录，为空时继承当前进程
+    pub cwd: Option<String>,
+    /// 额外的环境变量
+    pub env: HashMap<String, String>,
+    /// 传输方式
+    pub transport: TransportKind,
+    /// 远程服务地址（SSE / Streamable HTTP）
+    pub url: Option<String>,
+    /// 连接远程服务时附带的请求头
+    pub headers: HashMap<String, String>,
+    /// 远程服务断线重连策略
+    pub reconnect: Option<RetryPolicy>,
+    /// 服务异常退出后的自动重启策略，`max_attempts` 为连续重启的最大次数，0 表示不重启
+    pub restart: Option<RetryPolicy>,
+    /// 工具调用的超时时间（毫秒），为空时为 5 分钟
+    pub timeout_ms: Option<u64>,
+}
+
+impl McpServerConfig {
+    /// 兼容旧的调用方式：把 npm 包名当作 `npx -y {package}` 启动
+    pub fn npx(package: &str) -> Self {
+        Self {
+            command: "npx".to_string(),
+            args: vec!["-y".to_string(), package.to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// 远程服务的连接参数
+    pub fn remote_options(&self) -> Option<RemoteOptions> {
+        let url = self
+            .url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())?;
+        Some(RemoteOptions {
+            url: url.to_string(),
+            headers: self.headers.clone(),
+            reconnect: self.reconnect.clone().unwrap_or_default(),
+        })
+    }
+
+    /// 自动重启策略
+    pub fn restart_policy(&self) -> RetryPolicy {
+        self.restart.clone().unwrap_or(RetryPolicy {
+            max_attempts: 5,
+            initial_delay_ms: 1000,
+            ..Default::default()
+        })
+    }
+
+    /// 工具调用的超时时间
+    pub fn call_timeout(&self) -> Duration {
+        self.timeout_ms
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_CALL_TIMEOUT)
+    }
+
+    /// 构建子进程命令
+    ///
+    /// Windows 下 `npx`、`uvx` 等通常是 `.cmd` 脚本，不能直接作为可执行文件启动，
+    /// 因此通过 `cmd /c` 转发，并隐藏控制台窗口；其他平台直接启动。
+    pub fn build_command(&self, extra_env: Option<&HashMap<String, String>>) -> Command {
+        #[cfg(target_os = "windows")]
+        let mut cmd = {
+            let mut cmd = Command::new("cmd");
+            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+            cmd.arg("/c").arg(&self.command);
+            cmd
+        };
+        #[cfg(not(target_os = "windows"))]
+        let mut cmd = Command::new(&self.command);
+
+        cmd.args(&self.args);
+
+        if let Some(cwd) = self.cwd.as_deref().filter(|cwd| !cwd.is_empty()) {
+            cmd.current_dir(cwd);
+        }
+
+        // 调用方传入的环境变量优先级更高
+        cmd.envs(&self.env);
+        if let Some(env) = extra_env {
+            cmd.envs(env);
+        }
+
+        cmd
+    }
+}
@@ -0,0 +1,748 @@
+use crate::plugins::node::manifest;
+use crate::plugins::{node, plugin_fs};
+use anyhow::{Context, Result};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use hyper::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE, ORIGIN, WWW_AUTHENTICATE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use once_cell::sync::Lazy;
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, ClientJsonRpcMessage, Content, ErrorData, Implementation,
+    ListToolsResult, PaginatedRequestParam, ProtocolVersion, RequestId, ServerCapabilities,
+    ServerInfo, ServerJsonRpcMessage, Tool,
+};
+use rmcp::service::{RequestContext, RoleServer};
+use rmcp::{ServerHandler, ServiceExt};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+
+/// 本地 HTTP 服务的默认端口
+pub const DEFAULT_HOST_PORT: u16 = 17890;
+
+const EVENT_STREAM: &str = "text/event-stream";
+const SESSION_ID_HEADER: &str = "mcp-session-id";
+
+/// 请求前端查询知识库的事件
+pub const KNOWLEDGE_REQUEST_EVENT: &str = "mcp-host-knowledge-request";
+/// 等待前端返回知识库查询结果的时间
+const KNOWLEDGE_TIMEOUT: Duration = Duration::from_secs(60);
+const KNOWLEDGE_LIST_TOOL: &str = "knowledge_list";
+const KNOWLEDGE_SEARCH_TOOL: &str = "knowledge_search";
+
+// 正在运行的本地 HTTP 服务
+static HTTP_HOST: Lazy<tokio::sync::Mutex<Option<HttpHost>>> =
+    Lazy::new(|| tokio::sync::Mutex::new(None));
+
+type KnowledgeReply = Result<Value, String>;
+
+// 等待前端回复的知识库查询
+static KNOWLEDGE_REQUESTS: Lazy<Mutex<HashMap<String, oneshot::Sender<KnowledgeReply>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 对外发布的工具及其所属插件
+struct HostedTool {
+    plugin_id: String,
+    function: String,
+    tool: Tool,
+}
+
+/// 收集所有已保存插件导出的函数
+///
+/// 函数名在所有插件中唯一且不与知识库工具重名时直接作为工具名，否则加上插件 id 的前 8 位作为前缀。
+async fn catalog() -> Result<Vec<HostedTool>, String> {
+    let mut ids = plugin_fs::plugin_list().await?;
+    ids.sort();
+
+    let mut plugins = Vec::new();
+    for id in ids {
+        let Ok(content) = plugin_fs::plugin_get_content(id.clone()).await else {
+            continue;
+        };
+        let meta = manifest::parse_meta(&content);
+        plugins.push((id, meta, manifest::parse_tools(&content)));
+    }
+
+    let mut counts: HashMap<&str, usize> =
+        HashMap::from([(KNOWLEDGE_LIST_TOOL, 1), (KNOWLEDGE_SEARCH_TOOL, 1)]);
+    for (_, _, tools) in &plugins {
+        for tool in tools {
+            *counts.entry(tool.name.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut hosted = Vec::new();
+    for (id, meta, tools) in &plugins {
+        for tool in tools {
+            let name = if counts[tool.name.as_str()] > 1 {
+                format!("{}_{}", id.chars().take(8).collect::<String>(), tool.name)
+            } else {
+                tool.name.clone()
+            };
+            let description = match meta.get("name") {
+                Some(plugin) if tool.description.is_empty() => format!("插件 {}", plugin),
+                Some(plugin) => format!("{}\n\n来自插件 {}", tool.description, plugin),
+                None => tool.description.clone(),
+            };
+            let schema = tool
+                .parameters
+                .clone()
+                .unwrap_or_else(|| json!({ "type": "object", "properties": {} }));
+            let Value::Object(schema) = schema else {
+                continue;
+            };
+            hosted.push(HostedTool {
+                plugin_id: id.clone(),
+                function: tool.name.clone(),
+                tool: Tool::new(name, description, Arc::new(schema)),
+            });
+        }
+    }
+    Ok(hosted)
+}
+
+/// 向前端发出的知识库查询
+#[derive(Debug, Clone, Serialize)]
+struct KnowledgeRequest {
+    request_id: String,
+    /// `list` 或 `search`
+    kind: &'static str,
+    params: Value,
+}
+
+/// 知识库工具，只在界面运行时提供
+fn knowledge_tools() -> Vec<Tool> {
+    let schema = |value: Value| match value {
+        Value::Object(schema) => Arc::new(schema),
+        _ => Arc::default(),
+    };
+    vec![
+        Tool::new(
+            KNOWLEDGE_LIST_TOOL,
+            "列出 Ghostie 中的知识库及其说明",
+            schema(json!({ "type": "object", "properties": {} })),
+        ),
+        Tool::new(
+            KNOWLEDGE_SEARCH_TOOL,
+            "在 Ghostie 的知识库中搜索与查询相关的内容",
+            schema(json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "查询内容" },
+                    "knowledge_ids": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "要搜索的知识库 id，为空时搜索全部"
+                    }
+                },
+                "required": ["query"]
+            })),
+        ),
+    ]
+}
+
+/// 请求前端查询知识库
+///
+/// 知识库保存在前端，没有界面（stdio 模式）时不可用。
+async fn ask_knowledge(kind: &'static str, params: Value) -> Result<Value, String> {
+    let Some(app) = super::get_app_handle().await else {
+        return Err("知识库仅在 Ghostie 界面运行时可用".to_string());
+    };
+
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = oneshot::channel();
+    KNOWLEDGE_REQUESTS
+        .lock()
+        .unwrap()
+        .insert(request_id.clone(), tx);
+    let request = KnowledgeRequest {
+        request_id: request_id.clone(),
+        kind,
+        params,
+    };
+    if let Err(e) = app.emit(KNOWLEDGE_REQUEST_EVENT, request) {
+        eprintln!("Failed to emit {} event: {}", KNOWLEDGE_REQUEST_EVENT, e);
+    }
+
+    let reply = tokio::time::timeout(KNOWLEDGE_TIMEOUT, rx).await;
+    KNOWLEDGE_REQUESTS.lock().unwrap().remove(&request_id);
+    match reply {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(_)) => Err("请求已被丢弃".to_string()),
+        Err(_) => Err("等待知识库查询结果超时".to_string()),
+    }
+}
+
+/// 前端回复知识库查询，`error` 不为空表示查询失败
+pub fn respond_knowledge(
+    request_id: &str,
+    result: Option<Value>,
+    error: Option<String>,
+) -> Result<(), String> {
+    let sender = KNOWLEDGE_REQUESTS
+        .lock()
+        .unwrap()
+        .remove(request_id)
+        .ok_or_else(|| format!("请求 {} 不存在或已超时", request_id))?;
+    let reply = match error {
+        Some(error) => Err(error),
+        None => Ok(result.unwrap_or(Value::Null)),
+    };
+    let _ = sender.send(reply);
+    Ok(())
+}
+
+/// 调用知识库工具
+async fn call_knowledge(name: &str, args: Value) -> CallToolResult {
+    let reply = match name {
+        KNOWLEDGE_LIST_TOOL => ask_knowledge("list", Value::Null).await,
+        _ => match args.get("query").and_then(Value::as_str) {
+            Some(query) if !query.trim().is_empty() => ask_knowledge("search", args).await,
+            _ => Err("缺少查询内容 query".to_string()),
+        },
+    };
+    match reply {
+        Ok(output) => tool_result(json!({ "result": output })),
+        Err(e) => CallToolResult::error(vec![Content::text(e)]),
+    }
+}
+
+/// 把插件的执行结果转换为工具调用结果
+fn tool_result(output: Value) -> CallToolResult {
+    let text = |value: &Value| match value {
+        Value::String(text) => text.clone(),
+        value => serde_json::to_string_pretty(value).unwrap_or_default(),
+    };
+    match &output {
+        Value::Object(map) if map.contains_key("error") => {
+            CallToolResult::error(vec![Content::text(text(&map["error"]))])
+        }
+        Value::Object(map) if map.contains_key("result") => {
+            CallToolResult::success(vec![Content::text(text(&map["result"]))])
+        }
+        output => CallToolResult::success(vec![Content::text(text(output))]),
+    }
+}
+
+/// 把已保存的插件作为工具对外提供的 MCP 服务
+#[derive(Clone, Default)]
+pub struct GhostieServer;
+
+impl ServerHandler for GhostieServer {
+    async fn list_tools(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let tools = catalog()
+            .await
+            .map_err(|e| ErrorData::internal_error(e, None))?;
+        let mut tools: Vec<Tool> = tools.into_iter().map(|hosted| hosted.tool).collect();
+        if super::get_app_handle().await.is_some() {
+            tools.extend(knowledge_tools());
+        }
+        Ok(ListToolsResult {
+            next_cursor: None,
+            tools,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if matches!(
+            request.name.as_ref(),
+            KNOWLEDGE_LIST_TOOL | KNOWLEDGE_SEARCH_TOOL
+        ) {
+            let args = Value::Object(request.arguments.unwrap_or_default());
+            return Ok(call_knowledge(&request.name, args).await);
+        }
+
+        let tools = catalog()
+            .await
+            .map_err(|e| ErrorData::internal_error(e, None))?;
+        let hosted = tools
+            .into_iter()
+            .find(|hosted| hosted.tool.name == request.name)
+            .ok_or_else(|| {
+                ErrorData::invalid_params(format!("工具不存在: {}", request.name), None)
+            })?;
+
+        let args = Value::Object(request.arguments.unwrap_or_default());
+        match node::execute_saved_plugin(&hosted.plugin_id, &hosted.function, args).await {
+            Ok(output) => Ok(tool_result(output)),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(e.to_string())])),
+        }
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::default(),
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            server_info: Implementation {
+                name: "ghostie".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            instructions: Some("调用 Ghostie 中保存的插件，查询 Ghostie 的知识库".to_string()),
+        }
+    }
+}
+
+/// 通过标准输入输出提供服务，直到对端断开
+pub async fn serve_stdio() -> Result<()> {
+    let service = GhostieServer
+        .serve((tokio::io::stdin(), tokio::io::stdout()))
+        .await
+        .context("初始化 MCP 会话失败")?;
+    service.waiting().await?;
+    Ok(())
+}
+
+/// 一个 HTTP 会话，对应一个独立运行的服务处理器
+struct Session {
+    /// 发往服务处理器的消息
+    in_tx: mpsc::UnboundedSender<ClientJsonRpcMessage>,
+    /// 等待通过 POST 响应返回结果的请求
+    pending: Mutex<HashMap<RequestId, oneshot::Sender<ServerJsonRpcMessage>>>,
+    /// 推送其他消息的事件流，旧版 SSE 的所有消息都从这里返回
+    stream: Mutex<Option<mpsc::UnboundedSender<ServerJsonRpcMessage>>>,
+    ct: CancellationToken,
+}
+
+type Sessions = Arc<Mutex<HashMap<String, Arc<Session>>>>;
+
+/// 创建会话并在后台运行服务处理器，会话取消或处理器退出后自动移除
+fn open_session(sessions: &Sessions, parent: &CancellationToken) -> (String, Arc<Session>) {
+    let (in_tx, in_rx) = mpsc::unbounded::<ClientJsonRpcMessage>();
+    let (out_tx, mut out_rx) = mpsc::unbounded::<ServerJsonRpcMessage>();
+    let id = uuid::Uuid::new_v4().to_string();
+    let session = Arc::new(Session {
+        in_tx,
+        pending: Mutex::new(HashMap::new()),
+        stream: Mutex::new(None),
+        ct: parent.child_token(),
+    });
+    sessions.lock().unwrap().insert(id.clone(), session.clone());
+
+    let ct = session.ct.clone();
+    let transport = (
+        out_tx.sink_map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "会话已关闭")),
+        in_rx,
+    );
+    tokio::spawn(async move {
+        match GhostieServer.serve_with_ct(transport, ct.clone()).await {
+            Ok(service) => {
+                let _ = service.waiting().await;
+            }
+            Err(e) => tracing::debug!("MCP 会话初始化失败: {}", e),
+        }
+        ct.cancel();
+    });
+
+    // 把处理器的输出分发给等待中的请求或事件流
+    let dispatched = session.clone();
+    let sessions = sessions.clone();
+    let session_id = id.clone();
+    tokio::spawn(async move {
+        loop {
+            let message = tokio::select! {
+                message = out_rx.next() => match message {
+                    Some(message) => message,
+                    None => break,
+                },
+                _ = dispatched.ct.cancelled() => break,
+            };
+            let request_id = match &message {
+                ServerJsonRpcMessage::Response(response) => Some(response.id.clone()),
+                ServerJsonRpcMessage::Error(error) => Some(error.id.clone()),
+                _ => None,
+            };
+            let waiter = request_id.and_then(|id| dispatched.pending.lock().unwrap().remove(&id));
+            match waiter {
+                Some(waiter) => {
+                    let _ = waiter.send(message);
+                }
+                None => {
+                    let mut stream = dispatched.stream.lock().unwrap();
+                    if let Some(tx) = stream.as_ref() {
+                        if tx.unbounded_send(message).is_err() {
+                            *stream = None;
+                        }
+                    }
+                }
+            }
+        }
+        dispatched.ct.cancel();
+        sessions.lock().unwrap().remove(&session_id);
+    });
+
+    (id, session)
+}
+
+/// 把消息编码为一个 SSE 事件
+fn sse_event(event: &str, data: &str) -> Result<hyper::body::Bytes, Infallible> {
+    Ok(format!("event: {}\ndata: {}\n\n", event, data).into())
+}
+
+/// 把发往会话的消息转换为 SSE 响应体
+fn message_stream(
+    rx: mpsc::UnboundedReceiver<ServerJsonRpcMessage>,
+    prefix: Option<String>,
+    guard: Option<tokio_util::sync::DropGuard>,
+) -> Body {
+    let head = futures::stream::iter(prefix.map(|endpoint| sse_event("endpoint", &endpoint)));
+    let messages = rx.map(move |message| {
+        // 响应体被丢弃（客户端断开）时随之释放
+        let _ = &guard;
+        sse_event(
+            "message",
+            &serde_json::to_string(&message).unwrap_or_default(),
+        )
+    });
+    Body::wrap_stream(head.chain(messages))
+}
+
+fn response(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    let mut response = Response::new(body.into());
+    *response.status_mut() = status;
+    response
+}
+
+fn json_response(value: &impl serde::Serialize) -> Response<Body> {
+    let mut response = response(
+        StatusCode::OK,
+        serde_json::to_string(value).unwrap_or_default(),
+    );
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+/// 只接受来自本机页面的请求，防止 DNS 重绑定攻击
+fn is_local_origin(request: &Request<Body>) -> bool {
+    let Some(origin) = request.headers().get(ORIGIN) else {
+        return true;
+    };
+    let Some(host) = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| url::Url::parse(origin).ok())
+        .and_then(|url| url.host_str().map(str::to_string))
+    else {
+        return false;
+    };
+    matches!(host.as_str(), "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// 校验请求携带的令牌 `Authorization: Bearer {token}`
+fn is_authorized(request: &Request<Body>, token: &str) -> bool {
+    let Some(value) = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    // 比较全部字节，耗时与不匹配的位置无关
+    value.len() == token.len()
+        && value
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn session_of(sessions: &Sessions, request: &Request<Body>) -> Option<Arc<Session>> {
+    let id = request.headers().get(SESSION_ID_HEADER)?.to_str().ok()?;
+    sessions.lock().unwrap().get(id).cloned()
+}
+
+/// 处理 Streamable HTTP 的 POST 请求
+///
+/// 请求的结果直接作为响应体返回，通知和响应返回 202。
+async fn handle_post(
+    sessions: Sessions,
+    ct: CancellationToken,
+    request: Request<Body>,
+) -> Result<Response<Body>> {
+    let session = session_of(&sessions, &request);
+    if session.is_none() && request.headers().contains_key(SESSION_ID_HEADER) {
+        return Ok(response(StatusCode::NOT_FOUND, "会话不存在或已关闭"));
+    }
+
+    let body = hyper::body::to_bytes(request.into_body()).await?;
+    let value: Value = match serde_json::from_slice(&body) {
+        Ok(value) => value,
+        Err(e) => return Ok(response(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+    let batch = value.is_array();
+    let values = match value {
+        Value::Array(values) => values,
+        value => vec![value],
+    };
+    // 没有会话时只接受初始化请求
+    let initialize = values
+        .iter()
+        .any(|value| value.get("method") == Some(&json!("initialize")));
+    let mut messages = Vec::new();
+    for value in values {
+        match serde_json::from_value::<ClientJsonRpcMessage>(value) {
+            Ok(message) => messages.push(message),
+            Err(e) => return Ok(response(StatusCode::BAD_REQUEST, e.to_string())),
+        }
+    }
+
+    let (session_id, session) = match session {
+        Some(session) => (None, session),
+        None => {
+            if !initialize {
+                return Ok(response(StatusCode::BAD_REQUEST, "缺少会话 id"));
+            }
+            let (id, session) = open_session(&sessions, &ct);
+            (Some(id), session)
+        }
+    };
+
+    let mut waiters = Vec::new();
+    for message in messages {
+        if let ClientJsonRpcMessage::Request(request) = &message {
+            let (tx, rx) = oneshot::channel();
+            session
+                .pending
+                .lock()
+                .unwrap()
+                .insert(request.id.clone(), tx);
+            waiters.push(rx);
+        }
+        if session.in_tx.unbounded_send(message).is_err() {
+            return Ok(response(StatusCode::NOT_FOUND, "会话已关闭"));
+        }
+    }
+
+    let mut replies = Vec::new();
+    for waiter in waiters {
+        tokio::select! {
+            reply = waiter => match reply {
+                Ok(reply) => replies.push(reply),
+                Err(_) => return Ok(response(StatusCode::NOT_FOUND, "会话已关闭")),
+            },
+            _ = session.ct.cancelled() => return Ok(response(StatusCode::NOT_FOUND, "会话已关闭")),
+        }
+    }
+
+    let mut response = match replies.len() {
+        0 => response(StatusCode::ACCEPTED, Body::empty()),
+        1 if !batch => json_response(&replies[0]),
+        _ => json_response(&replies),
+    };
+    if let Some(id) = session_id.and_then(|id| HeaderValue::from_str(&id).ok()) {
+        response.headers_mut().insert(SESSION_ID_HEADER, id);
+    }
+    Ok(response)
+}
+
+/// 路由本地 HTTP 请求
+///
+/// - `POST /mcp`、`GET /mcp`、`DELETE /mcp`：Streamable HTTP
+/// - `GET /sse`、`POST /message?sessionId=`：旧版 HTTP+SSE
+///
+/// 除 `/message` 外都需要携带令牌，`/message` 的会话 id 只能从已校验令牌的 `/sse` 获得。
+async fn route(
+    sessions: Sessions,
+    ct: CancellationToken,
+    token: Arc<str>,
+    request: Request<Body>,
+) -> Result<Response<Body>> {
+    if !is_local_origin(&request) {
+        return Ok(response(StatusCode::FORBIDDEN, "不允许的来源"));
+    }
+    if request.uri().path() != "/message" && !is_authorized(&request, &token) {
+        let mut response = response(StatusCode::UNAUTHORIZED, "缺少或错误的令牌");
+        response
+            .headers_mut()
+            .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        return Ok(response);
+    }
+
+    match (request.method(), request.uri().path()) {
+        (&Method::POST, "/mcp") => handle_post(sessions, ct, request).await,
+        (&Method::GET, "/mcp") => {
+            let Some(session) = session_of(&sessions, &request) else {
+                return Ok(response(StatusCode::NOT_FOUND, "会话不存在或已关闭"));
+            };
+            let (tx, rx) = mpsc::unbounded();
+            *session.stream.lock().unwrap() = Some(tx);
+            let mut response = response(StatusCode::OK, message_stream(rx, None, None));
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(EVENT_STREAM));
+            Ok(response)
+        }
+        (&Method::DELETE, "/mcp") => {
+            if let Some(session) = session_of(&sessions, &request) {
+                session.ct.cancel();
+            }
+            Ok(response(StatusCode::OK, Body::empty()))
+        }
+        (&Method::GET, "/sse") => {
+            let (id, session) = open_session(&sessions, &ct);
+            let (tx, rx) = mpsc::unbounded();
+            *session.stream.lock().unwrap() = Some(tx);
+            let endpoint = format!("/message?sessionId={}", id);
+            // 事件流断开即结束会话
+            let guard = session.ct.clone().drop_guard();
+            let mut response = response(
+                StatusCode::OK,
+                message_stream(rx, Some(endpoint), Some(guard)),
+            );
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(EVENT_STREAM));
+            Ok(response)
+        }
+        (&Method::POST, "/message") => {
+            let session = request
+                .uri()
+                .query()
+                .and_then(|query| {
+                    url::form_urlencoded::parse(query.as_bytes())
+                        .find(|(key, _)| key == "sessionId")
+                        .map(|(_, value)| value.into_owned())
+                })
+                .and_then(|id| sessions.lock().unwrap().get(&id).cloned());
+            let Some(session) = session else {
+                return Ok(response(StatusCode::NOT_FOUND, "会话不存在或已关闭"));
+            };
+            let body = hyper::body::to_bytes(request.into_body()).await?;
+            let message: ClientJsonRpcMessage = match serde_json::from_slice(&body) {
+                Ok(message) => message,
+                Err(e) => return Ok(response(StatusCode::BAD_REQUEST, e.to_string())),
+            };
+            if session.in_tx.unbounded_send(message).is_err() {
+                return Ok(response(StatusCode::NOT_FOUND, "会话已关闭"));
+            }
+            Ok(response(StatusCode::ACCEPTED, Body::empty()))
+        }
+        _ => Ok(response(StatusCode::NOT_FOUND, Body::empty())),
+    }
+}
+
+/// 正在运行的本地 HTTP 服务
+struct HttpHost {
+    addr: SocketAddr,
+    token: String,
+    ct: CancellationToken,
+    task: tokio::task::JoinHandle<()>,
+}
+
+/// 本地 HTTP 服务的地址和访问令牌
+#[derive(Debug, Clone, Serialize)]
+pub struct HostStatus {
+    pub url: String,
+    /// 每次启动服务时生成，客户端通过 `Authorization: Bearer {token}` 携带
+    pub token: String,
+}
+
+impl HttpHost {
+    fn status(&self) -> HostStatus {
+        HostStatus {
+            url: format!("http://{}", self.addr),
+            token: self.token.clone(),
+        }
+    }
+}
+
+/// 在本机回环地址上启动 HTTP 服务，已在运行时返回当前地址和令牌
+///
+/// `port` 为 0 时由系统分配端口。
+pub async fn start_http(port: u16) -> Result<HostStatus> {
+    let mut host = HTTP_HOST.lock().await;
+    if let Some(host) = host.as_ref().filter(|host| !host.task.is_finished()) {
+        return Ok(host.status());
+    }
+
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let ct = CancellationToken::new();
+    let sessions: Sessions = Arc::new(Mutex::new(HashMap::new()));
+    let make_service = {
+        let ct = ct.clone();
+        let token: Arc<str> = Arc::from(token.as_str());
+        make_service_fn(move |_| {
+            let sessions = sessions.clone();
+            let ct = ct.clone();
+            let token = token.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let sessions = sessions.clone();
+                    let ct = ct.clone();
+                    let token = token.clone();
+                    async move {
+                        Ok::<_, Infallible>(
+                            route(sessions, ct, token, request)
+                                .await
+                                .unwrap_or_else(|e| {
+                                    response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                                }),
+                        )
+                    }
+                }))
+            }
+        })
+    };
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let server = Server::try_bind(&addr)
+        .with_context(|| format!("无法监听端口 {}", port))?
+        .serve(make_service);
+    let addr = server.local_addr();
+    let shutdown = ct.clone();
+    let task = tokio::spawn(async move {
+        if let Err(e) = server
+            .with_graceful_shutdown(shutdown.cancelled_owned())
+            .await
+        {
+            eprintln!("MCP 服务异常退出: {}", e);
+        }
+    });
+
+    let started = HttpHost {
+        addr,
+        token,
+        ct,
+        task,
+    };
+    let status = started.status();
+    *host = Some(started);
+    Ok(status)
+}
+
+/// 停止本地 HTTP 服务并关闭所有会话
+pub async fn stop_http() {
+    if let Some(host) = HTTP_HOST.lock().await.take() {
+        host.ct.cancel();
+        let _ = host.task.await;
+    }
+}
+
+/// 本地 HTTP 服务的地址和令牌，未运行时为空
+pub async fn http_status() -> Option<HostStatus> {
+    HTTP_HOST
+        .lock()
+        .await
+        .as_ref()
+        .filter(|host| !host.task.is_finished())
+        .map(HttpHost::status)
+}
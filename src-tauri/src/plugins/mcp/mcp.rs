@@ -1,127 +1,638 @@
+use crate::plugins::mcp::client::{GhostieClient, PendingCall, PendingCalls};
+use crate::plugins::mcp::config::{McpServerConfig, TransportKind};
+use crate::plugins::mcp::status::{
+    self, LogBuffer, ServiceSnapshot, ServiceStatus, ServiceStatusEvent,
+};
+use crate::plugins::mcp::transport;
 use anyhow::Result;
 use rmcp::{
-    model::{CallToolRequestParam, CallToolResult, Tool},
-    service::RunningService,
-    transport::TokioChildProcess,
+    model::{
+        CallToolRequest, CallToolRequestParam, CallToolResult, CancelledNotificationParam,
+        ClientRequest, GetPromptRequestParam, GetPromptResult, JsonObject, Prompt,
+        ReadResourceRequestParam, ReadResourceResult, Resource, ResourceTemplate, ServerResult,
+        SubscribeRequestParam, Tool, UnsubscribeRequestParam,
+    },
+    service::{Peer, PeerRequestOptions, RequestHandle, RunningService},
     RoleClient, ServiceExt,
 };
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::process::Stdio;
 use std::sync::Arc;
-use tokio::process::Command;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, ChildStderr};
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// 服务稳定运行超过该时间后，重新计算连续重启次数
+const STABLE_DURATION: Duration = Duration::from_secs(60);
+
+type Client = RunningService<RoleClient, GhostieClient>;
+type SharedState = Arc<std::sync::Mutex<ServiceState>>;
+
+/// 服务的运行时状态，由监控任务维护
+#[derive(Default)]
+struct ServiceState {
+    status: Option<ServiceStatus>,
+    message: Option<String>,
+    restarts: u32,
+    /// 服务运行中时可用的连接
+    peer: Option<Peer<RoleClient>>,
+    logs: LogBuffer,
+    /// 进行中的工具调用，重启后沿用
+    calls: PendingCalls,
+    /// 工具调用的默认超时时间
+    call_timeout: Duration,
+}
+
+/// 管理器中的一个服务
+struct ServiceEntry {
+    state: SharedState,
+    /// 串行化同一服务的启动和停止，工具调用不需要获取
+    lifecycle: Mutex<Lifecycle>,
+}
+
+/// 服务当前一次运行的控制句柄
+#[derive(Default)]
+struct Lifecycle {
+    /// 取消后监控任务会关闭服务并退出
+    ct: CancellationToken,
+    supervisor: Option<JoinHandle<()>>,
+}
+
+impl ServiceEntry {
+    fn status(&self) -> Option<ServiceStatus> {
+        self.state.lock().unwrap().status
+    }
+
+    fn peer(&self, service_id: &str) -> Result<Peer<RoleClient>> {
+        let state = self.state.lock().unwrap();
+        state.peer.clone().ok_or_else(|| {
+            anyhow::anyhow!(
+                "服务 {} 未在运行: {}",
+                service_id,
+                state.message.as_deref().unwrap_or("正在启动")
+            )
+        })
+    }
+}
+
 // MCP管理器结构体，管理多个MCP服务实例
+//
+// 服务表只在查找时短暂加锁，不跨越 await；工具调用复制出连接后并发执行，
+// 启动、停止只会等待同一服务的其他启动、停止操作。
+#[derive(Clone)]
 pub struct MCPManager {
-    services: Arc<Mutex<HashMap<String, RunningService<RoleClient, ()>>>>,
+    services: Arc<std::sync::Mutex<HashMap<String, Arc<ServiceEntry>>>>,
 }
 
 impl MCPManager {
     // 创建新的服务管理器
     pub fn new() -> Result<Self> {
         Ok(Self {
-            services: Arc::new(Mutex::new(HashMap::new())),
+            services: Arc::new(std::sync::Mutex::new(HashMap::new())),
         })
     }
 
-    // 启动一个新的MCP服务实例，注入环境变量
+    // 查找服务
+    fn entry(&self, service_id: &str) -> Result<Arc<ServiceEntry>> {
+        self.services
+            .lock()
+            .unwrap()
+            .get(service_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("服务 {} 不存在", service_id))
+    }
+
+    // 按配置启动一个新的MCP服务实例，注入环境变量，并在后台监控其运行状态
     pub async fn start_service(
         &self,
         service_id: &str,
+        config: &McpServerConfig,
         env: Option<HashMap<String, String>>,
     ) -> Result<()> {
-        let mut services = self.services.lock().await;
+        let entry = self
+            .services
+            .lock()
+            .unwrap()
+            .entry(service_id.to_string())
+            .or_insert_with(|| {
+                Arc::new(ServiceEntry {
+                    state: Default::default(),
+                    lifecycle: Default::default(),
+                })
+            })
+            .clone();
+        let mut lifecycle = entry.lifecycle.lock().await;
 
-        // 检查服务是否已存在
-        if services.contains_key(service_id) {
+        // 检查服务是否已在运行
+        if matches!(
+            entry.status(),
+            Some(ServiceStatus::Starting | ServiceStatus::Running)
+        ) {
             tracing::info!("服务 {} 已经存在", service_id);
             return Ok(());
         }
 
-        // 创建命令
-        let mut cmd = Command::new("cmd");
-        #[cfg(target_os = "windows")]
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
-        cmd.arg("/c").arg("npx").arg("-y").arg(service_id);
+        // 结束之前的监控任务（可能还在等待重启），日志保留，方便排查反复启动失败的问题
+        lifecycle.ct.cancel();
+        if let Some(supervisor) = lifecycle.supervisor.take() {
+            let _ = supervisor.await;
+        }
+        let state = entry.state.clone();
+        {
+            let mut state = state.lock().unwrap();
+            state.restarts = 0;
+            state.call_timeout = config.call_timeout();
+        }
+        let ct = CancellationToken::new();
+        lifecycle.ct = ct.clone();
 
-        // 如果提供了环境变量，添加到命令中
-        if let Some(env_vars) = env {
-            for (key, value) in env_vars {
-                cmd.env(key, value);
+        set_status(service_id, &state, ServiceStatus::Starting, None).await;
+        let connected = connect(service_id, config, env.as_ref(), ct.child_token(), &state).await;
+        let (service, child) = match connected {
+            Ok(connected) => connected,
+            Err(e) => {
+                set_status(
+                    service_id,
+                    &state,
+                    ServiceStatus::Crashed,
+                    Some(e.to_string()),
+                )
+                .await;
+                return Err(e);
             }
-        }
+        };
 
-        // 启动新服务
-        let service = ().serve(TokioChildProcess::new(&mut cmd)?).await?;
+        state.lock().unwrap().peer = Some(service.peer().clone());
+        set_status(service_id, &state, ServiceStatus::Running, None).await;
+        match config.transport {
+            TransportKind::Stdio => tracing::info!(
+                "服务 {} 已启动: {} {}",
+                service_id,
+                config.command,
+                config.args.join(" ")
+            ),
+            _ => tracing::info!(
+                "服务 {} 已连接: {}",
+                service_id,
+                config.url.as_deref().unwrap_or_default()
+            ),
+        }
 
-        services.insert(service_id.to_string(), service);
-        tracing::info!("服务 {} 已启动", service_id);
+        lifecycle.supervisor = Some(tokio::spawn(supervise(
+            service_id.to_string(),
+            config.clone(),
+            env,
+            state,
+            ct,
+            service,
+            child,
+        )));
 
         Ok(())
     }
 
     // 获取服务信息和工具列表
     pub async fn get_service_info(&self, service_id: &str) -> Result<(String, Vec<Tool>)> {
-        let services = self.services.lock().await;
-
-        if let Some(service) = services.get(service_id) {
-            let server_info = format!("{:?}", service.peer_info());
-            let tools = service.list_all_tools().await?;
-            return Ok((server_info, tools));
-        }
-
-        anyhow::bail!("服务 {} 不存在", service_id)
+        let peer = self.peer(service_id)?;
+        let server_info = format!("{:?}", peer.peer_info());
+        let tools = peer.list_all_tools().await?;
+        Ok((server_info, tools))
     }
 
     // 调用指定服务的工具
+    //
+    // `call_id` 用于取消调用和对应进度事件；`timeout` 为空时使用服务配置的超时，
+    // 超时后向服务端发送取消通知并返回错误。
     pub async fn call_tool(
         &self,
         service_id: &str,
         tool_name: &str,
         arguments: serde_json::Map<String, serde_json::Value>,
+        call_id: &str,
+        timeout: Option<Duration>,
     ) -> Result<CallToolResult> {
-        let services = self.services.lock().await;
-        let service = services
-            .get(service_id)
-            .ok_or_else(|| anyhow::anyhow!("服务 {} 未找到", service_id))?;
+        let entry = self.entry(service_id)?;
+        let peer = entry.peer(service_id)?;
+        let (calls, default_timeout) = {
+            let state = entry.state.lock().unwrap();
+            (state.calls.clone(), state.call_timeout)
+        };
+        if calls.lock().unwrap().contains_key(call_id) {
+            anyhow::bail!("调用 {} 正在进行中", call_id);
+        }
+
+        let request = ClientRequest::CallToolRequest(CallToolRequest {
+            method: Default::default(),
+            params: CallToolRequestParam {
+                name: Cow::Owned(tool_name.to_string()),
+                arguments: Some(arguments),
+            },
+        });
+        let RequestHandle {
+            rx, id: request_id, ..
+        } = peer
+            .send_cancellable_request(request, PeerRequestOptions::no_options())
+            .await?;
 
-        let request = CallToolRequestParam {
-            name: Cow::Owned(tool_name.to_string()),
-            arguments: Some(arguments),
+        let ct = CancellationToken::new();
+        calls.lock().unwrap().insert(
+            call_id.to_string(),
+            PendingCall {
+                request_id: request_id.clone(),
+                ct: ct.clone(),
+            },
+        );
+        let timeout = timeout.unwrap_or(default_timeout);
+        let response = tokio::select! {
+            response = tokio::time::timeout(timeout, rx) => Some(response),
+            _ = ct.cancelled() => None,
         };
+        calls.lock().unwrap().remove(call_id);
 
-        let result = service.call_tool(request).await?;
-        Ok(result)
+        match response {
+            None => anyhow::bail!("工具调用 {} 已取消", call_id),
+            Some(Err(_)) => {
+                let _ = peer
+                    .notify_cancelled(CancelledNotificationParam {
+                        request_id,
+                        reason: Some("timeout".to_string()),
+                    })
+                    .await;
+                anyhow::bail!(
+                    "工具 {} 调用超时（{} 秒）",
+                    tool_name,
+                    timeout.as_secs_f32()
+                )
+            }
+            Some(Ok(Err(_))) => anyhow::bail!("服务 {} 连接已断开", service_id),
+            Some(Ok(Ok(Err(e)))) => Err(e.into()),
+            Some(Ok(Ok(Ok(ServerResult::CallToolResult(result))))) => Ok(result),
+            Some(Ok(Ok(Ok(_)))) => anyhow::bail!("服务 {} 返回了意外的响应", service_id),
+        }
     }
 
-    // 停止指定的服务
-    pub async fn stop_service(&self, service_id: &str) -> Result<()> {
-        let mut services = self.services.lock().await;
+    // 取消进行中的工具调用，并通知服务端停止处理
+    pub async fn cancel_tool_call(
+        &self,
+        service_id: &str,
+        call_id: &str,
+        reason: Option<String>,
+    ) -> Result<()> {
+        let entry = self.entry(service_id)?;
+        let (peer, call) = {
+            let state = entry.state.lock().unwrap();
+            let call = state.calls.lock().unwrap().remove(call_id);
+            (state.peer.clone(), call)
+        };
+        let call = call.ok_or_else(|| anyhow::anyhow!("调用 {} 不存在或已结束", call_id))?;
 
-        if let Some(service) = services.remove(service_id) {
-            service.cancel().await?;
-            tracing::info!("服务 {} 已停止", service_id);
-            return Ok(());
+        call.ct.cancel();
+        if let Some(peer) = peer {
+            // rmcp 发送通知后不会回复结果，返回值总是连接断开的错误，这里忽略
+            let _ = peer
+                .notify_cancelled(CancelledNotificationParam {
+                    request_id: call.request_id,
+                    reason,
+                })
+                .await;
         }
+        Ok(())
+    }
+
+    // 获取运行中服务的连接，连接可以复制出来并发使用
+    pub fn peer(&self, service_id: &str) -> Result<Peer<RoleClient>> {
+        self.entry(service_id)?.peer(service_id)
+    }
+
+    // 获取资源列表
+    pub async fn list_resources(&self, service_id: &str) -> Result<Vec<Resource>> {
+        Ok(self.peer(service_id)?.list_all_resources().await?)
+    }
+
+    // 获取资源模板列表
+    pub async fn list_resource_templates(&self, service_id: &str) -> Result<Vec<ResourceTemplate>> {
+        Ok(self.peer(service_id)?.list_all_resource_templates().await?)
+    }
 
-        anyhow::bail!("服务 {} 不存在", service_id)
+    // 读取资源内容
+    pub async fn read_resource(&self, service_id: &str, uri: &str) -> Result<ReadResourceResult> {
+        let request = ReadResourceRequestParam {
+            uri: uri.to_string(),
+        };
+        Ok(self.peer(service_id)?.read_resource(request).await?)
+    }
+
+    // 订阅资源更新，更新时服务端会发送 resource_updated 通知
+    pub async fn subscribe_resource(&self, service_id: &str, uri: &str) -> Result<()> {
+        let request = SubscribeRequestParam {
+            uri: uri.to_string(),
+        };
+        Ok(self.peer(service_id)?.subscribe(request).await?)
+    }
+
+    // 取消订阅资源
+    pub async fn unsubscribe_resource(&self, service_id: &str, uri: &str) -> Result<()> {
+        let request = UnsubscribeRequestParam {
+            uri: uri.to_string(),
+        };
+        Ok(self.peer(service_id)?.unsubscribe(request).await?)
+    }
+
+    // 获取提示词列表
+    pub async fn list_prompts(&self, service_id: &str) -> Result<Vec<Prompt>> {
+        Ok(self.peer(service_id)?.list_all_prompts().await?)
+    }
+
+    // 按参数获取提示词
+    pub async fn get_prompt(
+        &self,
+        service_id: &str,
+        name: &str,
+        arguments: Option<JsonObject>,
+    ) -> Result<GetPromptResult> {
+        let request = GetPromptRequestParam {
+            name: name.to_string(),
+            arguments,
+        };
+        Ok(self.peer(service_id)?.get_prompt(request).await?)
+    }
+
+    // 停止指定的服务
+    pub async fn stop_service(&self, service_id: &str) -> Result<()> {
+        let entry = self.entry(service_id)?;
+        shutdown(service_id, &entry).await;
+        tracing::info!("服务 {} 已停止", service_id);
+        Ok(())
     }
 
     // 停止所有服务
     pub async fn stop_all_services(&self) -> Result<()> {
-        let mut services = self.services.lock().await;
+        let entries: Vec<_> = self
+            .services
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, entry)| (id.clone(), entry.clone()))
+            .collect();
 
-        for (service_id, service) in services.drain() {
-            service.cancel().await?;
+        futures::future::join_all(entries.iter().map(|(service_id, entry)| async move {
+            shutdown(service_id, entry).await;
             tracing::info!("服务 {} 已停止", service_id);
-        }
+        }))
+        .await;
 
         Ok(())
     }
 
     // 获取已启动的服务列表
     pub async fn list_services(&self) -> Result<Vec<String>> {
-        let services = self.services.lock().await;
-        Ok(services.keys().cloned().collect())
+        let services = self.services.lock().unwrap();
+        Ok(services
+            .iter()
+            .filter(|(_, entry)| entry.status() == Some(ServiceStatus::Running))
+            .map(|(id, _)| id.clone())
+            .collect())
+    }
+
+    // 获取服务状态和最近的日志，未指定 id 时返回全部服务
+    pub async fn service_snapshots(
+        &self,
+        service_id: Option<&str>,
+        lines: usize,
+    ) -> Result<Vec<ServiceSnapshot>> {
+        let services = self.services.lock().unwrap();
+        if let Some(service_id) = service_id {
+            if !services.contains_key(service_id) {
+                anyhow::bail!("服务 {} 不存在", service_id);
+            }
+        }
+
+        let mut snapshots: Vec<ServiceSnapshot> = services
+            .iter()
+            .filter(|(id, _)| !matches!(service_id, Some(service_id) if service_id != id.as_str()))
+            .map(|(id, entry)| {
+                let state = entry.state.lock().unwrap();
+                ServiceSnapshot {
+                    id: id.clone(),
+                    status: state.status.unwrap_or(ServiceStatus::Stopped),
+                    message: state.message.clone(),
+                    restarts: state.restarts,
+                    logs: state.logs.recent(lines),
+                }
+            })
+            .collect();
+        snapshots.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(snapshots)
+    }
+}
+
+/// 更新服务状态，记录到日志并通知前端
+async fn set_status(
+    service_id: &str,
+    state: &SharedState,
+    status: ServiceStatus,
+    message: Option<String>,
+) {
+    let event = {
+        let mut state = state.lock().unwrap();
+        state.status = Some(status);
+        state.message = message.clone();
+        if status != ServiceStatus::Running {
+            state.peer = None;
+        }
+        let line = match &message {
+            Some(message) => format!("{:?}: {}", status, message),
+            None => format!("{:?}", status),
+        };
+        state.logs.push("system", line);
+        ServiceStatusEvent {
+            id: service_id.to_string(),
+            status,
+            message,
+            restarts: state.restarts,
+        }
+    };
+    status::emit_status(event).await;
+}
+
+/// 停止服务并等待监控任务退出
+async fn shutdown(service_id: &str, entry: &ServiceEntry) {
+    let mut lifecycle = entry.lifecycle.lock().await;
+    lifecycle.ct.cancel();
+    match lifecycle.supervisor.take() {
+        Some(supervisor) => {
+            let _ = supervisor.await;
+        }
+        None => {
+            if entry.status() != Some(ServiceStatus::Stopped) {
+                set_status(service_id, &entry.state, ServiceStatus::Stopped, None).await;
+            }
+        }
+    }
+}
+
+/// 按配置建立连接，stdio 方式会同时返回子进程
+async fn connect(
+    service_id: &str,
+    config: &McpServerConfig,
+    env: Option<&HashMap<String, String>>,
+    ct: CancellationToken,
+    state: &SharedState,
+) -> Result<(Client, Option<Child>)> {
+    match config.transport {
+        TransportKind::Stdio => {
+            if config.command.trim().is_empty() {
+                anyhow::bail!("未配置启动命令");
+            }
+            let mut cmd = config.build_command(env);
+            cmd.kill_on_drop(true)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+            let mut child = cmd
+                .spawn()
+                .map_err(|e| anyhow::anyhow!("启动 {} 失败: {}", config.command, e))?;
+
+            if let Some(stderr) = child.stderr.take() {
+                tokio::spawn(capture_stderr(stderr, state.clone()));
+            }
+            let stdout = child
+                .stdout
+                .take()
+                .ok_or_else(|| anyhow::anyhow!("无法获取子进程输出"))?;
+            let stdin = child
+                .stdin
+                .take()
+                .ok_or_else(|| anyhow::anyhow!("无法获取子进程输入"))?;
+
+            let calls = state.lock().unwrap().calls.clone();
+            let service = GhostieClient::new(service_id, calls)
+                .serve_with_ct(transport::connect_stdio(stdout, stdin), ct)
+                .await?;
+            Ok((service, Some(child)))
+        }
+        TransportKind::Sse | TransportKind::StreamableHttp => {
+            let options = config
+                .remote_options()
+                .ok_or_else(|| anyhow::anyhow!("未配置服务地址"))?;
+            let transport = if config.transport == TransportKind::Sse {
+                transport::connect_sse(options).await
+            } else {
+                transport::connect_streamable_http(options).await
+            }
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "连接 {} 失败: {}",
+                    config.url.as_deref().unwrap_or_default(),
+                    e
+                )
+            })?;
+            let calls = state.lock().unwrap().calls.clone();
+            let service = GhostieClient::new(service_id, calls)
+                .serve_with_ct(transport, ct)
+                .await?;
+            Ok((service, None))
+        }
+    }
+}
+
+/// 把子进程的 stderr 逐行写入日志缓冲区
+async fn capture_stderr(stderr: ChildStderr, state: SharedState) {
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        state.lock().unwrap().logs.push("stderr", line);
+    }
+}
+
+/// 描述服务退出的原因
+async fn exit_reason(child: Option<&mut Child>) -> String {
+    let Some(child) = child else {
+        return "连接已断开".to_string();
+    };
+    match tokio::time::timeout(Duration::from_secs(2), child.wait()).await {
+        Ok(Ok(status)) => format!("进程已退出: {}", status),
+        _ => {
+            // 输出流关闭但进程还在，直接结束进程
+            let _ = child.kill().await;
+            "进程输出已关闭".to_string()
+        }
+    }
+}
+
+/// 监控服务运行，异常退出时按退避策略自动重启，直到服务被停止或重启次数用尽
+async fn supervise(
+    service_id: String,
+    config: McpServerConfig,
+    env: Option<HashMap<String, String>>,
+    state: SharedState,
+    ct: CancellationToken,
+    mut service: Client,
+    mut child: Option<Child>,
+) {
+    let policy = config.restart_policy();
+    let mut failures = 0;
+
+    loop {
+        let started = Instant::now();
+        let _ = service.waiting().await;
+
+        if ct.is_cancelled() {
+            drop(child);
+            set_status(&service_id, &state, ServiceStatus::Stopped, None).await;
+            return;
+        }
+
+        let reason = exit_reason(child.as_mut()).await;
+        tracing::warn!("服务 {} 异常退出: {}", service_id, reason);
+        set_status(&service_id, &state, ServiceStatus::Crashed, Some(reason)).await;
+        if started.elapsed() >= STABLE_DURATION {
+            failures = 0;
+        }
+
+        // 重启直到成功
+        loop {
+            failures += 1;
+            if failures > policy.max_attempts {
+                let message = format!("已连续重启 {} 次失败，不再自动重启", policy.max_attempts);
+                tracing::warn!("服务 {} {}", service_id, message);
+                set_status(&service_id, &state, ServiceStatus::Crashed, Some(message)).await;
+                return;
+            }
+
+            let delay = policy.backoff(failures);
+            tokio::select! {
+                _ = ct.cancelled() => {
+                    set_status(&service_id, &state, ServiceStatus::Stopped, None).await;
+                    return;
+                }
+                _ = tokio::time::sleep(delay) => {}
+            }
+
+            state.lock().unwrap().restarts += 1;
+            set_status(&service_id, &state, ServiceStatus::Starting, None).await;
+            match connect(&service_id, &config, env.as_ref(), ct.child_token(), &state).await {
+                Ok((restarted, restarted_child)) => {
+                    service = restarted;
+                    child = restarted_child;
+                    state.lock().unwrap().peer = Some(service.peer().clone());
+                    set_status(&service_id, &state, ServiceStatus::Running, None).await;
+                    tracing::info!("服务 {} 已重启", service_id);
+                    break;
+                }
+                Err(e) => {
+                    set_status(
+                        &service_id,
+                        &state,
+                        ServiceStatus::Crashed,
+                        Some(e.to_string()),
+                    )
+                    .await;
+                }
+            }
+        }
     }
 }
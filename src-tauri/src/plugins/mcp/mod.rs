@@ -1,76 +1,407 @@
+pub mod client;
+pub mod config;
+pub mod host;
 pub mod mcp;
+pub mod registry;
+pub mod result;
+pub mod status;
+pub mod store;
+pub mod transport;
+use crate::plugins::mcp::config::McpServerConfig;
 use crate::plugins::mcp::mcp::MCPManager;
+use crate::plugins::mcp::registry::{McpRegistry, McpServerEntry};
+use crate::plugins::mcp::result::ToolResult;
+use crate::plugins::mcp::status::ServiceSnapshot;
+use crate::plugins::mcp::store::ResolvedBlob;
 use anyhow::Result;
 use once_cell::sync::Lazy;
-use rmcp::model::{CallToolResult, Tool};
-use std::collections::HashMap;
+use rmcp::model::{GetPromptResult, Prompt, ReadResourceResult, Resource, ResourceTemplate, Tool};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use tauri::{AppHandle, Emitter};
 use tokio::sync::Mutex;
 
+/// 默认返回的日志行数
+const DEFAULT_LOG_LINES: usize = 100;
+
+static APP_HANDLE: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
 static MCP_MANAGER: Lazy<Mutex<Option<MCPManager>>> = Lazy::new(|| Mutex::new(None));
+static MCP_REGISTRY: Lazy<Mutex<Option<McpRegistry>>> = Lazy::new(|| Mutex::new(None));
 /// 初始化管理器
 pub async fn init() -> Result<()> {
     let mut plugin_manager = MCP_MANAGER.lock().await;
     if plugin_manager.is_none() {
         *plugin_manager = Some(MCPManager::new()?);
     }
+
+    let mut registry = MCP_REGISTRY.lock().await;
+    if registry.is_none() {
+        *registry = Some(McpRegistry::load()?);
+    }
     Ok(())
 }
 
+/// 取出管理器
+///
+/// 管理器内部自行加锁，复制出来后即释放全局锁，避免一个耗时的调用阻塞其他命令。
+async fn manager() -> Result<MCPManager, String> {
+    MCP_MANAGER
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| "MCP管理器未初始化".to_string())
+}
+
+/// 启动注册表中标记为自动启动的服务
+pub async fn autostart() {
+    let servers = match MCP_REGISTRY.lock().await.as_ref() {
+        Some(registry) => registry.autostart_servers(),
+        None => return,
+    };
+
+    let Ok(manager) = manager().await else {
+        return;
+    };
+    // 各服务并行启动，互不等待
+    let starts = servers.into_iter().map(|(name, entry)| {
+        let manager = manager.clone();
+        async move {
+            if let Err(e) = manager.start_service(&name, &entry.to_config(), None).await {
+                eprintln!("自动启动 MCP 服务 {} 失败: {}", name, e);
+            }
+        }
+    });
+    futures::future::join_all(starts).await;
+}
+
+/// 设置 AppHandle，用于发送服务状态事件
+pub async fn set_app_handle(handle: AppHandle) {
+    let mut app_handle = APP_HANDLE.lock().await;
+    *app_handle = Some(handle);
+}
+
+/// 获取 AppHandle
+pub async fn get_app_handle() -> Option<AppHandle> {
+    APP_HANDLE.lock().await.clone()
+}
+
+/// 向所有窗口广播事件
+pub async fn emit<S: Serialize + Clone>(event: &str, payload: S) {
+    if let Some(app) = get_app_handle().await {
+        if let Err(e) = app.emit(event, payload) {
+            eprintln!("Failed to emit {} event: {}", event, e);
+        }
+    }
+}
+
+/// 启动 MCP 服务
+///
+/// `id` 是用户自定义的服务名称；未提供 `config` 时优先使用注册表中的同名定义，
+/// 都没有时沿用旧行为，把 `id` 当作 npm 包名通过 `npx -y` 启动。
 #[tauri::command]
-pub async fn start_service(id: String, env: Option<HashMap<String, String>>) -> Result<(), String> {
-    let state = MCP_MANAGER.lock().await;
-    if let Some(manager) = state.as_ref() {
-        manager
-            .start_service(&id, env)
+pub async fn start_service(
+    id: String,
+    env: Option<HashMap<String, String>>,
+    config: Option<McpServerConfig>,
+) -> Result<(), String> {
+    let config = match config {
+        Some(config) => config,
+        None => MCP_REGISTRY
+            .lock()
             .await
-            .map_err(|e| e.to_string())
-    } else {
-        Err("MCP管理器未初始化".to_string())
-    }
+            .as_ref()
+            .and_then(|registry| registry.get(&id))
+            .map(|entry| entry.to_config())
+            .unwrap_or_else(|| McpServerConfig::npx(&id)),
+    };
+    let manager = manager().await?;
+    manager
+        .start_service(&id, &config, env)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn stop_service(id: String) -> Result<(), String> {
-    let state = MCP_MANAGER.lock().await;
-    if let Some(manager) = state.as_ref() {
-        manager.stop_service(&id).await.map_err(|e| e.to_string())
-    } else {
-        Err("MCP管理器未初始化".to_string())
-    }
+    let manager = manager().await?;
+    manager.stop_service(&id).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_service_info(id: String) -> Result<(String, Vec<Tool>), String> {
-    let state = MCP_MANAGER.lock().await;
-    if let Some(manager) = state.as_ref() {
-        manager
-            .get_service_info(&id)
-            .await
-            .map_err(|e| e.to_string())
-    } else {
-        Err("MCP管理器未初始化".to_string())
-    }
+    let manager = manager().await?;
+    manager
+        .get_service_info(&id)
+        .await
+        .map_err(|e| e.to_string())
 }
 
+/// 调用工具
+///
+/// `call_id` 由前端生成，用于 `cancel_tool_call` 和 `mcp-tool-progress` 进度事件，为空时自动生成；
+/// `timeout_ms` 为空时使用服务配置的超时时间。
+/// 结果中的图片和二进制资源保存到本地，以引用返回，通过 `resolve_blob` 获取内容。
+/// 调用前按审批策略检查，工具标识为 `mcp:{id}/{name}`。
 #[tauri::command]
 pub async fn call_tool(
     id: String,
     name: String,
     args: serde_json::Value,
-) -> Result<CallToolResult, String> {
-    let state = MCP_MANAGER.lock().await;
-    if let Some(manager) = state.as_ref() {
-        // 将args转换为Map
-        let arguments = match args {
-            serde_json::Value::Object(map) => map,
-            _ => return Err("参数必须是有效的JSON对象".to_string()),
-        };
-
-        manager
-            .call_tool(&id, &name, arguments)
-            .await
-            .map_err(|e| e.to_string())
-    } else {
-        Err("MCP管理器未初始化".to_string())
+    call_id: Option<String>,
+    timeout_ms: Option<u64>,
+) -> Result<ToolResult, String> {
+    crate::plugins::approval::check(&format!("mcp:{}/{}", id, name), &args).await?;
+    let manager = manager().await?;
+    // 将args转换为Map
+    let arguments = match args {
+        serde_json::Value::Object(map) => map,
+        _ => return Err("参数必须是有效的JSON对象".to_string()),
+    };
+    let call_id = call_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let timeout = timeout_ms.map(std::time::Duration::from_millis);
+
+    let result = manager
+        .call_tool(&id, &name, arguments, &call_id, timeout)
+        .await
+        .map_err(|e| e.to_string())?;
+    // 解码和写入文件较慢，放到阻塞线程中执行
+    tokio::task::spawn_blocking(move || result::normalize(result))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 解析工具结果中的内容引用
+///
+/// 默认只返回本地文件路径，`include_data` 为 true 时一并返回 base64 编码的内容。
+#[tauri::command]
+pub async fn resolve_blob(id: String, include_data: Option<bool>) -> Result<ResolvedBlob, String> {
+    tokio::task::spawn_blocking(move || store::resolve(&id, include_data.unwrap_or(false)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 取消进行中的工具调用，并向服务端发送取消通知
+#[tauri::command]
+pub async fn cancel_tool_call(
+    id: String,
+    call_id: String,
+    reason: Option<String>,
+) -> Result<(), String> {
+    let manager = manager().await?;
+    manager
+        .cancel_tool_call(&id, &call_id, reason)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取服务状态和最近的日志
+///
+/// 未指定 `id` 时返回所有服务，`lines` 为每个服务返回的日志行数。
+#[tauri::command]
+pub async fn get_service_status(
+    id: Option<String>,
+    lines: Option<usize>,
+) -> Result<Vec<ServiceSnapshot>, String> {
+    let manager = manager().await?;
+    manager
+        .service_snapshots(id.as_deref(), lines.unwrap_or(DEFAULT_LOG_LINES))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取注册表中的所有服务定义
+#[tauri::command]
+pub async fn mcp_server_list() -> Result<BTreeMap<String, McpServerEntry>, String> {
+    let registry = MCP_REGISTRY.lock().await;
+    match registry.as_ref() {
+        Some(registry) => Ok(registry.list().clone()),
+        None => Err("MCP注册表未初始化".to_string()),
+    }
+}
+
+/// 添加或修改服务定义
+///
+/// 修改名称时传入 `original_name`，正在运行的旧服务会被停止。
+#[tauri::command]
+pub async fn mcp_server_save(
+    name: String,
+    server: McpServerEntry,
+    original_name: Option<String>,
+) -> Result<(), String> {
+    {
+        let mut registry = MCP_REGISTRY.lock().await;
+        let registry = registry.as_mut().ok_or("MCP注册表未初始化")?;
+        registry
+            .upsert(&name, server, original_name.as_deref())
+            .map_err(|e| format!("{:#}", e))?;
+    }
+
+    if let Some(original) = original_name.filter(|original| *original != name.trim()) {
+        if let Ok(manager) = manager().await {
+            let _ = manager.stop_service(&original).await;
+        }
+    }
+    Ok(())
+}
+
+/// 删除服务定义，并停止正在运行的服务
+#[tauri::command]
+pub async fn mcp_server_remove(name: String) -> Result<(), String> {
+    {
+        let mut registry = MCP_REGISTRY.lock().await;
+        let registry = registry.as_mut().ok_or("MCP注册表未初始化")?;
+        if !registry.remove(&name).map_err(|e| format!("{:#}", e))? {
+            return Err(format!("服务 {} 不存在", name));
+        }
+    }
+
+    if let Ok(manager) = manager().await {
+        let _ = manager.stop_service(&name).await;
     }
+    Ok(())
+}
+
+/// 导入 `mcpServers` 格式的配置，返回导入的服务名称
+#[tauri::command]
+pub async fn mcp_server_import(
+    content: String,
+    overwrite: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let mut registry = MCP_REGISTRY.lock().await;
+    let registry = registry.as_mut().ok_or("MCP注册表未初始化")?;
+    registry
+        .import(&content, overwrite.unwrap_or(false))
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// 导出为 `mcpServers` 格式，未指定 `names` 时导出全部
+#[tauri::command]
+pub async fn mcp_server_export(names: Option<Vec<String>>) -> Result<String, String> {
+    let registry = MCP_REGISTRY.lock().await;
+    let registry = registry.as_ref().ok_or("MCP注册表未初始化")?;
+    registry
+        .export(names.as_deref())
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// 获取服务的资源列表
+#[tauri::command]
+pub async fn list_resources(id: String) -> Result<Vec<Resource>, String> {
+    let manager = manager().await?;
+    manager.list_resources(&id).await.map_err(|e| e.to_string())
+}
+
+/// 获取服务的资源模板列表
+#[tauri::command]
+pub async fn list_resource_templates(id: String) -> Result<Vec<ResourceTemplate>, String> {
+    let manager = manager().await?;
+    manager
+        .list_resource_templates(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 读取资源内容
+#[tauri::command]
+pub async fn read_resource(id: String, uri: String) -> Result<ReadResourceResult, String> {
+    let manager = manager().await?;
+    manager
+        .read_resource(&id, &uri)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 订阅资源更新，更新时通过 `mcp-notification` 事件通知
+#[tauri::command]
+pub async fn subscribe_resource(id: String, uri: String) -> Result<(), String> {
+    let manager = manager().await?;
+    manager
+        .subscribe_resource(&id, &uri)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 取消订阅资源
+#[tauri::command]
+pub async fn unsubscribe_resource(id: String, uri: String) -> Result<(), String> {
+    let manager = manager().await?;
+    manager
+        .unsubscribe_resource(&id, &uri)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取服务的提示词列表
+#[tauri::command]
+pub async fn list_prompts(id: String) -> Result<Vec<Prompt>, String> {
+    let manager = manager().await?;
+    manager.list_prompts(&id).await.map_err(|e| e.to_string())
+}
+
+/// 按参数获取提示词
+#[tauri::command]
+pub async fn get_prompt(
+    id: String,
+    name: String,
+    arguments: Option<serde_json::Value>,
+) -> Result<GetPromptResult, String> {
+    let arguments = match arguments {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::Object(map)) => Some(map),
+        Some(_) => return Err("参数必须是有效的JSON对象".to_string()),
+    };
+
+    let manager = manager().await?;
+    manager
+        .get_prompt(&id, &name, arguments)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 回复服务端发起的采样、根目录请求
+///
+/// `error` 不为空表示用户拒绝；采样请求的 `result` 为 `CreateMessageResult`，根目录请求为 `ListRootsResult`。
+#[tauri::command]
+pub async fn mcp_client_respond(
+    request_id: String,
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+) -> Result<(), String> {
+    client::respond(&request_id, result, error)
+}
+
+/// 启动本地 MCP 服务，把已保存的插件和知识库提供给其他客户端，返回服务地址和令牌
+///
+/// Streamable HTTP 地址为 `{url}/mcp`，旧版 SSE 地址为 `{url}/sse`，请求需携带 `Authorization: Bearer {token}`。
+#[tauri::command]
+pub async fn mcp_host_start(port: Option<u16>) -> Result<host::HostStatus, String> {
+    host::start_http(port.unwrap_or(host::DEFAULT_HOST_PORT))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 停止本地 MCP 服务
+#[tauri::command]
+pub async fn mcp_host_stop() -> Result<(), String> {
+    host::stop_http().await;
+    Ok(())
+}
+
+/// 本地 MCP 服务的地址和令牌，未运行时为空
+#[tauri::command]
+pub async fn mcp_host_status() -> Result<Option<host::HostStatus>, String> {
+    Ok(host::http_status().await)
+}
+
+/// 回复本地 MCP 服务发起的知识库查询
+///
+/// `list` 的 `result` 为知识库列表，`search` 为搜索结果；`error` 不为空表示查询失败。
+#[tauri::command]
+pub async fn mcp_host_respond(
+    request_id: String,
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+) -> Result<(), String> {
+    host::respond_knowledge(&request_id, result, error)
 }
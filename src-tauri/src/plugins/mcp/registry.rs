@@ -0,0 +1,281 @@
+use crate::plugins::chat::retry::RetryPolicy;
+use crate::plugins::mcp::config::{McpServerConfig, TransportKind};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+/// 注册表文件名，位于配置目录下
+const REGISTRY_FILE: &str = "mcp.json";
+
+/// 注册表中的一个服务定义
+///
+/// 字段与常见客户端的 `mcpServers` 配置保持一致，`autoStart`、`restart` 等为本应用的扩展字段，
+/// 无法识别的字段会原样保留，导出时一并写回。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, alias = "serverUrl", skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// 传输方式：`stdio`、`sse` 或 `http`（Streamable HTTP），缺省时根据其他字段推断
+    #[serde(
+        default,
+        rename = "type",
+        alias = "transport",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub kind: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub disabled: bool,
+    /// 应用启动时自动启动
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub auto_start: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconnect: Option<RetryPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<RetryPolicy>,
+    /// 工具调用超时（毫秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// 其他客户端写入的字段
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl McpServerEntry {
+    /// 统一大小写和分隔符后的传输方式，如 `streamable-http` 与 `streamableHttp` 都视为 `streamablehttp`
+    fn normalized_kind(&self) -> Option<String> {
+        self.kind
+            .as_deref()
+            .map(|kind| kind.trim().to_ascii_lowercase().replace(['-', '_'], ""))
+    }
+
+    /// 传输方式
+    pub fn transport(&self) -> TransportKind {
+        match self.normalized_kind().as_deref() {
+            Some("stdio") => TransportKind::Stdio,
+            Some("sse") => TransportKind::Sse,
+            Some("http" | "streamablehttp") => TransportKind::StreamableHttp,
+            _ => match self.url.as_deref() {
+                Some(url) if self.command.is_none() => {
+                    // 旧版 SSE 服务的地址通常以 /sse 结尾
+                    if url.trim_end_matches('/').ends_with("/sse") {
+                        TransportKind::Sse
+                    } else {
+                        TransportKind::StreamableHttp
+                    }
+                }
+                _ => TransportKind::Stdio,
+            },
+        }
+    }
+
+    /// 检查定义是否完整
+    pub fn validate(&self) -> Result<()> {
+        match self.transport() {
+            TransportKind::Stdio => {
+                if !matches!(self.command.as_deref(), Some(c) if !c.trim().is_empty()) {
+                    anyhow::bail!("缺少启动命令 command");
+                }
+            }
+            _ => {
+                if !matches!(self.url.as_deref(), Some(u) if !u.trim().is_empty()) {
+                    anyhow::bail!("缺少服务地址 url");
+                }
+            }
+        }
+        if let Some(kind) = self.normalized_kind() {
+            if !["stdio", "sse", "http", "streamablehttp"].contains(&kind.as_str()) {
+                anyhow::bail!(
+                    "不支持的传输方式: {}",
+                    self.kind.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// 转换为启动配置
+    pub fn to_config(&self) -> McpServerConfig {
+        McpServerConfig {
+            command: self.command.clone().unwrap_or_default(),
+            args: self.args.clone(),
+            cwd: self.cwd.clone(),
+            env: self.env.clone(),
+            transport: self.transport(),
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            reconnect: self.reconnect.clone(),
+            restart: self.restart.clone(),
+            timeout_ms: self.timeout,
+        }
+    }
+}
+
+/// 注册表文件的结构，保留文件中的其他字段
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryFile {
+    #[serde(rename = "mcpServers", default)]
+    mcp_servers: BTreeMap<String, McpServerEntry>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+/// 持久化的 MCP 服务注册表，保存在配置目录下的 `mcp.json`
+pub struct McpRegistry {
+    path: PathBuf,
+    file: RegistryFile,
+}
+
+impl McpRegistry {
+    /// 从配置目录加载，文件不存在时创建空注册表
+    pub fn load() -> Result<Self> {
+        let path = crate::utils::file::get_config_dir()
+            .context("无法获取配置目录")?
+            .join(REGISTRY_FILE);
+        let file = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("读取 {} 失败", path.display()))?;
+            if content.trim().is_empty() {
+                RegistryFile::default()
+            } else {
+                serde_json::from_str(&content)
+                    .with_context(|| format!("解析 {} 失败", path.display()))?
+            }
+        } else {
+            RegistryFile::default()
+        };
+        Ok(Self { path, file })
+    }
+
+    /// 写回文件，先写临时文件再替换，避免写入中断导致文件损坏
+    fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.file)?;
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, content).with_context(|| format!("写入 {} 失败", temp.display()))?;
+        fs::rename(&temp, &self.path)
+            .with_context(|| format!("写入 {} 失败", self.path.display()))?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> &BTreeMap<String, McpServerEntry> {
+        &self.file.mcp_servers
+    }
+
+    pub fn get(&self, name: &str) -> Option<&McpServerEntry> {
+        self.file.mcp_servers.get(name)
+    }
+
+    /// 添加或修改服务定义，`original_name` 不同于 `name` 时视为重命名
+    pub fn upsert(
+        &mut self,
+        name: &str,
+        entry: McpServerEntry,
+        original_name: Option<&str>,
+    ) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            anyhow::bail!("服务名称不能为空");
+        }
+        entry
+            .validate()
+            .with_context(|| format!("服务 {} 配置无效", name))?;
+
+        if let Some(original) = original_name.filter(|original| *original != name) {
+            if self.file.mcp_servers.contains_key(name) {
+                anyhow::bail!("服务 {} 已存在", name);
+            }
+            self.file.mcp_servers.remove(original);
+        }
+        self.file.mcp_servers.insert(name.to_string(), entry);
+        self.save()
+    }
+
+    /// 删除服务定义，返回是否存在
+    pub fn remove(&mut self, name: &str) -> Result<bool> {
+        let removed = self.file.mcp_servers.remove(name).is_some();
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// 导入其他客户端的配置
+    ///
+    /// 支持 `{"mcpServers": {...}}`、`{"servers": {...}}` 以及直接以服务名为键的对象。
+    /// 同名服务在 `overwrite` 为 false 时跳过。返回导入的服务名称。
+    pub fn import(&mut self, content: &str, overwrite: bool) -> Result<Vec<String>> {
+        let value: Value = serde_json::from_str(content).context("配置不是有效的 JSON")?;
+        let servers = value
+            .get("mcpServers")
+            .or_else(|| value.get("servers"))
+            .unwrap_or(&value)
+            .as_object()
+            .context("未找到 mcpServers 配置")?;
+
+        // 先全部解析校验，有错误时不写入任何服务
+        let mut entries = Vec::new();
+        for (name, server) in servers {
+            let entry: McpServerEntry = serde_json::from_value(server.clone())
+                .with_context(|| format!("服务 {} 配置无效", name))?;
+            entry
+                .validate()
+                .with_context(|| format!("服务 {} 配置无效", name))?;
+            entries.push((name.trim().to_string(), entry));
+        }
+
+        let mut imported = Vec::new();
+        for (name, entry) in entries {
+            if name.is_empty() || (!overwrite && self.file.mcp_servers.contains_key(&name)) {
+                continue;
+            }
+            self.file.mcp_servers.insert(name.clone(), entry);
+            imported.push(name);
+        }
+        if !imported.is_empty() {
+            self.save()?;
+        }
+        Ok(imported)
+    }
+
+    /// 导出为 `mcpServers` 格式，`names` 为空时导出全部
+    pub fn export(&self, names: Option<&[String]>) -> Result<String> {
+        let mcp_servers = self
+            .file
+            .mcp_servers
+            .iter()
+            .filter(|(name, _)| !matches!(names, Some(names) if !names.contains(name)))
+            .map(|(name, entry)| (name.clone(), entry.clone()))
+            .collect();
+        Ok(serde_json::to_string_pretty(&RegistryFile {
+            mcp_servers,
+            extra: Map::new(),
+        })?)
+    }
+
+    /// 需要在启动时自动运行的服务
+    pub fn autostart_servers(&self) -> Vec<(String, McpServerEntry)> {
+        self.file
+            .mcp_servers
+            .iter()
+            .filter(|(_, entry)| entry.auto_start && !entry.disabled)
+            .map(|(name, entry)| (name.clone(), entry.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,111 @@
+use crate::plugins::mcp::store::{self, BlobRef};
+use rmcp::model::{Annotations, CallToolResult, RawContent, ResourceContents};
+use serde::Serialize;
+
+/// 返回给前端的工具调用结果
+///
+/// 与 `CallToolResult` 结构一致，图片和二进制资源保存到本地存储后以引用代替原始内容。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolResult {
+    pub content: Vec<ToolContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_error: Option<bool>,
+}
+
+/// 结果中的一段内容
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolContent {
+    #[serde(flatten)]
+    pub raw: RawToolContent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RawToolContent {
+    Text {
+        text: String,
+    },
+    /// 图片，内容通过 `resolve_blob` 获取
+    Image {
+        blob: BlobRef,
+    },
+    /// 嵌入的资源，文本内容保留原文，二进制内容以引用代替
+    #[serde(rename_all = "camelCase")]
+    Resource {
+        uri: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        blob: Option<BlobRef>,
+    },
+}
+
+/// 保存失败时代替原内容的文本
+fn placeholder(what: &str, error: anyhow::Error) -> RawToolContent {
+    RawToolContent::Text {
+        text: format!("[{}无法保存: {}]", what, error),
+    }
+}
+
+/// 把二进制内容写入本地存储，转换为轻量的结果
+///
+/// 某段内容解码或写入失败时以说明文字代替，不影响其他内容和整个结果。
+pub fn normalize(result: CallToolResult) -> ToolResult {
+    let content = result
+        .content
+        .into_iter()
+        .map(|content| {
+            let raw = match content.raw {
+                RawContent::Text(text) => RawToolContent::Text { text: text.text },
+                RawContent::Image(image) => {
+                    match store::put_base64(&image.data, &image.mime_type) {
+                        Ok(blob) => RawToolContent::Image { blob },
+                        Err(e) => placeholder(&format!("图片（{}）", image.mime_type), e),
+                    }
+                }
+                RawContent::Resource(embedded) => match embedded.resource {
+                    ResourceContents::TextResourceContents {
+                        uri,
+                        mime_type,
+                        text,
+                    } => RawToolContent::Resource {
+                        uri,
+                        mime_type,
+                        text: Some(text),
+                        blob: None,
+                    },
+                    ResourceContents::BlobResourceContents {
+                        uri,
+                        mime_type,
+                        blob,
+                    } => match store::put_base64(
+                        &blob,
+                        mime_type.as_deref().unwrap_or("application/octet-stream"),
+                    ) {
+                        Ok(blob) => RawToolContent::Resource {
+                            uri,
+                            mime_type,
+                            text: None,
+                            blob: Some(blob),
+                        },
+                        Err(e) => placeholder(&format!("资源 {} ", uri), e),
+                    },
+                },
+            };
+            ToolContent {
+                raw,
+                annotations: content.annotations,
+            }
+        })
+        .collect();
+
+    ToolResult {
+        content,
+        is_error: result.is_error,
+    }
+}
@@ -0,0 +1,83 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// 每个服务最多保留的日志行数
+const MAX_LOG_LINES: usize = 500;
+
+/// 服务状态变化事件名
+pub const STATUS_EVENT: &str = "mcp-service-status";
+
+/// MCP 服务的运行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceStatus {
+    /// 正在启动或重启
+    Starting,
+    /// 正常运行
+    Running,
+    /// 异常退出
+    Crashed,
+    /// 已停止
+    Stopped,
+}
+
+/// 一行服务日志
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    /// 记录时间（RFC 3339）
+    pub time: String,
+    /// 来源：`stderr` 为子进程输出，`system` 为管理器记录的生命周期信息
+    pub source: &'static str,
+    pub line: String,
+}
+
+/// 固定容量的日志缓冲区，超出容量时丢弃最早的记录
+#[derive(Debug, Default)]
+pub struct LogBuffer {
+    lines: VecDeque<LogLine>,
+}
+
+impl LogBuffer {
+    pub fn push(&mut self, source: &'static str, line: impl Into<String>) {
+        if self.lines.len() >= MAX_LOG_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(LogLine {
+            time: chrono::Local::now().to_rfc3339(),
+            source,
+            line: line.into(),
+        });
+    }
+
+    /// 最近的 `limit` 行日志
+    pub fn recent(&self, limit: usize) -> Vec<LogLine> {
+        let skip = self.lines.len().saturating_sub(limit);
+        self.lines.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// 发送给前端的状态变化事件
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatusEvent {
+    pub id: String,
+    pub status: ServiceStatus,
+    /// 附加信息，如崩溃原因
+    pub message: Option<String>,
+    /// 自动重启的次数
+    pub restarts: u32,
+}
+
+/// 服务的状态快照，包含最近的日志
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceSnapshot {
+    pub id: String,
+    pub status: ServiceStatus,
+    pub message: Option<String>,
+    pub restarts: u32,
+    pub logs: Vec<LogLine>,
+}
+
+/// 广播状态变化
+pub async fn emit_status(event: ServiceStatusEvent) {
+    super::emit(STATUS_EVENT, event).await;
+}
@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+
+/// 存储目录名，位于配置目录下
+const STORE_DIR: &str = "blobs";
+
+/// 常见类型与扩展名，扩展名便于资源协议按类型返回文件
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("image/svg+xml", "svg"),
+    ("image/bmp", "bmp"),
+    ("audio/mpeg", "mp3"),
+    ("audio/wav", "wav"),
+    ("video/mp4", "mp4"),
+    ("application/pdf", "pdf"),
+    ("application/zip", "zip"),
+    ("application/json", "json"),
+    ("text/plain", "txt"),
+];
+
+/// 存储中一段内容的引用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobRef {
+    /// `{sha256}.{扩展名}`
+    pub id: String,
+    pub mime_type: String,
+    /// 字节数
+    pub size: u64,
+}
+
+/// 解析后的内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedBlob {
+    pub id: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// 本地文件路径，前端可通过 `convertFileSrc` 转为资源地址
+    pub path: String,
+    /// base64 编码的内容，仅在请求时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+fn store_dir() -> Result<PathBuf> {
+    let dir = crate::utils::file::get_config_dir()
+        .context("无法获取配置目录")?
+        .join(STORE_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("创建 {} 失败", dir.display()))?;
+    Ok(dir)
+}
+
+fn extension(mime_type: &str) -> &'static str {
+    EXTENSIONS
+        .iter()
+        .find(|(mime, _)| mime.eq_ignore_ascii_case(mime_type.trim()))
+        .map(|(_, ext)| *ext)
+        .unwrap_or("bin")
+}
+
+fn mime_type(extension: &str) -> &'static str {
+    EXTENSIONS
+        .iter()
+        .find(|(_, ext)| *ext == extension)
+        .map(|(mime, _)| *mime)
+        .unwrap_or("application/octet-stream")
+}
+
+/// 引用对应的文件路径，按哈希前两位分目录
+fn blob_path(id: &str) -> Result<PathBuf> {
+    let (hash, ext) = id.split_once('.').unwrap_or((id, ""));
+    let valid = hash.len() == 64
+        && hash.bytes().all(|b| b.is_ascii_hexdigit())
+        && !ext.is_empty()
+        && ext.bytes().all(|b| b.is_ascii_alphanumeric());
+    if !valid {
+        anyhow::bail!("无效的内容引用: {}", id);
+    }
+    Ok(store_dir()?.join(&hash[..2]).join(id))
+}
+
+/// 保存内容，相同内容只保存一份
+pub fn put(data: &[u8], mime_type: &str) -> Result<BlobRef> {
+    let hash = format!("{:x}", Sha256::digest(data));
+    let id = format!("{}.{}", hash, extension(mime_type));
+    let path = blob_path(&id)?;
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // 先写临时文件再替换，避免留下不完整的文件
+        let temp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&temp, data).with_context(|| format!("写入 {} 失败", temp.display()))?;
+        fs::rename(&temp, &path).with_context(|| format!("写入 {} 失败", path.display()))?;
+    }
+    Ok(BlobRef {
+        id,
+        mime_type: mime_type.to_string(),
+        size: data.len() as u64,
+    })
+}
+
+/// 保存 base64 编码的内容
+pub fn put_base64(data: &str, mime_type: &str) -> Result<BlobRef> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .context("内容不是有效的 base64")?;
+    put(&bytes, mime_type)
+}
+
+/// 解析引用，`include_data` 为 true 时一并返回 base64 编码的内容
+pub fn resolve(id: &str, include_data: bool) -> Result<ResolvedBlob> {
+    let path = blob_path(id)?;
+    let metadata = fs::metadata(&path).with_context(|| format!("内容 {} 不存在", id))?;
+    let data = if include_data {
+        let bytes = fs::read(&path).with_context(|| format!("读取 {} 失败", path.display()))?;
+        Some(base64::engine::general_purpose::STANDARD.encode(bytes))
+    } else {
+        None
+    };
+    let ext = id.rsplit('.').next().unwrap_or_default();
+    Ok(ResolvedBlob {
+        id: id.to_string(),
+        mime_type: mime_type(ext).to_string(),
+        size: metadata.len(),
+        path: path.to_string_lossy().to_string(),
+        data,
+    })
+}
@@ -0,0 +1,839 @@
+use crate::plugins::chat::retry::RetryPolicy;
+use crate::plugins::chat::sse::{SseDecoder, SseEvent};
+use futures::channel::mpsc;
+use futures::future::BoxFuture;
+use futures::{FutureExt, Sink, Stream, StreamExt};
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE};
+use reqwest::{Response, StatusCode, Url};
+use rmcp::model::{
+    ClientJsonRpcMessage, ClientNotification, ErrorData, JsonRpcError, JsonRpcMessage,
+    JsonRpcVersion2_0, RequestId, ServerJsonRpcMessage,
+};
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+const EVENT_STREAM: &str = "text/event-stream";
+const SESSION_ID_HEADER: &str = "mcp-session-id";
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+/// 等待 SSE 服务返回消息端点的最长时间
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 远程 MCP 服务共用的 HTTP 客户端，不设置整体超时，避免长连接被中断
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(10))
+        .tcp_keepalive(Some(Duration::from_secs(60)))
+        .build()
+        .expect("Failed to create HTTP client")
+});
+
+/// 远程 MCP 服务的连接参数
+#[derive(Debug, Clone)]
+pub struct RemoteOptions {
+    /// 服务地址：SSE 方式为事件流地址，Streamable HTTP 方式为 MCP 端点
+    pub url: String,
+    /// 每个请求都会附带的请求头，如 `Authorization: Bearer ...`
+    pub headers: HashMap<String, String>,
+    /// 断线重连策略
+    pub reconnect: RetryPolicy,
+}
+
+/// 基于通道的传输
+///
+/// 同时实现了 `Sink` 和 `Stream`，可以直接交给 rmcp 的 `serve`。
+/// 实际的读写由后台任务完成，通道被丢弃时后台任务随之退出。
+pub struct ChannelTransport {
+    tx: mpsc::UnboundedSender<ClientJsonRpcMessage>,
+    rx: mpsc::UnboundedReceiver<ServerJsonRpcMessage>,
+}
+
+impl Stream for ChannelTransport {
+    type Item = ServerJsonRpcMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_next_unpin(cx)
+    }
+}
+
+impl Sink<ClientJsonRpcMessage> for ChannelTransport {
+    type Error = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.tx).poll_ready(cx).map_err(closed)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: ClientJsonRpcMessage) -> Result<(), Self::Error> {
+        Pin::new(&mut self.tx).start_send(item).map_err(closed)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.tx).poll_flush(cx).map_err(closed)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.tx).poll_close(cx).map_err(closed)
+    }
+}
+
+fn closed(_: mpsc::SendError) -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "传输通道已关闭")
+}
+
+fn other_error(e: impl std::fmt::Display) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// 将用户配置的请求头转换为 HeaderMap
+fn header_map(headers: &HashMap<String, String>) -> io::Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (key, value) in headers {
+        let name = HeaderName::from_bytes(key.trim().as_bytes())
+            .map_err(|e| other_error(format!("无效的请求头 {}: {}", key, e)))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|e| other_error(format!("无效的请求头 {}: {}", key, e)))?;
+        map.insert(name, value);
+    }
+    Ok(map)
+}
+
+fn parse_url(url: &str) -> io::Result<Url> {
+    Url::parse(url.trim()).map_err(|e| other_error(format!("无效的服务地址 {}: {}", url, e)))
+}
+
+fn is_event_stream(response: &Response) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(EVENT_STREAM))
+}
+
+/// 解析服务端发来的 JSON-RPC 消息（支持批量）并转发，返回转发的消息
+fn forward(data: &str, in_tx: &mpsc::UnboundedSender<ServerJsonRpcMessage>) -> Vec<RequestId> {
+    let value: serde_json::Value = match serde_json::from_str(data) {
+        Ok(value) => value,
+        Err(e) => {
+            tracing::warn!("无法解析 MCP 消息: {}", e);
+            return Vec::new();
+        }
+    };
+    let values = match value {
+        serde_json::Value::Array(values) => values,
+        value => vec![value],
+    };
+
+    let mut answered = Vec::new();
+    for value in values {
+        match serde_json::from_value::<ServerJsonRpcMessage>(value) {
+            Ok(message) => {
+                match &message {
+                    JsonRpcMessage::Response(response) => answered.push(response.id.clone()),
+                    JsonRpcMessage::Error(error) => answered.push(error.id.clone()),
+                    _ => {}
+                }
+                let _ = in_tx.unbounded_send(message);
+            }
+            Err(e) => tracing::warn!("无法识别的 MCP 消息: {}", e),
+        }
+    }
+    answered
+}
+
+/// 序列化客户端消息
+///
+/// rmcp 的 `CallToolRequestParam` 不支持 `_meta`，这里为每个 `tools/call` 请求补上
+/// `progressToken`（取请求 id），服务端才会发送进度通知。
+fn encode(message: &ClientJsonRpcMessage) -> serde_json::Value {
+    let mut value = serde_json::to_value(message).unwrap_or_default();
+    if value.get("method").and_then(|m| m.as_str()) == Some("tools/call") {
+        let id = value.get("id").cloned();
+        if let (Some(id), Some(params)) = (id, value.get_mut("params")) {
+            if let Some(params) = params.as_object_mut() {
+                let meta = params
+                    .entry("_meta")
+                    .or_insert_with(|| serde_json::json!({}));
+                if let Some(meta) = meta.as_object_mut() {
+                    meta.entry("progressToken").or_insert(id);
+                }
+            }
+        }
+    }
+    value
+}
+
+/// 请求发送失败时，给调用方回一个错误响应，避免调用一直挂起
+fn fail_request(
+    message: &ClientJsonRpcMessage,
+    reason: &str,
+    in_tx: &mpsc::UnboundedSender<ServerJsonRpcMessage>,
+) {
+    tracing::warn!("MCP 消息发送失败: {}", reason);
+    if let JsonRpcMessage::Request(request) = message {
+        let _ = in_tx.unbounded_send(ServerJsonRpcMessage::Error(JsonRpcError {
+            jsonrpc: JsonRpcVersion2_0,
+            id: request.id.clone(),
+            error: ErrorData::internal_error(reason.to_string(), None),
+        }));
+    }
+}
+
+/// 打开事件流的方法，参数为 Last-Event-ID；返回 None 表示服务端不支持
+type OpenStream =
+    Box<dyn Fn(Option<String>) -> BoxFuture<'static, io::Result<Option<Response>>> + Send>;
+
+/// 持续读取事件流，断开后按策略重连
+///
+/// `handle` 返回 false 时停止读取。连续重连失败次数达到上限后放弃。
+async fn listen<H>(
+    mut response: Option<Response>,
+    open: OpenStream,
+    reconnect: RetryPolicy,
+    ct: CancellationToken,
+    mut handle: H,
+) where
+    H: FnMut(SseEvent) -> bool + Send,
+{
+    let max_attempts = reconnect.max_attempts.max(1);
+    let mut failures = 0;
+    let mut last_event_id: Option<String> = None;
+    let mut server_retry: Option<Duration> = None;
+
+    loop {
+        let current = match response.take() {
+            Some(response) => Some(response),
+            None => {
+                let opened = tokio::select! {
+                    _ = ct.cancelled() => return,
+                    opened = open(last_event_id.clone()) => opened,
+                };
+                match opened {
+                    Ok(Some(response)) => Some(response),
+                    Ok(None) => return,
+                    Err(e) => {
+                        tracing::warn!("MCP 事件流连接失败: {}", e);
+                        None
+                    }
+                }
+            }
+        };
+
+        if let Some(current) = current {
+            let mut stream = current.bytes_stream();
+            let mut decoder = SseDecoder::new();
+            loop {
+                let chunk = tokio::select! {
+                    _ = ct.cancelled() => return,
+                    chunk = stream.next() => chunk,
+                };
+                let chunk = match chunk {
+                    Some(Ok(chunk)) => chunk,
+                    Some(Err(e)) => {
+                        tracing::warn!("MCP 事件流中断: {}", e);
+                        break;
+                    }
+                    None => break,
+                };
+                for event in decoder.feed(&chunk) {
+                    // 收到过事件说明连接是可用的，重新计算失败次数
+                    failures = 0;
+                    if event.id.is_some() {
+                        last_event_id = event.id.clone();
+                    }
+                    if let Some(retry) = event.retry {
+                        server_retry = Some(Duration::from_millis(retry));
+                    }
+                    if !handle(event) {
+                        return;
+                    }
+                }
+            }
+            if let Some(event) = decoder.finish() {
+                if !handle(event) {
+                    return;
+                }
+            }
+        }
+
+        failures += 1;
+        if failures >= max_attempts {
+            tracing::warn!("MCP 事件流重连 {} 次失败，放弃重连", failures);
+            return;
+        }
+        let delay = server_retry.unwrap_or_else(|| reconnect.backoff(failures));
+        tracing::info!("{}ms 后重连 MCP 事件流", delay.as_millis());
+        tokio::select! {
+            _ = ct.cancelled() => return,
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
+
+/// 通过子进程的标准输入输出通信
+///
+/// 与 rmcp 自带的 stdio 传输一样按行收发 JSON，区别是发送前经过 [`encode`] 处理。
+pub fn connect_stdio<R, W>(reader: R, writer: W) -> ChannelTransport
+where
+    R: AsyncRead + Send + Unpin + 'static,
+    W: AsyncWrite + Send + Unpin + 'static,
+{
+    let (out_tx, mut out_rx) = mpsc::unbounded::<ClientJsonRpcMessage>();
+    let (in_tx, in_rx) = mpsc::unbounded::<ServerJsonRpcMessage>();
+
+    tokio::spawn(async move {
+        let mut writer = writer;
+        while let Some(message) = out_rx.next().await {
+            let line = format!("{}\n", encode(&message));
+            if writer.write_all(line.as_bytes()).await.is_err() || writer.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+            forward(&line, &in_tx);
+            if in_tx.is_closed() {
+                break;
+            }
+        }
+        // 输出关闭时结束输入流，rmcp 会据此结束服务
+        in_tx.close_channel();
+    });
+
+    ChannelTransport {
+        tx: out_tx,
+        rx: in_rx,
+    }
+}
+
+/// 连接旧版 SSE 传输的 MCP 服务
+///
+/// 先通过 GET 建立事件流，等待服务端的 `endpoint` 事件得到消息地址，之后客户端消息通过 POST 发送，
+/// 服务端消息从事件流返回。事件流断开后会携带 Last-Event-ID 自动重连。
+pub async fn connect_sse(options: RemoteOptions) -> io::Result<ChannelTransport> {
+    let url = parse_url(&options.url)?;
+    let headers = header_map(&options.headers)?;
+
+    let open: OpenStream = {
+        let url = url.clone();
+        let headers = headers.clone();
+        Box::new(move |last_event_id| {
+            let mut request = HTTP_CLIENT
+                .get(url.clone())
+                .headers(headers.clone())
+                .header(ACCEPT, EVENT_STREAM);
+            if let Some(id) = last_event_id {
+                request = request.header(LAST_EVENT_ID_HEADER, id);
+            }
+            async move {
+                let response = request.send().await.map_err(other_error)?;
+                let response = response.error_for_status().map_err(other_error)?;
+                if !is_event_stream(&response) {
+                    return Err(other_error("服务端返回的不是事件流"));
+                }
+                Ok(Some(response))
+            }
+            .boxed()
+        })
+    };
+
+    // 首次连接失败直接返回错误
+    let first = open(None)
+        .await?
+        .ok_or_else(|| other_error("服务端不支持事件流"))?;
+
+    let (out_tx, mut out_rx) = mpsc::unbounded::<ClientJsonRpcMessage>();
+    let (in_tx, in_rx) = mpsc::unbounded::<ServerJsonRpcMessage>();
+    let (endpoint_tx, mut endpoint_rx) = watch::channel(None::<Url>);
+    let ct = CancellationToken::new();
+
+    {
+        let in_tx = in_tx.clone();
+        let ct = ct.clone();
+        let reader_ct = ct.clone();
+        tokio::spawn(async move {
+            listen(Some(first), open, options.reconnect, reader_ct, |event| {
+                match event.event.as_deref() {
+                    Some("endpoint") => match url.join(event.data.trim()) {
+                        Ok(endpoint) => {
+                            let _ = endpoint_tx.send(Some(endpoint));
+                        }
+                        Err(e) => tracing::warn!("无效的消息端点 {}: {}", event.data, e),
+                    },
+                    None | Some("message") => {
+                        forward(&event.data, &in_tx);
+                    }
+                    _ => {}
+                }
+                !in_tx.is_closed()
+            })
+            .await;
+            // 读取结束时关闭输入流，rmcp 会据此结束服务
+            in_tx.close_channel();
+            ct.cancel();
+        });
+    }
+
+    tokio::time::timeout(ENDPOINT_TIMEOUT, endpoint_rx.wait_for(|e| e.is_some()))
+        .await
+        .map_err(|_| other_error("等待服务端返回消息端点超时"))?
+        .map_err(|_| other_error("事件流已关闭，未收到消息端点"))?;
+
+    tokio::spawn(async move {
+        loop {
+            let message = tokio::select! {
+                _ = ct.cancelled() => break,
+                message = out_rx.next() => match message {
+                    Some(message) => message,
+                    None => break,
+                },
+            };
+            // 重连后服务端可能下发新的端点，每次发送前重新读取
+            let Some(endpoint) = endpoint_rx.borrow().clone() else {
+                fail_request(&message, "消息端点不可用", &in_tx);
+                continue;
+            };
+            let result = HTTP_CLIENT
+                .post(endpoint)
+                .headers(headers.clone())
+                .json(&encode(&message))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                fail_request(&message, &e.to_string(), &in_tx);
+            }
+        }
+        ct.cancel();
+    });
+
+    Ok(ChannelTransport {
+        tx: out_tx,
+        rx: in_rx,
+    })
+}
+
+/// Streamable HTTP 会话状态
+struct HttpSession {
+    url: Url,
+    headers: HeaderMap,
+    /// 服务端在初始化响应中分配的会话 id
+    session_id: Mutex<Option<String>>,
+}
+
+impl HttpSession {
+    fn request(&self, method: reqwest::Method) -> reqwest::RequestBuilder {
+        let mut request = HTTP_CLIENT
+            .request(method, self.url.clone())
+            .headers(self.headers.clone());
+        if let Some(id) = self.session_id.lock().unwrap().as_ref() {
+            request = request.header(SESSION_ID_HEADER, id);
+        }
+        request
+    }
+
+    fn update_session(&self, response: &Response) {
+        if let Some(id) = response
+            .headers()
+            .get(SESSION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session_id.lock().unwrap() = Some(id.to_string());
+        }
+    }
+
+    fn has_session(&self) -> bool {
+        self.session_id.lock().unwrap().is_some()
+    }
+}
+
+/// 连接 Streamable HTTP 传输的 MCP 服务
+///
+/// 每条客户端消息都 POST 到同一个端点，服务端以 JSON 或事件流返回结果；
+/// 初始化完成后再通过 GET 打开一条事件流接收服务端主动推送的消息，断开后自动重连。
+/// 服务关闭时发送 DELETE 结束会话。
+pub async fn connect_streamable_http(options: RemoteOptions) -> io::Result<ChannelTransport> {
+    let session = Arc::new(HttpSession {
+        url: parse_url(&options.url)?,
+        headers: header_map(&options.headers)?,
+        session_id: Mutex::new(None),
+    });
+
+    let (out_tx, mut out_rx) = mpsc::unbounded::<ClientJsonRpcMessage>();
+    let (in_tx, in_rx) = mpsc::unbounded::<ServerJsonRpcMessage>();
+    let ct = CancellationToken::new();
+
+    tokio::spawn(async move {
+        let mut listening = false;
+        loop {
+            let message = tokio::select! {
+                _ = ct.cancelled() => break,
+                message = out_rx.next() => match message {
+                    Some(message) => message,
+                    None => break,
+                },
+            };
+
+            let initialized = matches!(
+                &message,
+                JsonRpcMessage::Notification(notification)
+                    if matches!(notification.notification, ClientNotification::InitializedNotification(_))
+            );
+
+            // 初始化完成后打开服务端推送通道
+            let open_listener = initialized && !listening;
+            listening |= open_listener;
+
+            // 每个 POST 在独立任务中发送，慢请求不会阻塞后续消息（例如取消通知）
+            let session = session.clone();
+            let reconnect = options.reconnect.clone();
+            let in_tx = in_tx.clone();
+            let ct = ct.clone();
+            tokio::spawn(async move {
+                let had_session = session.has_session();
+                let result = session
+                    .request(reqwest::Method::POST)
+                    .header(ACCEPT, format!("application/json, {}", EVENT_STREAM))
+                    .json(&encode(&message))
+                    .send()
+                    .await;
+                match result {
+                    Ok(response) => {
+                        handle_post_response(response, &message, had_session, &session, &in_tx, &ct)
+                            .await
+                    }
+                    Err(e) => fail_request(&message, &e.to_string(), &in_tx),
+                }
+
+                if open_listener {
+                    listen_server_messages(session, reconnect, in_tx, ct).await;
+                }
+            });
+        }
+        ct.cancel();
+
+        // 通知服务端结束会话，失败也无妨
+        if session.has_session() {
+            let _ = session
+                .request(reqwest::Method::DELETE)
+                .timeout(Duration::from_secs(5))
+                .send()
+                .await;
+        }
+    });
+
+    Ok(ChannelTransport {
+        tx: out_tx,
+        rx: in_rx,
+    })
+}
+
+/// 处理 POST 的响应：JSON 直接转发，事件流在后台读取
+async fn handle_post_response(
+    response: Response,
+    message: &ClientJsonRpcMessage,
+    had_session: bool,
+    session: &HttpSession,
+    in_tx: &mpsc::UnboundedSender<ServerJsonRpcMessage>,
+    ct: &CancellationToken,
+) {
+    session.update_session(&response);
+    let status = response.status();
+
+    if status == StatusCode::NOT_FOUND && had_session {
+        fail_request(message, "MCP 会话已失效，请重启服务", in_tx);
+        return;
+    }
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        fail_request(message, &format!("请求失败: {} - {}", status, text), in_tx);
+        return;
+    }
+    if status == StatusCode::ACCEPTED || status == StatusCode::NO_CONTENT {
+        return;
+    }
+
+    let request_id = match message {
+        JsonRpcMessage::Request(request) => Some(request.id.clone()),
+        _ => None,
+    };
+
+    if is_event_stream(&response) {
+        // 长时间运行的工具会持续推送进度，不能阻塞后续消息的发送
+        let message = message.clone();
+        let in_tx = in_tx.clone();
+        let ct = ct.clone();
+        tokio::spawn(async move {
+            let mut answered = false;
+            let mut stream = response.bytes_stream();
+            let mut decoder = SseDecoder::new();
+            loop {
+                let chunk = tokio::select! {
+                    _ = ct.cancelled() => return,
+                    chunk = stream.next() => chunk,
+                };
+                let events = match chunk {
+                    Some(Ok(chunk)) => decoder.feed(&chunk),
+                    Some(Err(e)) => {
+                        tracing::warn!("MCP 响应流中断: {}", e);
+                        break;
+                    }
+                    None => {
+                        let events: Vec<SseEvent> = decoder.finish().into_iter().collect();
+                        if events.is_empty() {
+                            break;
+                        }
+                        events
+                    }
+                };
+                for event in events {
+                    if matches!(event.event.as_deref(), None | Some("message")) {
+                        let ids = forward(&event.data, &in_tx);
+                        answered |= request_id.as_ref().is_some_and(|id| ids.contains(id));
+                    }
+                }
+            }
+            if !answered {
+                fail_request(&message, "响应流在返回结果前中断", &in_tx);
+            }
+        });
+        return;
+    }
+
+    match response.text().await {
+        Ok(text) if !text.trim().is_empty() => {
+            let ids = forward(&text, in_tx);
+            if request_id.as_ref().is_some_and(|id| !ids.contains(id)) {
+                fail_request(message, "响应中没有对应的结果", in_tx);
+            }
+        }
+        Ok(_) => {
+            if request_id.is_some() {
+                fail_request(message, "响应为空", in_tx);
+            }
+        }
+        Err(e) => fail_request(message, &e.to_string(), in_tx),
+    }
+}
+
+/// 通过 GET 事件流接收服务端主动推送的消息（通知、采样请求等）
+async fn listen_server_messages(
+    session: Arc<HttpSession>,
+    reconnect: RetryPolicy,
+    in_tx: mpsc::UnboundedSender<ServerJsonRpcMessage>,
+    ct: CancellationToken,
+) {
+    let open: OpenStream = {
+        let session = session.clone();
+        Box::new(move |last_event_id| {
+            let mut request = session
+                .request(reqwest::Method::GET)
+                .header(ACCEPT, EVENT_STREAM);
+            if let Some(id) = last_event_id {
+                request = request.header(LAST_EVENT_ID_HEADER, id);
+            }
+            async move {
+                let response = request.send().await.map_err(other_error)?;
+                // 服务端可以不提供推送通道
+                if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+                    return Ok(None);
+                }
+                let response = response.error_for_status().map_err(other_error)?;
+                if !is_event_stream(&response) {
+                    return Ok(None);
+                }
+                Ok(Some(response))
+            }
+            .boxed()
+        })
+    };
+
+    listen(None, open, reconnect, ct, |event| {
+        if matches!(event.event.as_deref(), None | Some("message")) {
+            forward(&event.data, &in_tx);
+        }
+        !in_tx.is_closed()
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::SinkExt;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// 接受一个连接并读完请求，返回小写的请求头和连接
+    async fn accept(listener: &TcpListener) -> (String, TcpStream) {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_lowercase();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length {
+                    return (text, socket);
+                }
+            }
+            assert!(n > 0, "请求未读完连接就关闭了");
+        }
+    }
+
+    async fn respond(socket: &mut TcpStream, head: &str, body: &str) {
+        let response = format!("HTTP/1.1 {}\r\nConnection: close\r\n\r\n{}", head, body);
+        socket.write_all(response.as_bytes()).await.unwrap();
+        socket.flush().await.unwrap();
+    }
+
+    fn options(url: String) -> RemoteOptions {
+        RemoteOptions {
+            url,
+            headers: HashMap::from([("Authorization".to_string(), "Bearer t".to_string())]),
+            reconnect: RetryPolicy {
+                max_attempts: 3,
+                initial_delay_ms: 10,
+                max_delay_ms: 10,
+                multiplier: 1.0,
+                jitter: false,
+            },
+        }
+    }
+
+    fn ping(id: u64) -> ClientJsonRpcMessage {
+        serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "ping",
+        }))
+        .unwrap()
+    }
+
+    const LIST_CHANGED: &str =
+        "{\"jsonrpc\":\"2.0\",\"method\":\"notifications/tools/list_changed\"}";
+
+    #[tokio::test]
+    async fn sse_reconnects_with_last_event_id() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sse", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            // 第一次连接下发端点和一条带 id 的消息后断开
+            let (request, mut socket) = accept(&listener).await;
+            assert!(request.starts_with("get /sse "));
+            assert!(request.contains("authorization: bearer t"));
+            assert!(!request.contains("last-event-id"));
+            let body = format!(
+                "event: endpoint\ndata: /messages?s=1\n\nid: 7\ndata: {}\n\n",
+                LIST_CHANGED
+            );
+            respond(
+                &mut socket,
+                "200 OK\r\nContent-Type: text/event-stream",
+                &body,
+            )
+            .await;
+            drop(socket);
+
+            // 重连时携带配置的请求头和最后的事件 id
+            let (request, mut stream) = accept(&listener).await;
+            assert!(request.starts_with("get /sse "));
+            assert!(request.contains("authorization: bearer t"));
+            assert!(request.contains("last-event-id: 7"));
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n";
+            stream.write_all(head.as_bytes()).await.unwrap();
+            let event = format!("id: 8\ndata: {}\n\n", LIST_CHANGED);
+            stream.write_all(event.as_bytes()).await.unwrap();
+
+            // 客户端消息发送到端点
+            let (request, mut socket) = accept(&listener).await;
+            assert!(request.starts_with("post /messages?s=1 "));
+            assert!(request.contains("authorization: bearer t"));
+            assert!(request.contains("\"method\":\"ping\""));
+            respond(&mut socket, "202 Accepted\r\nContent-Length: 0", "").await;
+            stream
+        });
+
+        let mut transport = connect_sse(options(url)).await.unwrap();
+        for _ in 0..2 {
+            let message = tokio::time::timeout(Duration::from_secs(5), transport.next())
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(matches!(message, JsonRpcMessage::Notification(_)));
+        }
+        transport.send(ping(1)).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn streamable_http_sends_session_id() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            // 第一个请求分配会话 id，以事件流返回结果
+            let (request, mut socket) = accept(&listener).await;
+            assert!(request.starts_with("post /mcp "));
+            assert!(request.contains("authorization: bearer t"));
+            assert!(!request.contains("mcp-session-id"));
+            let body = "data: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n\n";
+            respond(
+                &mut socket,
+                "200 OK\r\nContent-Type: text/event-stream\r\nMcp-Session-Id: abc",
+                body,
+            )
+            .await;
+
+            // 之后的请求带上会话 id，以 JSON 返回结果
+            let (request, mut socket) = accept(&listener).await;
+            assert!(request.contains("authorization: bearer t"));
+            assert!(request.contains("mcp-session-id: abc"));
+            let body = "{\"jsonrpc\":\"2.0\",\"id\":2,\"result\":{}}";
+            respond(
+                &mut socket,
+                "200 OK\r\nContent-Type: application/json",
+                body,
+            )
+            .await;
+        });
+
+        let mut transport = connect_streamable_http(options(url)).await.unwrap();
+        for id in 1..=2 {
+            transport.send(ping(id)).await.unwrap();
+            let message = tokio::time::timeout(Duration::from_secs(5), transport.next())
+                .await
+                .unwrap()
+                .unwrap();
+            let JsonRpcMessage::Response(response) = message else {
+                panic!("应收到响应: {:?}", message);
+            };
+            assert_eq!(response.id, RequestId::Number(id as u32));
+        }
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::plugins::node::error::{PluginError, Result};
+use crate::plugins::node::manifest::{self, ManifestError, PluginTool};
+use crate::plugins::node::permission::Permissions;
+use crate::plugins::plugin_fs;
+
+/// 插件包的格式版本，读取时拒绝更新的格式
+const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const SOURCE_FILE: &str = "plugin.ts";
+
+/// 包中单个文件解压后的大小上限
+const MAX_ENTRY_SIZE: u64 = 8 * 1024 * 1024;
+
+/// 依赖没有安装或版本无法导出时使用的版本
+const ANY_VERSION: &str = "latest";
+
+/// 插件包的清单
+///
+/// 插件包是扩展名为 `.ghostie-plugin` 的 zip 文件，包含 `manifest.json` 和插件源码 `plugin.ts`。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format: u32,
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// 插件需要的环境变量名，不含值
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// npm 依赖，包名 -> 版本
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    /// 插件声明的权限，导入后首次执行时仍需用户授予
+    #[serde(default)]
+    pub permissions: Permissions,
+    /// `plugin.ts` 的 SHA-256
+    pub checksum: String,
+    pub exported_at: String,
+}
+
+/// 导入前的预览
+#[derive(Debug, Clone, Serialize)]
+pub struct BundlePreview {
+    pub manifest: BundleManifest,
+    pub source: String,
+    pub tools: Vec<PluginTool>,
+    /// 解析源码时发现的问题
+    pub errors: Vec<ManifestError>,
+    /// 已存在同 id 的插件
+    pub conflict: bool,
+    /// 尚未安装的依赖，形如 `name@version`
+    pub missing_dependencies: Vec<String>,
+    /// 尚未设置的环境变量
+    pub missing_env: Vec<String>,
+}
+
+/// 导入时插件 id 已存在的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// 报错，不导入
+    #[default]
+    Fail,
+    /// 覆盖已有插件，覆盖前的内容保留在修订中
+    Replace,
+    /// 以新的 id 导入为副本
+    Copy,
+}
+
+fn checksum(source: &str) -> String {
+    format!("{:x}", Sha256::digest(source.as_bytes()))
+}
+
+/// 包名只允许 npm 合法的字符，版本只允许版本号和 `^`、`~` 等前缀
+///
+/// 依赖会作为 npm 的参数，不接受 git、文件等地址和可能被命令行解释的字符。
+fn validate_dependency(name: &str, version: &str) -> Result<()> {
+    let name_valid = !name.is_empty()
+        && !name.starts_with(['-', '.'])
+        && name.chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '@' | '/' | '-' | '_' | '.')
+        });
+    let version_valid = !version.is_empty()
+        && !version.starts_with('-')
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | '^' | '~' | '*'));
+    if name_valid && version_valid {
+        Ok(())
+    } else {
+        Err(PluginError::InvalidBundle(format!(
+            "依赖 {}@{} 不合法",
+            name, version
+        )))
+    }
+}
+
+/// 把插件打包到 `path`
+///
+/// 依赖取自插件引用的包，版本以插件目录 package.json 中的为准，
+/// 没有安装或版本是 git、文件等地址时导出为 `latest`。
+pub async fn export(id: &str, path: &Path) -> Result<BundleManifest> {
+    let source = plugin_fs::plugin_get_content(id.to_string()).await?;
+    let parsed = manifest::parse_manifest(&source);
+    let permissions = Permissions::parse(&source)?;
+    let installed = super::package_dependencies()?;
+    let dependencies = manifest::imported_packages(&source)
+        .into_iter()
+        .map(|name| {
+            let version = installed
+                .get(&name)
+                .filter(|version| validate_dependency(&name, version).is_ok())
+                .cloned()
+                .unwrap_or_else(|| ANY_VERSION.to_string());
+            (name, version)
+        })
+        .collect();
+
+    let bundle = BundleManifest {
+        format: FORMAT_VERSION,
+        id: id.to_string(),
+        name: parsed.name,
+        description: parsed.description,
+        version: parsed.version,
+        author: parsed.author,
+        env: permissions.env.clone(),
+        dependencies,
+        permissions,
+        checksum: checksum(&source),
+        exported_at: chrono::Local::now().to_rfc3339(),
+    };
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(MANIFEST_FILE, options)?;
+    zip.write_all(serde_json::to_string_pretty(&bundle)?.as_bytes())?;
+    zip.start_file(SOURCE_FILE, options)?;
+    zip.write_all(source.as_bytes())?;
+    zip.finish()?;
+    Ok(bundle)
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<String> {
+    let entry = archive
+        .by_name(name)
+        .map_err(|_| PluginError::InvalidBundle(format!("缺少 {}", name)))?;
+    if entry.size() > MAX_ENTRY_SIZE {
+        return Err(PluginError::InvalidBundle(format!("{} 过大", name)));
+    }
+    let mut content = String::new();
+    entry
+        .take(MAX_ENTRY_SIZE)
+        .read_to_string(&mut content)
+        .map_err(|e| PluginError::InvalidBundle(format!("读取 {} 失败: {}", name, e)))?;
+    Ok(content)
+}
+
+/// 读取并校验插件包，返回清单和源码
+pub fn read(path: &Path) -> Result<(BundleManifest, String)> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let bundle: BundleManifest = serde_json::from_str(&read_entry(&mut archive, MANIFEST_FILE)?)
+        .map_err(|e| PluginError::InvalidBundle(format!("清单格式错误: {}", e)))?;
+    if bundle.format > FORMAT_VERSION {
+        return Err(PluginError::InvalidBundle(format!(
+            "格式版本 {} 过新，请升级应用",
+            bundle.format
+        )));
+    }
+    let id_valid = !bundle.id.is_empty()
+        && bundle
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+    if !id_valid {
+        return Err(PluginError::InvalidBundle(format!(
+            "插件 id 不合法: {}",
+            bundle.id
+        )));
+    }
+    for (name, version) in &bundle.dependencies {
+        validate_dependency(name, version)?;
+    }
+
+    let source = read_entry(&mut archive, SOURCE_FILE)?;
+    if checksum(&source) != bundle.checksum.to_lowercase() {
+        return Err(PluginError::InvalidBundle(
+            "校验和不匹配，文件可能已损坏".to_string(),
+        ));
+    }
+    Ok((bundle, source))
+}
+
+/// 尚未安装的依赖，形如 `name@version`
+pub fn missing_dependencies(bundle: &BundleManifest) -> Result<Vec<String>> {
+    let installed = super::package_dependencies()?;
+    Ok(bundle
+        .dependencies
+        .iter()
+        .filter(|(name, _)| !installed.contains_key(*name))
+        .map(|(name, version)| format!("{}@{}", name, version))
+        .collect())
+}
+
+async fn plugin_exists(id: &str) -> Result<bool> {
+    Ok(plugin_fs::plugin_list()
+        .await?
+        .iter()
+        .any(|item| item == id))
+}
+
+/// 预览插件包，不做任何修改
+pub async fn preview(path: &Path) -> Result<BundlePreview> {
+    let (bundle, source) = read(path)?;
+    let parsed = manifest::parse_manifest(&source);
+    let env = super::env_list().await?;
+    Ok(BundlePreview {
+        conflict: plugin_exists(&bundle.id).await?,
+        missing_dependencies: missing_dependencies(&bundle)?,
+        missing_env: bundle
+            .env
+            .iter()
+            .filter(|key| !env.iter().any(|var| &var.key == *key))
+            .cloned()
+            .collect(),
+        tools: parsed.tools,
+        errors: parsed.errors,
+        manifest: bundle,
+        source,
+    })
+}
+
+/// 按冲突处理方式确定导入后的插件 id
+pub async fn target_id(bundle: &BundleManifest, conflict: ConflictStrategy) -> Result<String> {
+    if !plugin_exists(&bundle.id).await? {
+        return Ok(bundle.id.clone());
+    }
+    match conflict {
+        ConflictStrategy::Fail => Err(PluginError::Plugin(format!("插件已存在: {}", bundle.id))),
+        ConflictStrategy::Replace => Ok(bundle.id.clone()),
+        ConflictStrategy::Copy => Ok(uuid::Uuid::new_v4().to_string()),
+    }
+}
+
+/// 保存导入的插件，记为一个带说明的修订
+pub async fn save(id: &str, bundle: &BundleManifest, source: &str) -> Result<()> {
+    plugin_fs::plugin_save_content(
+        id.to_string(),
+        source.to_string(),
+        Some(format!("从插件包导入 {} v{}", bundle.name, bundle.version)),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use crate::plugins::node::error::{PluginError, Result};
+
+/// 设置文件名，位于配置目录下
+const SETTINGS_FILE: &str = "node.json";
+
+/// 支持权限模型的最低主版本，插件在沙箱中执行需要此版本
+pub const MIN_SANDBOX_MAJOR: u64 = 20;
+
+#[cfg(windows)]
+const NODE_EXE: &str = "node.exe";
+#[cfg(not(windows))]
+const NODE_EXE: &str = "node";
+
+/// Node 的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeSource {
+    /// 用户在设置中指定
+    Pinned,
+    /// 环境变量 PATH
+    Path,
+    Nvm,
+    Volta,
+    Fnm,
+    Scoop,
+    /// 系统默认安装位置
+    System,
+}
+
+/// 检测到的 Node
+#[derive(Debug, Clone)]
+pub struct NodeInfo {
+    /// 可执行文件路径
+    pub path: PathBuf,
+    pub version: semver::Version,
+    pub source: NodeSource,
+}
+
+impl NodeInfo {
+    /// 启用权限模型的参数，版本过低不支持时为空
+    pub fn permission_flag(&self) -> Option<&'static str> {
+        let version = &self.version;
+        if version.major >= 24
+            || (version.major == 23 && version.minor >= 5)
+            || (version.major == 22 && version.minor >= 13)
+        {
+            Some("--permission")
+        } else if version.major >= MIN_SANDBOX_MAJOR {
+            Some("--experimental-permission")
+        } else {
+            None
+        }
+    }
+
+    /// 与 Node 同目录的 npm，找不到时使用 PATH 中的 npm
+    pub fn npm_path(&self) -> PathBuf {
+        let npm = if cfg!(windows) { "npm.cmd" } else { "npm" };
+        self.path
+            .parent()
+            .map(|dir| dir.join(npm))
+            .filter(|npm| npm.is_file())
+            .unwrap_or_else(|| PathBuf::from(npm))
+    }
+
+    /// 把 Node 所在目录放在最前面的 PATH，保证 npm 和插件启动的子进程使用同一个 Node
+    pub fn path_env(&self) -> OsString {
+        let mut paths: Vec<PathBuf> = self
+            .path
+            .parent()
+            .map(Path::to_path_buf)
+            .into_iter()
+            .collect();
+        if let Some(path) = env::var_os("PATH") {
+            paths.extend(env::split_paths(&path));
+        }
+        env::join_paths(paths).unwrap_or_default()
+    }
+}
+
+/// Node 设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSettings {
+    /// 指定的 Node 可执行文件，为空时自动检测
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_path: Option<String>,
+}
+
+impl NodeSettings {
+    fn path() -> Result<PathBuf> {
+        Ok(crate::utils::file::get_config_dir()
+            .ok_or_else(|| PluginError::Plugin("无法获取配置目录".to_string()))?
+            .join(SETTINGS_FILE))
+    }
+
+    /// 读取设置，文件不存在或损坏时使用默认设置
+    pub fn load() -> Self {
+        Self::path()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(Self::path()?, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// 执行 `node -v` 并解析版本号，不是可用的 Node 时返回空
+pub fn probe(path: &Path) -> Option<semver::Version> {
+    if !path.is_file() {
+        return None;
+    }
+    let mut cmd = Command::new(path);
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let output = cmd.arg("-v").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.lines().next()?.trim();
+    semver::Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Node 安装目录中的可执行文件，Windows 在根目录，其他系统在 `bin` 下
+fn node_in(dir: &Path) -> PathBuf {
+    if cfg!(windows) {
+        dir.join(NODE_EXE)
+    } else {
+        dir.join("bin").join(NODE_EXE)
+    }
+}
+
+/// 目录下以版本号命名的子目录，按版本从高到低排列
+fn versions_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut versions: Vec<(semver::Version, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let version = semver::Version::parse(name.trim_start_matches('v')).ok()?;
+            Some((version, entry.path()))
+        })
+        .collect();
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    versions.into_iter().map(|(_, path)| path).collect()
+}
+
+/// 按优先级列出可能的 Node 位置
+fn candidates() -> Vec<(PathBuf, NodeSource)> {
+    let mut candidates = Vec::new();
+    let home = home_dir();
+
+    if let Some(path) = env::var_os("PATH") {
+        for dir in env::split_paths(&path) {
+            candidates.push((dir.join(NODE_EXE), NodeSource::Path));
+        }
+    }
+
+    // nvm 和 nvm-windows
+    let nvm_dir = env_dir("NVM_DIR").or_else(|| home.as_ref().map(|home| home.join(".nvm")));
+    if let Some(dir) = nvm_dir {
+        for version in versions_in(&dir.join("versions").join("node")) {
+            candidates.push((node_in(&version), NodeSource::Nvm));
+        }
+    }
+    if let Some(dir) = env_dir("NVM_SYMLINK") {
+        candidates.push((dir.join(NODE_EXE), NodeSource::Nvm));
+    }
+    if let Some(dir) = env_dir("NVM_HOME") {
+        for version in versions_in(&dir) {
+            candidates.push((version.join(NODE_EXE), NodeSource::Nvm));
+        }
+    }
+
+    // volta
+    let volta_dirs = [
+        env_dir("VOLTA_HOME"),
+        home.as_ref().map(|home| home.join(".volta")),
+        env_dir("LOCALAPPDATA").map(|dir| dir.join("Volta")),
+    ];
+    for dir in volta_dirs.into_iter().flatten() {
+        candidates.push((dir.join("bin").join(NODE_EXE), NodeSource::Volta));
+    }
+
+    // fnm
+    let fnm_dirs = [
+        env_dir("FNM_DIR"),
+        home.as_ref()
+            .map(|home| home.join(".local").join("share").join("fnm")),
+        home.as_ref().map(|home| home.join(".fnm")),
+        home.as_ref()
+            .map(|home| home.join("Library").join("Application Support").join("fnm")),
+        env_dir("APPDATA").map(|dir| dir.join("fnm")),
+    ];
+    for dir in fnm_dirs.into_iter().flatten() {
+        candidates.push((
+            node_in(&dir.join("aliases").join("default")),
+            NodeSource::Fnm,
+        ));
+        for version in versions_in(&dir.join("node-versions")) {
+            candidates.push((node_in(&version.join("installation")), NodeSource::Fnm));
+        }
+    }
+
+    // scoop
+    let scoop_dir = env_dir("SCOOP").or_else(|| home.as_ref().map(|home| home.join("scoop")));
+    if let Some(dir) = scoop_dir {
+        for app in ["nodejs", "nodejs-lts"] {
+            candidates.push((
+                dir.join("apps").join(app).join("current").join(NODE_EXE),
+                NodeSource::Scoop,
+            ));
+        }
+    }
+
+    // 系统默认安装位置
+    if cfg!(windows) {
+        for dir in [env_dir("ProgramFiles"), env_dir("ProgramFiles(x86)")]
+            .into_iter()
+            .flatten()
+        {
+            candidates.push((dir.join("nodejs").join(NODE_EXE), NodeSource::System));
+        }
+    } else {
+        for dir in ["/usr/local/bin", "/opt/homebrew/bin", "/usr/bin"] {
+            candidates.push((Path::new(dir).join(NODE_EXE), NodeSource::System));
+        }
+    }
+
+    candidates
+}
+
+/// 检测 Node
+///
+/// 设置中指定了 Node 时只使用指定的可执行文件，否则依次查找 PATH、nvm、volta、fnm、scoop
+/// 和系统默认安装位置，返回第一个支持权限模型的；都不支持时返回第一个能正常执行 `node -v` 的，
+/// 由 `node_check` 提示版本过低。
+pub fn detect() -> Option<NodeInfo> {
+    if let Some(path) = NodeSettings::load().node_path {
+        let path = PathBuf::from(path);
+        return probe(&path).map(|version| NodeInfo {
+            path,
+            version,
+            source: NodeSource::Pinned,
+        });
+    }
+
+    let mut seen = HashSet::new();
+    let mut fallback = None;
+    for (path, source) in candidates() {
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        let Some(version) = probe(&path) else {
+            continue;
+        };
+        let node = NodeInfo {
+            path,
+            version,
+            source,
+        };
+        if node.permission_flag().is_some() {
+            return Some(node);
+        }
+        fallback.get_or_insert(node);
+    }
+    fallback
+}
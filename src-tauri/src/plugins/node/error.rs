@@ -7,16 +7,40 @@ pub enum PluginError {
     Io(String),
     #[error("Node未安装")]
     NodeNotInstalled,
+    /// Node 不支持权限模型，插件无法在沙箱中执行
+    #[error("Node v{0} 版本过低，插件沙箱需要 Node 20 及以上版本")]
+    NodeTooOld(String),
     #[error("JSON错误: {0}")]
     Json(String),
     #[error("TOML错误: {0}")]
     Toml(String),
     #[error("插件错误: {0}")]
     Plugin(String),
+    /// TypeScript 编译失败，行列从 1 开始
+    #[error("编译失败 ({line}:{column}): {message}")]
+    Compile {
+        line: usize,
+        column: usize,
+        message: String,
+    },
     #[error("执行超时")]
     Timeout,
+    #[error("执行已取消")]
+    Cancelled,
+    #[error("插件包无效: {0}")]
+    InvalidBundle(String),
     #[error("插件不存在: {0}")]
     NotFound(String),
+    #[error("插件未导出函数: {0}")]
+    ToolNotFound(String),
+    #[error("调用被拒绝: {0}")]
+    Denied(String),
+    /// 插件执行了未声明权限的操作，`permission` 为 `fs.read`、`fs.write`、`net`、`process` 等
+    #[error("插件没有 {permission} 权限: {resource}")]
+    PermissionDenied {
+        permission: String,
+        resource: String,
+    },
 }
 
 impl From<std::io::Error> for PluginError {
@@ -43,6 +67,12 @@ impl From<toml::de::Error> for PluginError {
     }
 }
 
+impl From<zip::result::ZipError> for PluginError {
+    fn from(err: zip::result::ZipError) -> Self {
+        PluginError::InvalidBundle(err.to_string())
+    }
+}
+
 impl From<String> for PluginError {
     fn from(err: String) -> Self {
         PluginError::Plugin(err)
@@ -0,0 +1,951 @@
+use oxc::allocator::Allocator;
+use oxc::ast::ast::{
+    Argument, BindingPatternKind, CallExpression, Declaration, ExportAllDeclaration,
+    ExportNamedDeclaration, Expression, FormalParameters, Function, ImportDeclaration,
+    ImportExpression, MemberExpression, ObjectPropertyKind, Program, Statement, TSLiteral,
+    TSSignature, TSType, TSTypeName, VariableDeclarationKind,
+};
+use oxc::ast::visit::walk;
+use oxc::ast::{Comment, CommentKind, Trivias, Visit};
+use oxc::diagnostics::OxcDiagnostic;
+use oxc::parser::Parser;
+use oxc::span::{GetSpan, SourceType};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// 未声明版本时使用的版本号，与前端一致
+const DEFAULT_VERSION: &str = "0.0.1";
+
+/// Node 的内置模块，不需要安装
+const BUILTIN_MODULES: &[&str] = &[
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "sys",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+/// 插件导出的工具函数
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginTool {
+    /// 函数名
+    pub name: String,
+    pub description: String,
+    /// 参数的 JSON Schema，函数没有参数时为空
+    pub parameters: Option<Value>,
+}
+
+/// 解析插件时发现的问题
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestError {
+    /// 所在行，从 1 开始
+    pub line: usize,
+    pub message: String,
+}
+
+/// 插件清单
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginManifest {
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    pub author: Option<String>,
+    pub tools: Vec<PluginTool>,
+    /// 解析中发现的问题，不影响其余字段
+    pub errors: Vec<ManifestError>,
+}
+
+/// 文件开头 JSDoc 中的一个标签
+struct MetaTag {
+    line: usize,
+    key: String,
+    value: String,
+}
+
+/// 偏移量所在的行，从 1 开始
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
+/// 文件开头 JSDoc 中的 `@key value` 标签，按出现顺序排列
+fn meta_tags(content: &str) -> Vec<MetaTag> {
+    let start = content.len() - content.trim_start().len();
+    let header = &content[start..];
+    if !header.starts_with("/**") {
+        return Vec::new();
+    }
+    let Some(end) = header.find("*/") else {
+        return Vec::new();
+    };
+    let first_line = line_of(content, start);
+    header[3..end]
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line_text = line.trim().trim_start_matches('*').trim();
+            line_text.strip_prefix('@').and_then(|tag| {
+                tag.split_once(char::is_whitespace)
+                    .map(|(key, value)| MetaTag {
+                        line: first_line + index,
+                        key: key.to_string(),
+                        value: value.trim().to_string(),
+                    })
+            })
+        })
+        .collect()
+}
+
+/// 解析文件开头 JSDoc 中的 `@key value` 元数据，如 `@name`、`@description`
+pub fn parse_meta(content: &str) -> HashMap<String, String> {
+    meta_tags(content)
+        .into_iter()
+        .map(|tag| (tag.key, tag.value))
+        .collect()
+}
+
+/// 文件开头 JSDoc 中可以重复出现的标签的全部值，如 `@permission`
+pub fn meta_values(content: &str, key: &str) -> Vec<String> {
+    meta_tags(content)
+        .into_iter()
+        .filter(|tag| tag.key == key)
+        .map(|tag| tag.value)
+        .collect()
+}
+
+/// 解析后的插件源码
+///
+/// 插件按 TypeScript 模块解析，JavaScript 插件同样适用。
+struct Source<'a> {
+    content: &'a str,
+    program: Program<'a>,
+    trivias: Trivias,
+    errors: Vec<OxcDiagnostic>,
+}
+
+impl<'a> Source<'a> {
+    fn parse(allocator: &'a Allocator, content: &'a str) -> Self {
+        let parsed = Parser::new(allocator, content, source_type()).parse();
+        Self {
+            content,
+            program: parsed.program,
+            trivias: parsed.trivias,
+            errors: parsed.errors,
+        }
+    }
+
+    fn line(&self, offset: u32) -> usize {
+        line_of(self.content, offset as usize)
+    }
+
+    /// 第一条语法错误
+    fn syntax_error(&self) -> Option<ManifestError> {
+        let error = self.errors.first()?;
+        let offset = error
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.first())
+            .map_or(0, |label| label.offset());
+        Some(ManifestError {
+            line: line_of(self.content, offset.min(self.content.len())),
+            message: format!("语法错误: {}", error),
+        })
+    }
+
+    /// 紧挨在 `start` 之前的注释，两者之间只有空白
+    fn leading_comment(&self, start: u32) -> Option<&Comment> {
+        let comment = self.trivias.comments_range(..start).next_back()?;
+        let end = match comment.kind {
+            CommentKind::SingleLine => comment.span.end,
+            CommentKind::MultiLine => comment.span.end + 2,
+        } as usize;
+        self.content
+            .get(end..start as usize)
+            .is_some_and(|gap| gap.trim().is_empty())
+            .then_some(comment)
+    }
+
+    /// 注释的内容，不含 `//`、`/*` 和 `*/`
+    fn comment_text(&self, comment: &Comment) -> &'a str {
+        &self.content[comment.span.start as usize..comment.span.end as usize]
+    }
+
+    /// 紧挨在 `start` 之前的 JSDoc（`/** */`）
+    fn jsdoc(&self, start: u32) -> Option<&'a str> {
+        let comment = self.leading_comment(start)?;
+        let text = self.comment_text(comment);
+        (comment.kind == CommentKind::MultiLine && text.starts_with('*')).then(|| &text[1..])
+    }
+
+    /// 以 `export [async] function` 导出且有函数体的函数，以及 `export` 的位置
+    ///
+    /// 没有函数体的是重载签名或 `declare` 声明，不算作导出的函数。
+    fn exported_functions(&self) -> impl Iterator<Item = (u32, &Function<'a>)> + '_ {
+        self.program.body.iter().filter_map(|statement| {
+            let Statement::ExportNamedDeclaration(export) = statement else {
+                return None;
+            };
+            let Some(Declaration::FunctionDeclaration(function)) = &export.declaration else {
+                return None;
+            };
+            function.body.as_ref()?;
+            Some((export.span.start, &**function))
+        })
+    }
+
+    /// 顶层的同名接口或类型别名
+    fn type_declaration(&self, name: &str) -> Option<&Declaration<'a>> {
+        self.program.body.iter().find_map(|statement| {
+            let declaration = match statement {
+                Statement::ExportNamedDeclaration(export) => export.declaration.as_ref()?,
+                _ => statement.as_declaration()?,
+            };
+            let id = match declaration {
+                Declaration::TSInterfaceDeclaration(interface) => &interface.id,
+                Declaration::TSTypeAliasDeclaration(alias) => &alias.id,
+                _ => return None,
+            };
+            (id.name == name).then_some(declaration)
+        })
+    }
+
+    /// 解析 `export const meta = { ... }` 中的字符串字段，返回 (行, 字段, 值)
+    fn meta_object(&self, errors: &mut Vec<ManifestError>) -> Vec<(usize, String, String)> {
+        let Some((start, init)) = self.program.body.iter().find_map(|statement| {
+            let Statement::ExportNamedDeclaration(export) = statement else {
+                return None;
+            };
+            let Some(Declaration::VariableDeclaration(variable)) = &export.declaration else {
+                return None;
+            };
+            if variable.kind != VariableDeclarationKind::Const {
+                return None;
+            }
+            variable
+                .declarations
+                .iter()
+                .find_map(|declarator| match &declarator.id.kind {
+                    BindingPatternKind::BindingIdentifier(id) if id.name == "meta" => {
+                        Some((export.span.start, declarator.init.as_ref()))
+                    }
+                    _ => None,
+                })
+        }) else {
+            return Vec::new();
+        };
+        let Some(Expression::ObjectExpression(object)) = init.map(|e| e.get_inner_expression())
+        else {
+            errors.push(ManifestError {
+                line: self.line(start),
+                message: "meta 必须是对象字面量".to_string(),
+            });
+            return Vec::new();
+        };
+
+        let mut fields = Vec::new();
+        for property in &object.properties {
+            let ObjectPropertyKind::ObjectProperty(property) = property else {
+                continue;
+            };
+            let Some(key) = property.key.static_name() else {
+                continue;
+            };
+            if !matches!(key.as_ref(), "name" | "description" | "version" | "author") {
+                continue;
+            }
+            let line = self.line(property.span.start);
+            match string_value(&property.value) {
+                Some(value) => fields.push((line, key.to_string(), value)),
+                None => errors.push(ManifestError {
+                    line,
+                    message: format!("meta.{} 必须是字符串字面量", key),
+                }),
+            }
+        }
+        fields
+    }
+
+    /// 生成函数参数的 JSON Schema
+    fn parameters_schema(&self, params: &FormalParameters<'a>, doc: &JsDoc) -> Option<Value> {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+
+        for param in &params.items {
+            // 有默认值时类型注解在赋值的左侧
+            let (pattern, has_default) = match &param.pattern.kind {
+                BindingPatternKind::AssignmentPattern(assignment) => (&assignment.left, true),
+                _ => (&param.pattern, false),
+            };
+            let ty = pattern
+                .type_annotation
+                .as_ref()
+                .or(param.pattern.type_annotation.as_ref())
+                .map(|annotation| &annotation.type_annotation);
+
+            // 参数类型是接口或对象字面量时，把成员展开为工具参数
+            if let Some((members, member_required)) = ty.and_then(|ty| self.object_members(ty, 0)) {
+                properties.extend(members);
+                required.extend(member_required);
+                continue;
+            }
+
+            let BindingPatternKind::BindingIdentifier(id) = &pattern.kind else {
+                continue;
+            };
+            let name = id.name.to_string();
+            let optional = pattern.optional || param.pattern.optional || has_default;
+            let tag = doc.params.get(&name);
+            let mut schema = match ty {
+                Some(ty) => type_schema(ty),
+                None => tag
+                    .and_then(|(ty, _, _)| ty.as_deref())
+                    .map(jsdoc_type_schema)
+                    .unwrap_or_else(|| json!({})),
+            };
+            if let Some((_, description, _)) = tag.filter(|(_, d, _)| !d.is_empty()) {
+                schema["description"] = json!(description);
+            }
+            properties.insert(name.clone(), schema);
+            if !optional && !tag.is_some_and(|(_, _, optional)| *optional) {
+                required.push(name);
+            }
+        }
+
+        if properties.is_empty() {
+            return None;
+        }
+        Some(json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        }))
+    }
+
+    /// 解析对象字面量类型或同文件中的接口、类型别名，返回属性和必填项
+    fn object_members(
+        &self,
+        ty: &TSType<'a>,
+        depth: usize,
+    ) -> Option<(Map<String, Value>, Vec<String>)> {
+        if depth > 8 {
+            return None;
+        }
+        match ty {
+            TSType::TSTypeLiteral(literal) => Some(self.members(&literal.members)),
+            TSType::TSParenthesizedType(inner) => {
+                self.object_members(&inner.type_annotation, depth)
+            }
+            TSType::TSTypeReference(reference) if reference.type_parameters.is_none() => {
+                match &reference.type_name {
+                    TSTypeName::IdentifierReference(id) => self.named_members(&id.name, depth),
+                    TSTypeName::QualifiedName(_) => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// 同文件中接口（含继承的接口）或类型别名的成员
+    fn named_members(&self, name: &str, depth: usize) -> Option<(Map<String, Value>, Vec<String>)> {
+        match self.type_declaration(name)? {
+            Declaration::TSInterfaceDeclaration(interface) => {
+                let (mut properties, mut required) = (Map::new(), Vec::new());
+                for base in interface.extends.iter().flatten() {
+                    if let Expression::Identifier(id) = &base.expression {
+                        if let Some((p, r)) = self.named_members(&id.name, depth + 1) {
+                            properties.extend(p);
+                            required.extend(r);
+                        }
+                    }
+                }
+                let (p, r) = self.members(&interface.body.body);
+                properties.extend(p);
+                required.extend(r);
+                Some((properties, required))
+            }
+            Declaration::TSTypeAliasDeclaration(alias) => {
+                self.object_members(&alias.type_annotation, depth + 1)
+            }
+            _ => None,
+        }
+    }
+
+    /// 解析对象类型的属性，属性前的注释作为描述
+    fn members(&self, signatures: &[TSSignature<'a>]) -> (Map<String, Value>, Vec<String>) {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for signature in signatures {
+            let TSSignature::TSPropertySignature(property) = signature else {
+                continue;
+            };
+            let Some(name) = property.key.static_name() else {
+                continue;
+            };
+            let mut schema = property
+                .type_annotation
+                .as_ref()
+                .map(|annotation| type_schema(&annotation.type_annotation))
+                .unwrap_or_else(|| json!({}));
+            let description = self
+                .leading_comment(property.span.start)
+                .map(|comment| comment_description(self.comment_text(comment)))
+                .unwrap_or_default();
+            if !description.is_empty() {
+                schema["description"] = json!(description);
+            }
+            if !property.optional {
+                required.push(name.to_string());
+            }
+            properties.insert(name.to_string(), schema);
+        }
+        (properties, required)
+    }
+}
+
+fn source_type() -> SourceType {
+    SourceType::default()
+        .with_typescript(true)
+        .with_module(true)
+}
+
+/// 注释去掉每行开头的 `*` 后的内容
+fn comment_description(text: &str) -> String {
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 字符串字面量的值，含有插值的模板字符串不是字面量
+fn string_value(expression: &Expression) -> Option<String> {
+    match expression.get_inner_expression() {
+        Expression::StringLiteral(literal) => Some(literal.value.to_string()),
+        Expression::TemplateLiteral(literal) if literal.expressions.is_empty() => {
+            literal.quasi().map(|value| value.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// 解析插件清单
+///
+/// 名称、描述、版本和作者取自文件开头 JSDoc 的 `@name`、`@description`、`@version`、`@author`，
+/// 导出的 `meta` 对象中的同名字段优先。解析中发现的问题连同行号放在 `errors` 中。
+pub fn parse_manifest(content: &str) -> PluginManifest {
+    let allocator = Allocator::default();
+    let source = Source::parse(&allocator, content);
+    let mut errors = Vec::new();
+    let start = content.len() - content.trim_start().len();
+    if content[start..].starts_with("/**") && !content[start..].contains("*/") {
+        errors.push(ManifestError {
+            line: line_of(content, start),
+            message: "文件开头的注释缺少 */".to_string(),
+        });
+    } else if let Some(error) = source.syntax_error() {
+        errors.push(error);
+    }
+
+    let mut fields: Vec<(usize, String, String)> = meta_tags(content)
+        .into_iter()
+        .map(|tag| (tag.line, tag.key, tag.value))
+        .collect();
+    fields.extend(source.meta_object(&mut errors));
+    let field = |key: &str| {
+        fields
+            .iter()
+            .rev()
+            .find(|(_, name, _)| name == key)
+            .map(|(line, _, value)| (*line, value.clone()))
+    };
+
+    let version = match field("version") {
+        Some((line, version)) => {
+            if semver::Version::parse(&version).is_err() {
+                errors.push(ManifestError {
+                    line,
+                    message: format!("版本号 {} 不是有效的语义化版本", version),
+                });
+            }
+            version
+        }
+        None => DEFAULT_VERSION.to_string(),
+    };
+    let tools = scan_tools(&source, &mut errors);
+    undeclared_env(&source, &mut errors);
+    errors.sort_by_key(|error| error.line);
+
+    PluginManifest {
+        name: field("name").map(|(_, name)| name).unwrap_or_default(),
+        description: field("description")
+            .map(|(_, description)| description)
+            .unwrap_or_default(),
+        version,
+        author: field("author").map(|(_, author)| author),
+        tools,
+        errors,
+    }
+}
+
+/// 收集 `process.env.NAME` 和 `process.env["NAME"]` 读取的变量
+#[derive(Default)]
+struct EnvReads {
+    reads: Vec<(u32, String)>,
+}
+
+impl<'a> Visit<'a> for EnvReads {
+    fn visit_member_expression(&mut self, it: &MemberExpression<'a>) {
+        if it.object().is_specific_member_access("process", "env") {
+            if let Some(name) = it.static_property_name() {
+                self.reads.push((it.span().start, name.to_string()));
+            }
+        }
+        walk::walk_member_expression(self, it);
+    }
+}
+
+/// 读取了未声明的环境变量
+///
+/// 插件进程只保留 PATH、基本的系统变量和 `@permission env` 声明的变量，其余变量读取不到。
+/// 只识别 `process.env.NAME` 和 `process.env["NAME"]`。
+fn undeclared_env(source: &Source, errors: &mut Vec<ManifestError>) {
+    let declared: Vec<String> = meta_values(source.content, "permission")
+        .into_iter()
+        .filter_map(|value| match value.split_once(char::is_whitespace) {
+            Some(("env", name)) => Some(name.trim().to_string()),
+            _ => None,
+        })
+        .collect();
+    let mut visitor = EnvReads::default();
+    visitor.visit_program(&source.program);
+
+    let mut reported = Vec::new();
+    for (pos, name) in visitor.reads {
+        let allowed = name == "PATH"
+            || super::permission::BASE_ENV.contains(&name.as_str())
+            || declared.contains(&name);
+        if name.is_empty() || allowed || reported.contains(&name) {
+            continue;
+        }
+        errors.push(ManifestError {
+            line: source.line(pos),
+            message: format!(
+                "环境变量 {} 未声明，执行时读取不到，请添加 @permission env {}",
+                name, name
+            ),
+        });
+        reported.push(name);
+    }
+}
+
+/// 解析插件导出的函数及其参数
+///
+/// 函数描述取自 JSDoc，参数优先展开为接口或对象字面量的成员，
+/// 其次使用参数的类型注解和 `@param` 标签。
+pub fn parse_tools(content: &str) -> Vec<PluginTool> {
+    let allocator = Allocator::default();
+    let source = Source::parse(&allocator, content);
+    scan_tools(&source, &mut Vec::new())
+}
+
+/// 解析导出的函数，重复导出记入 `errors`
+fn scan_tools(source: &Source, errors: &mut Vec<ManifestError>) -> Vec<PluginTool> {
+    let mut tools = Vec::new();
+    let mut names = HashSet::new();
+    for (start, function) in source.exported_functions() {
+        let Some(id) = &function.id else {
+            continue;
+        };
+        let name = id.name.to_string();
+        if !names.insert(name.clone()) {
+            errors.push(ManifestError {
+                line: source.line(start),
+                message: format!("函数 {} 重复导出", name),
+            });
+            continue;
+        }
+
+        let doc = JsDoc::parse(source.jsdoc(start).unwrap_or_default());
+        tools.push(PluginTool {
+            description: doc.description.clone(),
+            parameters: source.parameters_schema(&function.params, &doc),
+            name,
+        });
+    }
+    tools
+}
+
+/// 插件是否以 `export [async] function` 导出了指定的函数
+pub fn exports_function(content: &str, name: &str) -> bool {
+    let allocator = Allocator::default();
+    let source = Source::parse(&allocator, content);
+    // 先取出结果，迭代器要在 source 之前释放
+    let exported = source
+        .exported_functions()
+        .any(|(_, function)| function.id.as_ref().is_some_and(|id| id.name == name));
+    exported
+}
+
+/// 收集模块说明符
+#[derive(Default)]
+struct Imports {
+    specifiers: Vec<String>,
+}
+
+impl<'a> Visit<'a> for Imports {
+    fn visit_import_declaration(&mut self, it: &ImportDeclaration<'a>) {
+        self.specifiers.push(it.source.value.to_string());
+    }
+
+    fn visit_export_all_declaration(&mut self, it: &ExportAllDeclaration<'a>) {
+        self.specifiers.push(it.source.value.to_string());
+    }
+
+    fn visit_export_named_declaration(&mut self, it: &ExportNamedDeclaration<'a>) {
+        if let Some(source) = &it.source {
+            self.specifiers.push(source.value.to_string());
+        }
+        walk::walk_export_named_declaration(self, it);
+    }
+
+    fn visit_import_expression(&mut self, it: &ImportExpression<'a>) {
+        if let Expression::StringLiteral(source) = &it.source {
+            self.specifiers.push(source.value.to_string());
+        }
+        walk::walk_import_expression(self, it);
+    }
+
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        if it.callee.is_specific_id("require") {
+            if let Some(Argument::StringLiteral(source)) = it.arguments.first() {
+                self.specifiers.push(source.value.to_string());
+            }
+        }
+        walk::walk_call_expression(self, it);
+    }
+}
+
+/// 插件通过 `import`、`import()` 和 `require()` 引用的 npm 包，不含内置模块和相对路径，按名称排序
+pub fn imported_packages(content: &str) -> Vec<String> {
+    let allocator = Allocator::default();
+    let source = Source::parse(&allocator, content);
+    let mut visitor = Imports::default();
+    visitor.visit_program(&source.program);
+
+    let mut packages = Vec::new();
+    for specifier in visitor.specifiers {
+        if let Some(name) = package_name(&specifier) {
+            if !packages.contains(&name) {
+                packages.push(name);
+            }
+        }
+    }
+    packages.sort();
+    packages
+}
+
+/// 模块说明符对应的包名，`@scope/name/sub` 取 `@scope/name`
+fn package_name(specifier: &str) -> Option<String> {
+    if specifier.is_empty() || specifier.starts_with(['.', '/']) || specifier.contains(':') {
+        return None;
+    }
+    let mut segments = specifier.split('/');
+    let first = segments.next()?;
+    if BUILTIN_MODULES.contains(&first) {
+        return None;
+    }
+    match first.strip_prefix('@') {
+        Some(_) => Some(format!("{}/{}", first, segments.next()?)),
+        None => Some(first.to_string()),
+    }
+}
+
+/// 函数的 JSDoc
+#[derive(Default)]
+struct JsDoc {
+    description: String,
+    /// 参数名 -> (类型, 描述, 是否可选)
+    params: HashMap<String, (Option<String>, String, bool)>,
+}
+
+impl JsDoc {
+    fn parse(body: &str) -> Self {
+        let mut doc = JsDoc::default();
+        let mut lines = Vec::new();
+        let mut tag_description = None;
+        for line in body.lines() {
+            let line = line.trim().trim_start_matches('*').trim();
+            if let Some(rest) = line.strip_prefix("@description") {
+                tag_description = Some(rest.trim().to_string());
+            } else if let Some(rest) = line.strip_prefix("@param") {
+                doc.parse_param(rest.trim());
+            } else if !line.starts_with('@') && !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        doc.description = tag_description.unwrap_or_else(|| lines.join("\n"));
+        doc
+    }
+
+    /// `@param {type} [name] - description`
+    fn parse_param(&mut self, mut rest: &str) {
+        let mut ty = None;
+        if let Some(stripped) = rest.strip_prefix('{') {
+            if let Some(end) = stripped.find('}') {
+                ty = Some(stripped[..end].trim().to_string());
+                rest = stripped[end + 1..].trim_start();
+            }
+        }
+        let (name, description) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let optional = name.starts_with('[') || ty.as_deref().is_some_and(|t| t.ends_with('='));
+        let name = name
+            .trim_matches(|c| c == '[' || c == ']')
+            .split('=')
+            .next()
+            .unwrap_or_default();
+        let description = description.trim().trim_start_matches('-').trim();
+        if !name.is_empty() {
+            self.params.insert(
+                name.to_string(),
+                (
+                    ty.map(|t| t.trim_end_matches('=').to_string()),
+                    description.to_string(),
+                    optional,
+                ),
+            );
+        }
+    }
+}
+
+/// 把 TypeScript 类型映射为 JSON Schema
+fn type_schema(ty: &TSType) -> Value {
+    match ty {
+        TSType::TSStringKeyword(_) | TSType::TSTemplateLiteralType(_) => {
+            json!({ "type": "string" })
+        }
+        TSType::TSNumberKeyword(_) => json!({ "type": "number" }),
+        TSType::TSBooleanKeyword(_) => json!({ "type": "boolean" }),
+        TSType::TSObjectKeyword(_) | TSType::TSTypeLiteral(_) | TSType::TSMappedType(_) => {
+            json!({ "type": "object" })
+        }
+        TSType::TSArrayType(array) => {
+            json!({ "type": "array", "items": type_schema(&array.element_type) })
+        }
+        TSType::TSParenthesizedType(inner) => type_schema(&inner.type_annotation),
+        TSType::JSDocNullableType(inner) => type_schema(&inner.type_annotation),
+        TSType::JSDocNonNullableType(inner) => type_schema(&inner.type_annotation),
+        TSType::TSLiteralType(literal) => match &literal.literal {
+            TSLiteral::StringLiteral(_) | TSLiteral::TemplateLiteral(_) => {
+                json!({ "type": "string" })
+            }
+            TSLiteral::NumericLiteral(_) | TSLiteral::UnaryExpression(_) => {
+                json!({ "type": "number" })
+            }
+            TSLiteral::BooleanLiteral(_) => json!({ "type": "boolean" }),
+            _ => json!({}),
+        },
+        TSType::TSUnionType(union) => {
+            let literals: Vec<&str> = union
+                .types
+                .iter()
+                .filter_map(|ty| match ty {
+                    TSType::TSLiteralType(literal) => match &literal.literal {
+                        TSLiteral::StringLiteral(value) => Some(value.value.as_str()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect();
+            if literals.len() == union.types.len() {
+                return json!({ "type": "string", "enum": literals });
+            }
+            union
+                .types
+                .iter()
+                .find(|ty| !matches!(ty, TSType::TSNullKeyword(_) | TSType::TSUndefinedKeyword(_)))
+                .map(type_schema)
+                .unwrap_or_else(|| json!({}))
+        }
+        TSType::TSTypeReference(reference) => match &reference.type_name {
+            TSTypeName::IdentifierReference(id) if id.name == "Array" => {
+                let items = reference
+                    .type_parameters
+                    .as_ref()
+                    .and_then(|params| params.params.first())
+                    .map_or_else(|| json!({}), type_schema);
+                json!({ "type": "array", "items": items })
+            }
+            // Record、Object 以及接口等命名类型
+            TSTypeName::IdentifierReference(id) if id.name.starts_with(char::is_uppercase) => {
+                json!({ "type": "object" })
+            }
+            _ => json!({}),
+        },
+        _ => json!({}),
+    }
+}
+
+/// 把 JSDoc 中的类型映射为 JSON Schema，按 TypeScript 类型解析
+fn jsdoc_type_schema(ty: &str) -> Value {
+    let allocator = Allocator::default();
+    let text = format!("type T = {};", ty);
+    let parsed = Parser::new(&allocator, &text, source_type()).parse();
+    match parsed.program.body.first() {
+        Some(Statement::TSTypeAliasDeclaration(alias)) if parsed.errors.is_empty() => {
+            type_schema(&alias.type_annotation)
+        }
+        _ => json!({}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_exports_in_strings_and_comments() {
+        let source = r#"
+// export function commented() {}
+const text = "export function quoted() {}";
+/* export function blocked() {} */
+export async function real(a: string) {}
+"#;
+        assert!(exports_function(source, "real"));
+        for name in ["commented", "quoted", "blocked"] {
+            assert!(!exports_function(source, name));
+        }
+        assert_eq!(parse_tools(source).len(), 1);
+    }
+
+    #[test]
+    fn meta_object_with_braces_in_strings_and_comments() {
+        let source = r#"export const meta = {
+  name: "a}b",
+  /* version: "x", */
+  version: `1.0.0`,
+  description: desc,
+};"#;
+        let manifest = parse_manifest(source);
+        assert_eq!(manifest.name, "a}b");
+        assert_eq!(manifest.version, "1.0.0");
+        assert_eq!(manifest.errors.len(), 1);
+        assert_eq!(manifest.errors[0].line, 5);
+    }
+
+    #[test]
+    fn function_typed_member_does_not_end_the_object() {
+        let source = r#"
+type Args = {
+  map: (x: number) => Map<string, number>;
+  /** 名称 */
+  name: string;
+};
+export function run(args: Args) {}
+"#;
+        let schema = parse_tools(source).remove(0).parameters.unwrap();
+        assert_eq!(schema["required"], json!(["map", "name"]));
+        assert_eq!(schema["properties"]["name"]["description"], "名称");
+    }
+
+    #[test]
+    fn interface_with_apostrophe_in_comment() {
+        let source = r#"
+interface Args {
+  /** the user's name */
+  name: string;
+  // don't forget the greeting
+  greeting?: string;
+}
+
+/** Greet someone */
+export function greet(args: Args) {}
+"#;
+        let tools = parse_tools(source);
+        assert_eq!(tools.len(), 1);
+        assert_eq!(
+            tools[0].parameters,
+            Some(json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "the user's name" },
+                    "greeting": { "type": "string", "description": "don't forget the greeting" },
+                },
+                "required": ["name"],
+            }))
+        );
+    }
+
+    #[test]
+    fn parameters_with_string_defaults() {
+        let source = r#"export function pick(sep: string = ",", mode: "a" | "b" = 'a)') {}"#;
+        let schema = parse_tools(source).remove(0).parameters.unwrap();
+        assert_eq!(schema["required"], json!([]));
+        assert_eq!(schema["properties"]["sep"]["type"], "string");
+        assert!(schema["properties"].get("mode").is_some());
+    }
+
+    #[test]
+    fn jsdoc_parameters_and_imports() {
+        let source = r#"
+import fs from "node:fs";
+import type { T } from "@types/node";
+import _ from "lodash/fp";
+const x = require("@scope/pkg/sub");
+/**
+ * 搜索
+ * @param {string} query - 关键词
+ * @param {number=} limit
+ * @param {"a"|"b"} [mode]
+ */
+export async function search(query, limit, mode) { return import("axios"); }
+"#;
+        let tool = parse_tools(source).remove(0);
+        assert_eq!(tool.description, "搜索");
+        let schema = tool.parameters.unwrap();
+        assert_eq!(schema["required"], json!(["query"]));
+        assert_eq!(schema["properties"]["query"]["description"], "关键词");
+        assert_eq!(schema["properties"]["limit"]["type"], "number");
+        assert_eq!(schema["properties"]["mode"]["enum"], json!(["a", "b"]));
+        assert_eq!(
+            imported_packages(source),
+            vec!["@scope/pkg", "@types/node", "axios", "lodash"]
+        );
+    }
+}
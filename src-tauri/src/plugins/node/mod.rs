@@ -1,21 +1,32 @@
+pub mod bundle;
+pub mod compile;
+pub mod detect;
 pub mod env;
 pub mod error;
+pub mod manifest;
+pub mod permission;
 pub mod plugin;
+pub mod pool;
 pub mod runtime;
 
+use bundle::{BundleManifest, BundlePreview, ConflictStrategy};
 pub use env::{EnvManager, EnvVar};
 pub use error::{PluginError, Result};
 use once_cell::sync::Lazy;
-pub use plugin::PluginManager;
+pub use plugin::{ExecuteOptions, PluginManager};
+use pool::CallEvent;
 pub use runtime::NodeRuntime;
+use serde::Serialize;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 use tauri::AppHandle;
 use tauri::Emitter;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 
 // 全局状态
 static APP_HANDLE: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
@@ -130,36 +141,254 @@ pub async fn node_install(window: tauri::Window) -> Result<bool> {
 }
 
 /// 检查 Node 是否已安装
+///
+/// 返回 `installed`，已安装时还有 `version`、`path`、来源 `source`，
+/// 以及是否支持插件沙箱 `sandbox`（版本过低时为 false，插件无法执行）；
+/// 在设置中指定了 Node 时返回 `pinned`。
 #[tauri::command]
 pub async fn node_check() -> Result<HashMap<String, String>> {
-    let runtime = NodeRuntime::new()?;
+    let runtime = tokio::task::spawn_blocking(NodeRuntime::new)
+        .await
+        .map_err(|e| PluginError::Plugin(e.to_string()))??;
     let is_installed = runtime.check_installed();
 
-    // 无论是否安装，都更新全局运行时
-    let mut global_runtime = NODE_RUNTIME.lock().await;
-    *global_runtime = Some(runtime);
-
     let mut result = HashMap::new();
     result.insert("installed".to_string(), is_installed.to_string());
 
     if is_installed {
-        if let Some((version, path)) = global_runtime.as_ref().unwrap().get_version_and_path() {
+        if let Some((version, path)) = runtime.get_version_and_path() {
             result.insert("version".to_string(), version);
             result.insert("path".to_string(), path);
         }
+        if let Some(node) = runtime.node() {
+            let source = serde_json::to_value(node.source)?;
+            result.insert(
+                "source".to_string(),
+                source.as_str().unwrap_or("").to_string(),
+            );
+            result.insert(
+                "sandbox".to_string(),
+                node.permission_flag().is_some().to_string(),
+            );
+        }
+    }
+    if let Some(pinned) = detect::NodeSettings::load().node_path {
+        result.insert("pinned".to_string(), pinned);
     }
 
+    // 无论是否安装，都更新全局运行时
+    *NODE_RUNTIME.lock().await = Some(runtime);
+
     Ok(result)
 }
 
+/// 指定使用的 Node 可执行文件，为空时恢复自动检测
+///
+/// 指定的文件必须能正常执行 `node -v`，返回其版本号。
+#[tauri::command]
+pub async fn node_set_path(path: Option<String>) -> Result<Option<String>> {
+    let path = path.filter(|path| !path.trim().is_empty());
+    let version = match &path {
+        Some(path) => {
+            let node = std::path::PathBuf::from(path);
+            let version = tokio::task::spawn_blocking(move || detect::probe(&node))
+                .await
+                .map_err(|e| PluginError::Plugin(e.to_string()))?
+                .ok_or_else(|| PluginError::Plugin(format!("{} 不是可用的 Node", path)))?;
+            Some(format!("v{}", version))
+        }
+        None => None,
+    };
+
+    detect::NodeSettings { node_path: path }.save()?;
+
+    let runtime = tokio::task::spawn_blocking(NodeRuntime::new)
+        .await
+        .map_err(|e| PluginError::Plugin(e.to_string()))??;
+    *NODE_RUNTIME.lock().await = Some(runtime);
+
+    Ok(version)
+}
+
+/// 插件调用进度事件名
+pub const PROGRESS_EVENT: &str = "plugin-tool-progress";
+
+/// 插件调用进度
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub plugin_id: Option<String>,
+    /// 调用 `plugin_execute` 时指定的调用 id
+    pub call_id: String,
+    pub tool: String,
+    pub progress: f64,
+    pub total: Option<f64>,
+    pub message: Option<String>,
+}
+
+/// 确定调用方提交的插件源码的身份
+///
+/// 与 `id` 对应的已保存源码一致时就是该插件，否则（没有 id 或源码经过前端改写）以源码哈希作为身份，
+/// 审批策略和授予的权限都按这个身份匹配，调用方无法借用其他插件的身份。
+async fn plugin_identity(id: Option<&str>, content: &str) -> (String, bool) {
+    if let Some(id) = id {
+        let saved = crate::plugins::plugin_fs::plugin_get_content(id.to_string()).await;
+        if saved.is_ok_and(|saved| saved == content) {
+            return (id.to_string(), true);
+        }
+    }
+    let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+    (format!("sha256-{}", &hash[..16]), false)
+}
+
 /// 执行插件工具
+///
+/// 源码与插件 `id` 已保存的 TypeScript 源码一致时由后端编译执行，否则视为编译好的 JavaScript，
+/// 以源码哈希为身份执行，见 [`plugin_identity`]。
+/// 调用前按审批策略检查，工具标识为 `plugin:{身份}/{tool}`。
+/// `call_id` 由前端生成，用于 `plugin_cancel` 和 `plugin-tool-progress` 进度事件，为空时自动生成；
+/// `timeout_ms` 为空时使用插件头部的 `@timeout`。
+/// 提供插件 id 时，执行期间插件的输出以 `plugin-log-{id}` 事件发送到窗口。
 #[tauri::command]
-pub async fn plugin_execute(content: String, tool: String, args: Value) -> Result<Value> {
-    let manager = PLUGIN_MANAGER.lock().await;
-    let manager = manager
-        .as_ref()
+pub async fn plugin_execute(
+    window: tauri::Window,
+    content: String,
+    tool: String,
+    args: Value,
+    id: Option<String>,
+    call_id: Option<String>,
+    timeout_ms: Option<u64>,
+) -> Result<Value> {
+    let (identity, saved) = plugin_identity(id.as_deref(), &content).await;
+    let manager = PLUGIN_MANAGER
+        .lock()
+        .await
+        .clone()
         .ok_or_else(|| PluginError::Plugin("插件管理器未初始化".to_string()))?;
-    manager.execute(&content, &tool, args).await
+
+    let call_id = call_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let (events, mut receiver) = mpsc::unbounded_channel::<CallEvent>();
+    let log_event = id.as_ref().map(|id| format!("plugin-log-{}", id));
+    let (event_call_id, event_tool, event_plugin_id) = (call_id.clone(), tool.clone(), id.clone());
+    let forward = tokio::spawn(async move {
+        while let Some(event) = receiver.recv().await {
+            let (name, payload) = match event {
+                CallEvent::Log(log) => {
+                    let Some(name) = &log_event else {
+                        eprintln!("[plugin {}] {}", event_tool, log.message);
+                        continue;
+                    };
+                    let payload = serde_json::json!({
+                        "call_id": event_call_id,
+                        "tool": event_tool,
+                        "stream": log.stream,
+                        "message": log.message,
+                    });
+                    (name.as_str(), payload)
+                }
+                CallEvent::Progress(progress) => {
+                    let payload = ProgressEvent {
+                        plugin_id: event_plugin_id.clone(),
+                        call_id: event_call_id.clone(),
+                        tool: event_tool.clone(),
+                        progress: progress.progress,
+                        total: progress.total,
+                        message: progress.message,
+                    };
+                    (
+                        PROGRESS_EVENT,
+                        serde_json::to_value(payload).unwrap_or_default(),
+                    )
+                }
+            };
+            if let Err(e) = window.emit(name, payload) {
+                eprintln!("Failed to emit {} event: {}", name, e);
+            }
+        }
+    });
+    let options = ExecuteOptions {
+        plugin_id: Some(identity),
+        call_id: Some(call_id),
+        timeout: timeout_ms.map(std::time::Duration::from_millis),
+        events: Some(events),
+    };
+    let result = if saved {
+        manager
+            .execute_typescript(&content, &tool, args, options)
+            .await
+    } else {
+        manager.execute(&content, &tool, args, options).await
+    };
+    // 等待剩余的事件发送完，保证结果在输出之后到达
+    let _ = forward.await;
+    result
+}
+
+/// 取消进行中的插件调用，插件宽限期内没有结束时结束执行它的进程
+#[tauri::command]
+pub async fn plugin_cancel(call_id: String) -> Result<()> {
+    let manager = PLUGIN_MANAGER
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| PluginError::Plugin("插件管理器未初始化".to_string()))?;
+    manager.cancel(&call_id).await
+}
+
+/// 执行已保存的插件中导出的函数
+///
+/// 插件源码直接交给 Node 执行，不经过前端的 `__DB__`、`__IMAGE__` 替换。
+pub async fn execute_saved_plugin(id: &str, tool: &str, args: Value) -> Result<Value> {
+    let content = crate::plugins::plugin_fs::plugin_get_content(id.to_string())
+        .await
+        .map_err(|_| PluginError::NotFound(id.to_string()))?;
+    let manager = PLUGIN_MANAGER
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| PluginError::Plugin("插件管理器未初始化".to_string()))?;
+    let options = ExecuteOptions {
+        plugin_id: Some(id.to_string()),
+        ..Default::default()
+    };
+    manager
+        .execute_typescript(&content, tool, args, options)
+        .await
+}
+
+/// 把插件导出为插件包，包含源码、清单、需要的环境变量名和 npm 依赖
+#[tauri::command]
+pub async fn plugin_export(id: String, path: String) -> Result<BundleManifest> {
+    bundle::export(&id, Path::new(&path)).await
+}
+
+/// 校验插件包并返回预览，不做任何修改
+#[tauri::command]
+pub async fn plugin_import_preview(path: String) -> Result<BundlePreview> {
+    bundle::preview(Path::new(&path)).await
+}
+
+/// 导入插件包，先安装缺少的依赖再保存插件，返回插件 id
+///
+/// 插件 id 已存在时按 `conflict` 处理，默认报错。依赖安装时不执行生命周期脚本；
+/// 覆盖已有插件时清除之前授予该 id 的权限，新内容首次运行时重新询问。
+#[tauri::command]
+pub async fn plugin_import(
+    window: tauri::Window,
+    path: String,
+    conflict: Option<ConflictStrategy>,
+) -> Result<String> {
+    let (manifest, source) = bundle::read(Path::new(&path))?;
+    let id = bundle::target_id(&manifest, conflict.unwrap_or_default()).await?;
+    let missing = bundle::missing_dependencies(&manifest)?;
+    if !missing.is_empty() && !install_packages(window, missing.clone(), false, true).await? {
+        return Err(PluginError::Plugin(format!(
+            "依赖安装失败: {}",
+            missing.join(", ")
+        )));
+    }
+    permission::revoke(&id)?;
+    bundle::save(&id, &manifest, &source).await?;
+    Ok(id)
 }
 
 /// 获取环境变量列表
@@ -194,6 +423,11 @@ pub async fn node_list_dependencies() -> Result<HashMap<String, String>> {
         return Err(PluginError::NodeNotInstalled);
     }
 
+    package_dependencies()
+}
+
+/// 插件目录 package.json 中声明的依赖及版本
+fn package_dependencies() -> Result<HashMap<String, String>> {
     // 获取插件目录
     let config_dir = crate::utils::file::get_config_dir()
         .ok_or_else(|| PluginError::Plugin("无法获取配置目录".to_string()))?;
@@ -241,6 +475,16 @@ pub async fn node_install_dependency(
     window: tauri::Window,
     packages: Vec<String>,
     dev: bool,
+) -> Result<bool> {
+    install_packages(window, packages, dev, false).await
+}
+
+/// 用 npm 安装依赖，`ignore_scripts` 时不执行依赖的生命周期脚本
+async fn install_packages(
+    window: tauri::Window,
+    packages: Vec<String>,
+    dev: bool,
+    ignore_scripts: bool,
 ) -> Result<bool> {
     let runtime = NODE_RUNTIME.lock().await;
     let runtime = runtime
@@ -260,29 +504,8 @@ pub async fn node_install_dependency(
         return Ok(true);
     }
 
-    #[cfg(windows)]
-    let mut cmd = Command::new("cmd");
-    #[cfg(not(windows))]
-    let mut cmd = Command::new("sh");
-
-    #[cfg(windows)]
-    {
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        cmd.args(["/C", "npm", "install"]);
-    }
-
-    #[cfg(not(windows))]
-    {
-        cmd.args(["-c", &format!("npm install {}", packages.join(" "))]);
-    }
-
-    // 添加依赖包
-    #[cfg(windows)]
-    {
-        for package in &packages {
-            cmd.arg(package);
-        }
-    }
+    let mut cmd = runtime.npm_command(&plugins_dir)?;
+    cmd.arg("install").args(&packages);
 
     // 如果是开发依赖，添加 --save-dev 参数
     if dev {
@@ -290,10 +513,12 @@ pub async fn node_install_dependency(
     } else {
         cmd.arg("--save");
     }
+    if ignore_scripts {
+        cmd.arg("--ignore-scripts");
+    }
 
     let _ = window.emit("node_dependency_progress", "正在安装依赖...");
 
-    cmd.current_dir(&plugins_dir);
     cmd.stdout(std::process::Stdio::piped());
     let mut child = cmd.spawn()?;
 
@@ -348,33 +573,11 @@ pub async fn node_uninstall_dependency(
         return Ok(true);
     }
 
-    #[cfg(windows)]
-    let mut cmd = Command::new("cmd");
-    #[cfg(not(windows))]
-    let mut cmd = Command::new("sh");
-
-    #[cfg(windows)]
-    {
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        cmd.args(["/C", "npm", "uninstall"]);
-    }
-
-    #[cfg(not(windows))]
-    {
-        cmd.args(["-c", &format!("npm uninstall {}", packages.join(" "))]);
-    }
-
-    // 添加依赖包
-    #[cfg(windows)]
-    {
-        for package in &packages {
-            cmd.arg(package);
-        }
-    }
+    let mut cmd = runtime.npm_command(&plugins_dir)?;
+    cmd.arg("uninstall").args(&packages);
 
     let _ = window.emit("node_dependency_progress", "正在删除依赖...");
 
-    cmd.current_dir(&plugins_dir);
     cmd.stdout(std::process::Stdio::piped());
     let mut child = cmd.spawn()?;
 
@@ -422,25 +625,11 @@ pub async fn node_update_dependencies(window: tauri::Window) -> Result<bool> {
         .ok_or_else(|| PluginError::Plugin("无法获取配置目录".to_string()))?;
     let plugins_dir = config_dir.join("plugins");
 
-    #[cfg(windows)]
-    let mut cmd = Command::new("cmd");
-    #[cfg(not(windows))]
-    let mut cmd = Command::new("sh");
-
-    #[cfg(windows)]
-    {
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        cmd.args(["/C", "npm", "update"]);
-    }
-
-    #[cfg(not(windows))]
-    {
-        cmd.args(["-c", "npm update"]);
-    }
+    let mut cmd = runtime.npm_command(&plugins_dir)?;
+    cmd.arg("update");
 
     let _ = window.emit("node_dependency_progress", "正在更新所有依赖...");
 
-    cmd.current_dir(&plugins_dir);
     cmd.stdout(std::process::Stdio::piped());
     let mut child = cmd.spawn()?;
 
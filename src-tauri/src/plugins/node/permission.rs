@@ -0,0 +1,296 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::plugins::node::detect::NodeInfo;
+use crate::plugins::node::env::EnvVar;
+use crate::plugins::node::error::{PluginError, Result};
+use crate::plugins::node::manifest;
+
+/// 授权记录文件名，位于配置目录下
+const GRANTS_FILE: &str = "plugin_permissions.json";
+
+/// 进程始终保留的系统环境变量，其余变量需要插件声明
+pub(crate) const BASE_ENV: &[&str] = &[
+    "HOME",
+    "USERPROFILE",
+    "SystemRoot",
+    "WINDIR",
+    "TEMP",
+    "TMP",
+    "TMPDIR",
+    "LANG",
+    "LC_ALL",
+    "TZ",
+];
+
+// 读写授权记录时加锁，避免并发调用互相覆盖
+static GRANTS_LOCK: Lazy<std::sync::Mutex<()>> = Lazy::new(|| std::sync::Mutex::new(()));
+
+/// 插件的权限
+///
+/// 在文件开头的 JSDoc 中用 `@permission` 声明，每行一项：
+///
+/// ```text
+/// @permission fs.read ~/Documents
+/// @permission fs.write /tmp/output
+/// @permission net api.example.com
+/// @permission net *.example.com:443
+/// @permission process
+/// @permission env API_KEY
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Permissions {
+    /// 可读取的路径，包括其中的文件和子目录
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fs_read: Vec<String>,
+    /// 可写入的路径
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fs_write: Vec<String>,
+    /// 可连接的主机，`*.` 开头匹配子域名，可带 `:端口`，`*` 表示任意主机
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub net: Vec<String>,
+    /// 可启动子进程
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub process: bool,
+    /// 可读取的环境变量
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+}
+
+fn push_unique(list: &mut Vec<String>, value: &str) {
+    if !list.iter().any(|item| item == value) {
+        list.push(value.to_string());
+    }
+}
+
+fn missing_from(list: &[String], granted: &[String]) -> Vec<String> {
+    list.iter()
+        .filter(|item| !granted.contains(item))
+        .cloned()
+        .collect()
+}
+
+/// 展开 `~`，只接受绝对路径
+fn expand_path(path: &str) -> Result<PathBuf> {
+    let home = || {
+        std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(PathBuf::from)
+            .ok_or_else(|| PluginError::Plugin("无法获取用户目录".to_string()))
+    };
+    let path = match path.strip_prefix('~') {
+        Some("") => home()?,
+        Some(rest) if rest.starts_with(['/', '\\']) => home()?.join(&rest[1..]),
+        _ => PathBuf::from(path),
+    };
+    if !path.is_absolute() {
+        return Err(PluginError::Plugin(format!(
+            "权限中的路径必须是绝对路径: {}",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+fn flag(name: &str, path: &Path) -> OsString {
+    let mut flag = OsString::from(name);
+    flag.push("=");
+    flag.push(path);
+    flag
+}
+
+impl Permissions {
+    /// 解析插件头部声明的权限
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut permissions = Self::default();
+        for value in manifest::meta_values(content, "permission") {
+            let (kind, target) = match value.split_once(char::is_whitespace) {
+                Some((kind, target)) => (kind, target.trim()),
+                None => (value.as_str(), ""),
+            };
+            let list = match kind {
+                "fs.read" => &mut permissions.fs_read,
+                "fs.write" => &mut permissions.fs_write,
+                "net" => &mut permissions.net,
+                "env" => &mut permissions.env,
+                "process" => {
+                    permissions.process = true;
+                    continue;
+                }
+                _ => return Err(PluginError::Plugin(format!("未知的权限: {}", kind))),
+            };
+            if target.is_empty() {
+                return Err(PluginError::Plugin(format!("权限 {} 缺少目标", kind)));
+            }
+            push_unique(list, target);
+        }
+        Ok(permissions)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 不在 `granted` 中的权限
+    pub fn missing(&self, granted: &Self) -> Self {
+        Self {
+            fs_read: missing_from(&self.fs_read, &granted.fs_read),
+            fs_write: missing_from(&self.fs_write, &granted.fs_write),
+            net: missing_from(&self.net, &granted.net),
+            process: self.process && !granted.process,
+            env: missing_from(&self.env, &granted.env),
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for (list, other) in [
+            (&mut self.fs_read, &other.fs_read),
+            (&mut self.fs_write, &other.fs_write),
+            (&mut self.net, &other.net),
+            (&mut self.env, &other.env),
+        ] {
+            for value in other {
+                push_unique(list, value);
+            }
+        }
+        self.process |= other.process;
+    }
+
+    /// 启用 Node 权限模型的参数，Node 版本过低时返回 [`PluginError::NodeTooOld`]
+    ///
+    /// `module_dirs` 始终可读，用于加载缓存的模块和依赖；插件目录的其余内容
+    /// （环境变量、其他插件的源码和历史版本）不可读。
+    ///
+    /// Node 的权限模型不限制网络，`net` 权限只由 worker.mjs 在进程内替换 `net.Socket` 的连接入口来检查，
+    /// 并非系统层面的隔离：原生模块或子进程（需要 `process` 权限）可以绕过。
+    pub fn node_args(&self, node: &NodeInfo, module_dirs: &[PathBuf]) -> Result<Vec<OsString>> {
+        let flag_name = node
+            .permission_flag()
+            .ok_or_else(|| PluginError::NodeTooOld(node.version.to_string()))?;
+
+        let mut args = vec![OsString::from(flag_name)];
+        for dir in module_dirs {
+            args.push(flag("--allow-fs-read", dir));
+        }
+        for path in &self.fs_read {
+            args.push(flag("--allow-fs-read", &expand_path(path)?));
+        }
+        for path in &self.fs_write {
+            args.push(flag("--allow-fs-write", &expand_path(path)?));
+        }
+        if self.process {
+            args.push(OsString::from("--allow-child-process"));
+        }
+        Ok(args)
+    }
+
+    /// 进程的环境变量：PATH、基本的系统变量，以及声明过的变量
+    ///
+    /// 声明的变量优先取自插件环境变量，其次取自系统环境变量。
+    pub fn env_vars(&self, node: &NodeInfo, env_list: &[EnvVar]) -> Vec<(String, OsString)> {
+        let mut vars = vec![("PATH".to_string(), node.path_env())];
+        for key in BASE_ENV {
+            if let Some(value) = std::env::var_os(key) {
+                vars.push((key.to_string(), value));
+            }
+        }
+        for key in &self.env {
+            let value = env_list
+                .iter()
+                .find(|var| &var.key == key)
+                .map(|var| OsString::from(&var.value))
+                .or_else(|| std::env::var_os(key));
+            if let Some(value) = value {
+                vars.push((key.clone(), value));
+            }
+        }
+        vars
+    }
+}
+
+fn grants_path() -> Result<PathBuf> {
+    Ok(crate::utils::file::get_config_dir()
+        .ok_or_else(|| PluginError::Plugin("无法获取配置目录".to_string()))?
+        .join(GRANTS_FILE))
+}
+
+/// 读取已授予各插件的权限，以插件 id 为键
+fn load_grants() -> Result<HashMap<String, Permissions>> {
+    let path = grants_path()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let content = fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn save_grants(grants: &HashMap<String, Permissions>) -> Result<()> {
+    let path = grants_path()?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string_pretty(grants)?)?;
+    fs::rename(&temp, &path)?;
+    Ok(())
+}
+
+/// 记录授予插件的权限
+fn grant(plugin_id: &str, permissions: &Permissions) -> Result<()> {
+    let _lock = GRANTS_LOCK.lock().unwrap();
+    let mut grants = load_grants()?;
+    grants
+        .entry(plugin_id.to_string())
+        .or_default()
+        .merge(permissions);
+    save_grants(&grants)
+}
+
+/// 清除授予插件的权限，插件内容被替换时调用
+pub fn revoke(plugin_id: &str) -> Result<()> {
+    let _lock = GRANTS_LOCK.lock().unwrap();
+    let mut grants = load_grants()?;
+    if grants.remove(plugin_id).is_some() {
+        save_grants(&grants)?;
+    }
+    Ok(())
+}
+
+/// 确认插件声明的权限都已授予
+///
+/// 插件首次声明某项权限时询问用户，同意后记录下来，之后不再询问。
+/// 没有插件 id 的调用无法记录，每次都询问。用户拒绝时返回 [`PluginError::Denied`]。
+pub async fn authorize(plugin_id: Option<&str>, permissions: &Permissions) -> Result<()> {
+    if permissions.is_empty() {
+        return Ok(());
+    }
+    let missing = match plugin_id {
+        Some(id) => {
+            let granted = {
+                let _lock = GRANTS_LOCK.lock().unwrap();
+                load_grants()?.remove(id).unwrap_or_default()
+            };
+            permissions.missing(&granted)
+        }
+        None => permissions.clone(),
+    };
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    crate::plugins::approval::confirm_permissions(
+        plugin_id.unwrap_or("*"),
+        &serde_json::to_value(&missing)?,
+    )
+    .await
+    .map_err(PluginError::Denied)?;
+    if let Some(id) = plugin_id {
+        grant(id, &missing)?;
+    }
+    Ok(())
+}
@@ -1,7 +1,27 @@
 use crate::plugins::node::error::{PluginError, Result};
+use crate::plugins::node::manifest;
+use crate::plugins::node::permission::{self, Permissions};
+use crate::plugins::node::pool::{CallEvent, Invocation};
+use crate::plugins::node::NodeRuntime;
 use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// 执行选项
+#[derive(Debug, Default)]
+pub struct ExecuteOptions {
+    /// 插件 id，授予的权限按插件记录
+    pub plugin_id: Option<String>,
+    /// 调用 id，用于取消，为空时自动生成
+    pub call_id: Option<String>,
+    /// 超时时间，为空时使用插件头部的 `@timeout`（毫秒），再没有时使用运行时的默认值
+    pub timeout: Option<Duration>,
+    /// 接收插件的输出和进度
+    pub events: Option<UnboundedSender<CallEvent>>,
+}
 
 /// 插件管理器
+#[derive(Clone)]
 pub struct PluginManager {}
 
 impl PluginManager {
@@ -11,46 +31,110 @@ impl PluginManager {
     }
 
     /// 执行插件工具
-    pub async fn execute(&self, content: &str, tool: &str, args: Value) -> Result<Value> {
-        let runtime = crate::plugins::node::NODE_RUNTIME.lock().await;
-        let runtime = runtime
-            .as_ref()
-            .ok_or_else(|| PluginError::Plugin("Node运行时未初始化".to_string()))?;
+    pub async fn execute(
+        &self,
+        content: &str,
+        tool: &str,
+        args: Value,
+        options: ExecuteOptions,
+    ) -> Result<Value> {
+        self.call(content, "mjs", tool, args, options).await
+    }
 
-        if !runtime.check_installed() {
-            return Err(PluginError::NodeNotInstalled);
+    /// 执行未编译的 TypeScript 插件工具
+    ///
+    /// 先在后端编译为 JavaScript，编译结果按内容哈希缓存，错误的调用栈指向 TypeScript 源码。
+    pub async fn execute_typescript(
+        &self,
+        content: &str,
+        tool: &str,
+        args: Value,
+        options: ExecuteOptions,
+    ) -> Result<Value> {
+        self.call(content, "ts", tool, args, options).await
+    }
+
+    /// 取消进行中的调用
+    pub async fn cancel(&self, call_id: &str) -> Result<()> {
+        let pool = {
+            let runtime = crate::plugins::node::NODE_RUNTIME.lock().await;
+            Self::runtime(runtime.as_ref())?.pool()?
+        };
+        if pool.cancel(call_id) {
+            Ok(())
+        } else {
+            Err(PluginError::NotFound(format!("调用 {}", call_id)))
         }
+    }
 
-        // 构造执行脚本
-        let script = format!(
-            r#"
-            {content}
-            (async () => {{
-                try {{
-                    if (typeof {tool} !== 'function') {{
-                        throw new Error(`can't find function '{tool}' or it's not a function`);
-                    }}
-                    const args = {args_json};
-                    const result = await {tool}(args);
-                    console.log(JSON.stringify({{ result: result !== undefined ? result : null }}));
-                }} catch (error) {{
-                    console.log(JSON.stringify({{ 
-                        error: error instanceof Error ? error.message : String(error) 
-                    }}));
-                }}
-            }})();
-            "#,
-            content = content,
-            tool = tool,
-            args_json = serde_json::to_string(&args)?
+    /// 交给进程池执行，不在执行期间持有运行时的锁
+    ///
+    /// 插件作为模块加载，只能调用它导出的函数，参数经标准输入以 JSON 传递，不会拼接进源码。
+    /// 每次调用先按审批策略检查，工具标识为 `plugin:{插件 id}/{函数}`，没有插件 id 时为 `plugin:*/{函数}`。
+    /// 插件头部声明的权限首次出现时需要用户授予，执行时只能使用声明过的权限。
+    async fn call(
+        &self,
+        content: &str,
+        extension: &str,
+        tool: &str,
+        args: Value,
+        options: ExecuteOptions,
+    ) -> Result<Value> {
+        if !manifest::exports_function(content, tool) {
+            return Err(PluginError::ToolNotFound(tool.to_string()));
+        }
+        let target = format!(
+            "plugin:{}/{}",
+            options.plugin_id.as_deref().unwrap_or("*"),
+            tool
         );
+        crate::plugins::approval::check(&target, &args)
+            .await
+            .map_err(PluginError::Denied)?;
+        let permissions = Permissions::parse(content)?;
+        permission::authorize(options.plugin_id.as_deref(), &permissions).await?;
+        let (pool, default_timeout) = {
+            let runtime = crate::plugins::node::NODE_RUNTIME.lock().await;
+            let runtime = Self::runtime(runtime.as_ref())?;
+            (runtime.pool()?, runtime.timeout())
+        };
+        let timeout = options
+            .timeout
+            .or_else(|| {
+                manifest::parse_meta(content)
+                    .get("timeout")
+                    .and_then(|timeout| timeout.parse().ok())
+                    .map(Duration::from_millis)
+            })
+            .unwrap_or(default_timeout);
         let env_vars = crate::plugins::node::env_list().await?;
-        let output = runtime.execute(&script, &env_vars).await?;
-        // 尝试将输出解析为 JSON 值
-        match serde_json::from_str::<Value>(&output) {
-            Ok(json_value) => Ok(json_value),
-            // JSON 解析失败时返回原始字符串
-            Err(_) => Ok(Value::String(output)),
+        let source_name = format!(
+            "{}.{}",
+            options.plugin_id.as_deref().unwrap_or("plugin"),
+            if extension == "ts" { "ts" } else { "js" }
+        );
+        let invocation = Invocation {
+            content,
+            extension,
+            source_name: &source_name,
+            tool,
+            args,
+            call_id: options
+                .call_id
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            timeout,
+            events: options.events,
+        };
+        pool.call(invocation, &permissions, &env_vars).await
+    }
+
+    fn runtime(runtime: Option<&NodeRuntime>) -> Result<&NodeRuntime> {
+        let runtime =
+            runtime.ok_or_else(|| PluginError::Plugin("Node运行时未初始化".to_string()))?;
+
+        if !runtime.check_installed() {
+            return Err(PluginError::NodeNotInstalled);
         }
+        Ok(runtime)
     }
 }
@@ -0,0 +1,702 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use crate::plugins::node::compile;
+use crate::plugins::node::detect::NodeInfo;
+use crate::plugins::node::env::EnvVar;
+use crate::plugins::node::error::{PluginError, Result};
+use crate::plugins::node::permission::Permissions;
+
+/// 执行进程的脚本，启动时写入缓存目录
+const WORKER_SCRIPT: &str = include_str!("worker.mjs");
+const WORKER_FILE: &str = "worker.mjs";
+/// 插件目录下的模块缓存目录，模块以内容哈希命名
+const CACHE_DIR: &str = ".cache";
+/// 插件目录下的依赖目录
+const MODULES_DIR: &str = "node_modules";
+/// 超过此时间未使用的缓存模块在启动时清理
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 同时运行的进程数上限，权限不同的插件在不同的进程中执行
+const MAX_WORKERS: usize = 4;
+/// 单个进程处理的调用数上限，超过后回收
+const MAX_CALLS: u64 = 1000;
+/// 单个进程加载的模块数上限，ES 模块加载后无法卸载，超过后回收
+const MAX_MODULES: usize = 64;
+/// 单个进程的内存上限，超过后回收
+const MAX_RSS: u64 = 512 * 1024 * 1024;
+/// 取消或超时后等待插件自行结束的时间，超过后结束进程
+const CANCEL_GRACE: Duration = Duration::from_secs(3);
+
+/// 插件抛出的错误，其余错误码表示模块加载失败
+const TOOL_ERROR: i64 = -32000;
+/// 插件执行了没有权限的操作
+const PERMISSION_ERROR: i64 = -32002;
+/// 协议消息的前缀，与 worker.mjs 一致
+const SENTINEL: &str = "\u{1e}ghostie\u{1e}";
+
+/// 插件输出的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// 插件在调用期间的一段输出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginLog {
+    pub stream: LogStream,
+    pub message: String,
+}
+
+/// 插件通过 `progress()` 报告的进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginProgress {
+    pub progress: f64,
+    #[serde(default)]
+    pub total: Option<f64>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// 调用期间产生的事件
+#[derive(Debug, Clone)]
+pub enum CallEvent {
+    Log(PluginLog),
+    Progress(PluginProgress),
+}
+
+#[derive(Debug, Deserialize)]
+struct LogParams {
+    id: u64,
+    #[serde(flatten)]
+    log: PluginLog,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProgressParams {
+    id: u64,
+    #[serde(flatten)]
+    progress: PluginProgress,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "lowercase")]
+enum Notification {
+    Log(LogParams),
+    Progress(ProgressParams),
+}
+
+#[derive(Debug, Deserialize)]
+struct DeniedData {
+    permission: String,
+    #[serde(default)]
+    resource: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ErrorData {
+    /// 插件抛出的错误的调用栈
+    #[serde(default)]
+    stack: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    #[serde(default)]
+    data: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    id: u64,
+    #[serde(default)]
+    result: Value,
+    error: Option<RpcError>,
+    /// 进程当前占用的内存
+    #[serde(default)]
+    rss: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Message {
+    Notification(Notification),
+    Response(Response),
+}
+
+/// 进行中的调用
+struct Call {
+    reply: oneshot::Sender<Response>,
+    events: Option<mpsc::UnboundedSender<CallEvent>>,
+}
+
+/// 一次插件调用
+pub struct Invocation<'a> {
+    /// 插件源码
+    pub content: &'a str,
+    /// 源码的扩展名，`mjs` 或 `ts`，TypeScript 先编译为 JavaScript 再写入缓存
+    pub extension: &'a str,
+    /// 源文件名，如 `{id}.ts`，出现在 source map 和错误的调用栈中
+    pub source_name: &'a str,
+    pub tool: &'a str,
+    pub args: Value,
+    /// 调用 id，用于取消
+    pub call_id: String,
+    pub timeout: Duration,
+    /// 接收插件的输出和进度，未提供时输出写到标准错误
+    pub events: Option<mpsc::UnboundedSender<CallEvent>>,
+}
+
+/// 结束进程及其启动的子进程，不等待进程退出
+fn kill_tree(pid: u32) {
+    // 不等待 taskkill 结束，避免阻塞异步运行时
+    #[cfg(windows)]
+    let result = {
+        let mut cmd = std::process::Command::new("taskkill");
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        cmd.args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map(drop)
+    };
+    // 进程启动时创建了独立的进程组，组 id 即进程 id
+    #[cfg(not(windows))]
+    let result = match unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) } {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    };
+    if let Err(e) = result {
+        eprintln!("结束插件进程 {} 失败: {}", pid, e);
+    }
+}
+
+type Pending = Arc<Mutex<HashMap<u64, Call>>>;
+
+/// 调用栈中属于插件的帧，如 `at hello (abc.ts:12:5)`
+///
+/// 有 source map 时帧已指向源文件，否则指向缓存的模块文件，统一显示为源文件名。
+fn plugin_frames(stack: &str, source_name: &str, module_name: &str) -> Vec<String> {
+    stack
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim().strip_prefix("at ")?;
+            let (start, name) = [source_name, module_name]
+                .into_iter()
+                .find_map(|name| line.find(name).map(|start| (start, name)))?;
+            let location = &line[start + name.len()..];
+            let location = location.find(')').map_or(location, |end| &location[..end]);
+            let location = format!("{}{}", source_name, location);
+            Some(match line.split_once(" (") {
+                Some((function, _)) => format!("at {} ({})", function, location),
+                None => format!("at {}", location),
+            })
+        })
+        .collect()
+}
+
+/// 按行读取，容忍插件输出的非 UTF-8 内容
+async fn read_lines<R>(reader: R, mut line: impl FnMut(String))
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(reader).split(b'\n');
+    while let Ok(Some(bytes)) = lines.next_segment().await {
+        let text = String::from_utf8_lossy(&bytes);
+        line(text.trim_end_matches('\r').to_string());
+    }
+}
+
+/// 一个常驻的 Node 进程
+struct Worker {
+    child: Mutex<Child>,
+    stdin: tokio::sync::Mutex<ChildStdin>,
+    /// 等待响应的调用
+    pending: Pending,
+    /// 启动时权限和环境变量的指纹
+    profile: u64,
+    alive: Arc<AtomicBool>,
+    /// 不再接收新调用，进行中的调用结束后退出
+    retiring: AtomicBool,
+    calls: AtomicU64,
+    modules: Mutex<HashSet<PathBuf>>,
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+impl Worker {
+    fn load(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    fn available(&self) -> bool {
+        self.alive.load(Ordering::SeqCst) && !self.retiring.load(Ordering::SeqCst)
+    }
+
+    fn kill(&self) {
+        self.alive.store(false, Ordering::SeqCst);
+        let mut child = self.child.lock().unwrap();
+        if let (Ok(None), Some(pid)) = (child.try_wait(), child.id()) {
+            kill_tree(pid);
+        }
+        let _ = child.start_kill();
+    }
+
+    async fn send(&self, message: &Value) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(&line).await?;
+        stdin.flush().await
+    }
+}
+
+/// 插件执行进程池
+///
+/// 进程常驻并通过标准输入输出交换 JSON-RPC 消息。插件源码按内容哈希写入缓存目录，
+/// 每个进程只加载一次，多个调用可以在同一进程中并发执行。崩溃、超时或超出资源上限的进程会被回收。
+/// 进程以 Node 的权限模型启动，只有权限和环境变量都相同的插件共用进程。
+pub struct WorkerPool {
+    node: NodeInfo,
+    /// 插件目录，进程的工作目录
+    dir: PathBuf,
+    workers: Mutex<Vec<Arc<Worker>>>,
+    /// 进行中的调用，以调用 id 为键
+    running: Mutex<HashMap<String, CancellationToken>>,
+    next_id: AtomicU64,
+}
+
+fn profile_fingerprint(permissions: &Permissions, env_vars: &[(String, OsString)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    permissions.hash(&mut hasher);
+    env_vars.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl WorkerPool {
+    pub fn new(node: NodeInfo, plugins_dir: PathBuf) -> std::io::Result<Self> {
+        let cache = plugins_dir.join(CACHE_DIR);
+        fs::create_dir_all(&cache)?;
+        // 清理长期未使用的模块
+        if let Ok(entries) = fs::read_dir(&cache) {
+            for entry in entries.flatten() {
+                let stale = entry
+                    .metadata()
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .and_then(|time| SystemTime::now().duration_since(time).ok())
+                    .is_some_and(|age| age > CACHE_TTL);
+                if stale && entry.file_name() != WORKER_FILE {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+        let script = cache.join(WORKER_FILE);
+        if fs::read_to_string(&script).ok().as_deref() != Some(WORKER_SCRIPT) {
+            fs::write(&script, WORKER_SCRIPT)?;
+        }
+
+        Ok(Self {
+            node,
+            dir: plugins_dir,
+            workers: Mutex::new(Vec::new()),
+            running: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    fn cache_dir(&self) -> PathBuf {
+        self.dir.join(CACHE_DIR)
+    }
+
+    /// 把插件源码写入缓存目录，返回模块路径
+    ///
+    /// TypeScript 按源码、源文件名和编译器版本的哈希缓存编译结果，相同的源码只编译一次。
+    fn module(&self, content: &str, extension: &str, source_name: &str) -> Result<PathBuf> {
+        let typescript = extension == "ts";
+        let mut hasher = Sha256::new();
+        if typescript {
+            hasher.update(compile::CACHE_TAG);
+            hasher.update([0u8]);
+            hasher.update(source_name);
+            hasher.update([0u8]);
+        }
+        hasher.update(content);
+        let hash = format!("{:x}", hasher.finalize());
+        let path = self.cache_dir().join(format!("{}.mjs", hash));
+        if !path.exists() {
+            let code = if typescript {
+                compile::transpile(content, source_name)?
+            } else {
+                content.to_string()
+            };
+            let temp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+            fs::write(&temp, code)?;
+            fs::rename(&temp, &path)?;
+        }
+        Ok(path)
+    }
+
+    fn spawn(
+        &self,
+        permissions: &Permissions,
+        env_vars: &[(String, OsString)],
+        profile: u64,
+    ) -> Result<Worker> {
+        let worker_id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut cmd = Command::new(&self.node.path);
+        #[cfg(windows)]
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+        // 只允许读取缓存的模块和依赖
+        let module_dirs = [self.cache_dir(), self.dir.join(MODULES_DIR)];
+        cmd.args(permissions.node_args(&self.node, &module_dirs)?);
+        // 网络权限由 worker.mjs 检查
+        let sandbox = json!({ "net": permissions.net });
+        cmd.arg("--no-warnings")
+            .arg("--enable-source-maps")
+            .arg(self.cache_dir().join(WORKER_FILE))
+            .arg(sandbox.to_string())
+            .current_dir(&self.dir)
+            .env_clear()
+            .envs(env_vars.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(not(windows))]
+        cmd.process_group(0);
+
+        let mut child = cmd.spawn()?;
+        let stdin = child.stdin.take().ok_or("无法连接插件进程的标准输入")?;
+        let stdout = child.stdout.take().ok_or("无法连接插件进程的标准输出")?;
+        let stderr = child.stderr.take().ok_or("无法连接插件进程的标准错误")?;
+
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+        let alive = Arc::new(AtomicBool::new(true));
+
+        // 读取响应和插件输出，进程退出后让等待中的调用失败
+        let reader_pending = pending.clone();
+        let reader_alive = alive.clone();
+        tokio::spawn(async move {
+            read_lines(stdout, |line| {
+                let (output, message) = match line.find(SENTINEL) {
+                    Some(index) => (&line[..index], Some(&line[index + SENTINEL.len()..])),
+                    None => (line.as_str(), None),
+                };
+                if !output.is_empty() {
+                    eprintln!("[node worker {}] {}", worker_id, output);
+                }
+                match message.map(serde_json::from_str::<Message>) {
+                    Some(Ok(Message::Notification(notification))) => {
+                        let (id, event) = match notification {
+                            Notification::Log(params) => (params.id, CallEvent::Log(params.log)),
+                            Notification::Progress(params) => {
+                                (params.id, CallEvent::Progress(params.progress))
+                            }
+                        };
+                        let events = reader_pending
+                            .lock()
+                            .unwrap()
+                            .get(&id)
+                            .and_then(|call| call.events.clone());
+                        match (events, event) {
+                            (Some(events), event) => {
+                                let _ = events.send(event);
+                            }
+                            (None, CallEvent::Log(log)) => {
+                                eprintln!("[node worker {}] {}", worker_id, log.message)
+                            }
+                            (None, CallEvent::Progress(_)) => {}
+                        }
+                    }
+                    Some(Ok(Message::Response(response))) => {
+                        let call = reader_pending.lock().unwrap().remove(&response.id);
+                        if let Some(call) = call {
+                            let _ = call.reply.send(response);
+                        }
+                    }
+                    Some(Err(e)) => eprintln!("[node worker {}] 无法解析消息: {}", worker_id, e),
+                    None => {}
+                }
+            })
+            .await;
+            reader_alive.store(false, Ordering::SeqCst);
+            reader_pending.lock().unwrap().clear();
+        });
+
+        // 调用之外的输出
+        tokio::spawn(read_lines(stderr, move |line| {
+            eprintln!("[node worker {}] {}", worker_id, line);
+        }));
+
+        Ok(Worker {
+            child: Mutex::new(child),
+            stdin: tokio::sync::Mutex::new(stdin),
+            pending,
+            profile,
+            alive,
+            retiring: AtomicBool::new(false),
+            calls: AtomicU64::new(0),
+            modules: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// 在权限和环境变量相同的进程中选择负载最低的，都在忙且未达到上限时启动新进程
+    fn acquire(&self, permissions: &Permissions, env_list: &[EnvVar]) -> Result<Arc<Worker>> {
+        let env_vars = permissions.env_vars(&self.node, env_list);
+        let profile = profile_fingerprint(permissions, &env_vars);
+        let mut workers = self.workers.lock().unwrap();
+
+        workers.retain(|worker| {
+            let keep = worker.alive.load(Ordering::SeqCst)
+                && !(worker.retiring.load(Ordering::SeqCst) && worker.load() == 0);
+            if !keep {
+                worker.kill();
+            }
+            keep
+        });
+
+        let available: Vec<&Arc<Worker>> = workers
+            .iter()
+            .filter(|worker| worker.profile == profile && worker.available())
+            .collect();
+        let idlest = available.iter().min_by_key(|worker| worker.load());
+        if let Some(worker) = idlest {
+            if worker.load() == 0 || available.len() >= MAX_WORKERS {
+                return Ok((*worker).clone());
+            }
+        }
+
+        // 达到上限时先回收其他插件的空闲进程
+        if workers.len() >= MAX_WORKERS {
+            if let Some(index) = workers
+                .iter()
+                .position(|worker| worker.profile != profile && worker.load() == 0)
+            {
+                workers.remove(index).kill();
+            }
+        }
+
+        let worker = Arc::new(self.spawn(permissions, &env_vars, profile)?);
+        workers.push(worker.clone());
+        Ok(worker)
+    }
+
+    /// 移出进程池并结束进程
+    fn remove(&self, worker: &Arc<Worker>) {
+        worker.kill();
+        self.workers
+            .lock()
+            .unwrap()
+            .retain(|other| !Arc::ptr_eq(other, worker));
+    }
+
+    /// 取消进行中的调用
+    ///
+    /// 先通知插件中止，插件在宽限期内没有结束时才结束所在的进程及其子进程，见 [`Self::abort`]。
+    /// 调用不存在时返回 false。
+    pub fn cancel(&self, call_id: &str) -> bool {
+        let Some(ct) = self.running.lock().unwrap().remove(call_id) else {
+            return false;
+        };
+        ct.cancel();
+        true
+    }
+
+    /// 通知插件中止调用，宽限期内没有结束时结束进程
+    ///
+    /// 插件通过 `abortSignal()` 得知调用被取消。卡在同步代码中或不理会取消的插件
+    /// 会连同所在进程中的其他调用一起结束。
+    fn abort(&self, worker: &Arc<Worker>, id: u64, reply: oneshot::Receiver<Response>) {
+        // 调用方已不再等待，之后的输出不再转发
+        if let Some(call) = worker.pending.lock().unwrap().get_mut(&id) {
+            call.events = None;
+        }
+        let worker = worker.clone();
+        tokio::spawn(async move {
+            let cancel = json!({ "jsonrpc": "2.0", "method": "cancel", "params": { "id": id } });
+            // 插件结束时进程照常回复，进程退出时回复的发送端被丢弃
+            let finished = worker.send(&cancel).await.is_ok()
+                && tokio::time::timeout(CANCEL_GRACE, reply).await.is_ok();
+            if !finished {
+                worker.pending.lock().unwrap().remove(&id);
+                worker.kill();
+            }
+        });
+    }
+
+    /// 执行插件中导出的函数
+    ///
+    /// 返回与插件输出一致的 `{ result }`，插件抛出错误时为 `{ error }`，
+    /// 执行了 `permissions` 之外的操作时返回 [`PluginError::PermissionDenied`]。
+    /// 超时与取消的处理相同，先通知插件中止，宽限期后仍未结束再回收进程。
+    pub async fn call(
+        &self,
+        invocation: Invocation<'_>,
+        permissions: &Permissions,
+        env_list: &[EnvVar],
+    ) -> Result<Value> {
+        let Invocation {
+            content,
+            extension,
+            source_name,
+            tool,
+            args,
+            call_id,
+            timeout,
+            events,
+        } = invocation;
+        let module = self.module(content, extension, source_name)?;
+        let worker = self.acquire(permissions, env_list)?;
+
+        let ct = CancellationToken::new();
+        {
+            let mut running = self.running.lock().unwrap();
+            if running.contains_key(&call_id) {
+                return Err(PluginError::Plugin(format!("调用 {} 已在执行", call_id)));
+            }
+            running.insert(call_id.clone(), ct.clone());
+        }
+        let result = self
+            .invoke(
+                &worker,
+                module,
+                source_name,
+                tool,
+                args,
+                timeout,
+                events,
+                &ct,
+            )
+            .await;
+        self.running.lock().unwrap().remove(&call_id);
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn invoke(
+        &self,
+        worker: &Arc<Worker>,
+        module: PathBuf,
+        source_name: &str,
+        tool: &str,
+        args: Value,
+        timeout: Duration,
+        events: Option<mpsc::UnboundedSender<CallEvent>>,
+        ct: &CancellationToken,
+    ) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        worker
+            .pending
+            .lock()
+            .unwrap()
+            .insert(id, Call { reply: tx, events });
+        let module_name = module
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "call",
+            "params": { "module": module, "tool": tool, "args": args },
+        });
+        if let Err(e) = worker.send(&request).await {
+            worker.pending.lock().unwrap().remove(&id);
+            self.remove(worker);
+            return Err(e.into());
+        }
+        let modules = {
+            let mut modules = worker.modules.lock().unwrap();
+            modules.insert(module);
+            modules.len()
+        };
+
+        let mut rx = rx;
+        let reply = tokio::select! {
+            reply = &mut rx => reply,
+            _ = tokio::time::sleep(timeout) => {
+                self.abort(worker, id, rx);
+                return Err(PluginError::Timeout);
+            }
+            _ = ct.cancelled() => {
+                self.abort(worker, id, rx);
+                return Err(PluginError::Cancelled);
+            }
+        };
+        worker.pending.lock().unwrap().remove(&id);
+        let response = match reply {
+            Ok(response) => response,
+            Err(_) => {
+                self.remove(worker);
+                return Err(PluginError::Plugin("插件进程意外退出".to_string()));
+            }
+        };
+
+        let calls = worker.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        if response.rss > MAX_RSS || calls >= MAX_CALLS || modules >= MAX_MODULES {
+            worker.retiring.store(true, Ordering::SeqCst);
+        }
+        if worker.retiring.load(Ordering::SeqCst) && worker.load() == 0 {
+            self.remove(worker);
+        }
+
+        let Some(error) = response.error else {
+            return Ok(json!({ "result": response.result }));
+        };
+        if error.code == PERMISSION_ERROR {
+            if let Ok(data) = serde_json::from_value::<DeniedData>(error.data.clone()) {
+                return Err(PluginError::PermissionDenied {
+                    permission: data.permission,
+                    resource: data.resource,
+                });
+            }
+        }
+        // 插件内的调用栈，已映射回源码的行列
+        let stack = serde_json::from_value::<ErrorData>(error.data)
+            .unwrap_or_default()
+            .stack
+            .map(|stack| plugin_frames(&stack, source_name, &module_name))
+            .unwrap_or_default();
+        if error.code == TOOL_ERROR {
+            if stack.is_empty() {
+                return Ok(json!({ "error": error.message }));
+            }
+            return Ok(json!({ "error": error.message, "stack": stack }));
+        }
+        let mut message = error.message;
+        for frame in stack {
+            message.push_str("\n    ");
+            message.push_str(&frame);
+        }
+        Err(PluginError::Plugin(message))
+    }
+}
@@ -0,0 +1,262 @@
+// 常驻的插件执行进程
+//
+// 以 Node 的权限模型启动，启动参数中的 JSON 给出允许连接的主机：{"net":["api.example.com"]}
+// 标准输入每行一条 JSON-RPC 请求：
+//   {"jsonrpc":"2.0","id":1,"method":"call","params":{"module":"/abs/path.mjs","tool":"fn","args":{}}}
+// 标准输出中 SENTINEL 之后到行尾的内容是协议消息，其余内容都是插件的输出。
+// 响应附带当前进程占用的内存 `rss`，供宿主判断是否回收：
+//   {"jsonrpc":"2.0","id":1,"result":...,"rss":123}
+//   {"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"...","data":{"stack":"..."}},"rss":123}
+// 没有权限的操作返回被拒绝的权限和资源：
+//   {"jsonrpc":"2.0","id":1,"error":{"code":-32002,"message":"...","data":{"permission":"fs.read","resource":"/etc"}},"rss":123}
+// 调用期间插件写到标准输出和标准错误的内容，以及通过 `progress()` 报告的进度以通知发送：
+//   {"jsonrpc":"2.0","method":"log","params":{"id":1,"stream":"stdout","message":"..."}}
+//   {"jsonrpc":"2.0","method":"progress","params":{"id":1,"progress":1,"total":10,"message":"..."}}
+// 宿主取消调用时发送通知，插件通过 `abortSignal()` 或函数的第二个参数 `{ signal }` 得知，
+// 调用照常以结果或错误结束：
+//   {"jsonrpc":"2.0","method":"cancel","params":{"id":1}}
+import { AsyncLocalStorage } from "node:async_hooks";
+import dgram from "node:dgram";
+import { createRequire } from "node:module";
+import net from "node:net";
+import path from "node:path";
+import { createInterface } from "node:readline";
+import { pathToFileURL } from "node:url";
+
+// 插件抛出的错误
+const TOOL_ERROR = -32000;
+// 模块加载失败
+const LOAD_ERROR = -32001;
+// 没有权限
+const PERMISSION_ERROR = -32002;
+
+// Node 权限模型中的权限名与插件声明的权限名的对应关系
+const PERMISSIONS = {
+  FileSystemRead: "fs.read",
+  FileSystemWrite: "fs.write",
+  ChildProcess: "process",
+  WorkerThreads: "worker",
+};
+
+const SENTINEL = "\x1eghostie\x1e";
+
+const write = process.stdout.write.bind(process.stdout);
+
+function send(message) {
+  write(SENTINEL + JSON.stringify({ jsonrpc: "2.0", ...message }) + "\n");
+}
+
+// 当前调用的 id，用于把输出归到对应的调用
+const current = new AsyncLocalStorage();
+
+// 截获插件的输出，调用之外的输出保持原样
+for (const stream of ["stdout", "stderr"]) {
+  const original = process[stream].write.bind(process[stream]);
+  process[stream].write = (chunk, encoding, callback) => {
+    const id = current.getStore();
+    if (id === undefined) {
+      return original(chunk, encoding, callback);
+    }
+    const message =
+      typeof chunk === "string"
+        ? chunk
+        : Buffer.from(chunk).toString(
+            typeof encoding === "string" ? encoding : "utf8",
+          );
+    send({
+      method: "log",
+      params: { id, stream, message: message.replace(/\n$/, "") },
+    });
+    const done = typeof encoding === "function" ? encoding : callback;
+    if (typeof done === "function") {
+      process.nextTick(done);
+    }
+    return true;
+  };
+}
+
+// 供插件报告进度：progress(已完成, 总量?, 说明?)
+globalThis.progress = (progress, total, message) => {
+  const id = current.getStore();
+  if (id === undefined || typeof progress !== "number") {
+    return;
+  }
+  send({
+    method: "progress",
+    params: {
+      id,
+      progress,
+      total: typeof total === "number" ? total : undefined,
+      message: message !== undefined ? String(message) : undefined,
+    },
+  });
+};
+
+// 进行中的调用，以 id 为键，用于取消
+const controllers = new Map();
+
+// 供插件响应取消：返回当前调用的 AbortSignal
+globalThis.abortSignal = () => controllers.get(current.getStore())?.signal;
+
+// 与 Node 权限模型的错误一致
+function accessDenied(permission, resource) {
+  const error = new Error(`Access to ${resource} has been restricted`);
+  error.code = "ERR_ACCESS_DENIED";
+  error.permission = permission;
+  error.resource = resource;
+  return error;
+}
+
+// 主机匹配 `host`、`*.domain`、`host:port` 或 `*`
+function hostAllowed(patterns, host, port) {
+  host = String(host).toLowerCase().replace(/^\[(.*)\]$/, "$1");
+  return patterns.some((pattern) => {
+    if (pattern === "*") {
+      return true;
+    }
+    const match = /^(.*?)(?::(\d+))?$/.exec(pattern.toLowerCase());
+    const [, name, allowedPort] = match;
+    if (allowedPort !== undefined && Number(allowedPort) !== Number(port)) {
+      return false;
+    }
+    const hostname = name.replace(/^\[(.*)\]$/, "$1");
+    return hostname.startsWith("*.")
+      ? host.endsWith(hostname.slice(1))
+      : host === hostname;
+  });
+}
+
+// Node 的权限模型不限制网络，在所有 TCP 连接的入口检查主机
+function guardNetwork(patterns) {
+  if (patterns.includes("*")) {
+    return;
+  }
+  const connect = net.Socket.prototype.connect;
+  net.Socket.prototype.connect = function (...args) {
+    // net.connect 传入的是规范化后的参数数组
+    const first = Array.isArray(args[0]) ? args[0][0] : args[0];
+    let options;
+    if (first !== null && typeof first === "object") {
+      options = first;
+    } else if (typeof first === "string" && !/^\d+$/.test(first)) {
+      options = { path: first };
+    } else {
+      options = { port: first, host: args[1] };
+    }
+    if (options.path !== undefined) {
+      throw accessDenied("net", String(options.path));
+    }
+    const host = options.host ?? "localhost";
+    if (!hostAllowed(patterns, host, options.port)) {
+      throw accessDenied("net", `${host}:${options.port}`);
+    }
+    return connect.apply(this, args);
+  };
+  // UDP 只在允许任意主机时可用
+  dgram.createSocket = () => {
+    throw accessDenied("net", "udp");
+  };
+}
+
+const sandbox = JSON.parse(process.argv[2] ?? "{}");
+guardNetwork(Array.isArray(sandbox.net) ? sandbox.net : []);
+
+// 兼容在 ES 模块中使用 require 的插件，从插件目录解析依赖
+globalThis.require = createRequire(path.join(process.cwd(), "index.js"));
+
+// 模块文件名即内容哈希，每个模块只加载一次
+const modules = new Map();
+
+function load(file) {
+  let module = modules.get(file);
+  if (!module) {
+    module = import(pathToFileURL(file).href);
+    modules.set(file, module);
+    module.catch(() => modules.delete(file));
+  }
+  return module;
+}
+
+function reply(message) {
+  send({ ...message, rss: process.memoryUsage().rss });
+}
+
+// 错误或其原因链中的权限错误
+function denied(error) {
+  for (let cause = error; cause instanceof Error; cause = cause.cause) {
+    if (cause.code === "ERR_ACCESS_DENIED") {
+      return cause;
+    }
+  }
+}
+
+function fail(id, code, error) {
+  const message = error instanceof Error ? error.message : String(error);
+  const access = denied(error);
+  if (access) {
+    // process.binding 等内部接口被拒绝时没有权限名
+    const permission = access.permission
+      ? (PERMISSIONS[access.permission] ?? access.permission)
+      : "native";
+    const resource = access.resource ?? "";
+    return reply({
+      id,
+      error: { code: PERMISSION_ERROR, message, data: { permission, resource } },
+    });
+  }
+  // 以 --enable-source-maps 启动，调用栈已映射回 TypeScript 源码
+  const stack = error instanceof Error ? error.stack : undefined;
+  reply({ id, error: { code, message, data: stack ? { stack } : undefined } });
+}
+
+async function call(id, params) {
+  const controller = new AbortController();
+  controllers.set(id, controller);
+  try {
+    await invoke(id, params, controller.signal);
+  } finally {
+    controllers.delete(id);
+  }
+}
+
+async function invoke(id, { module: file, tool, args }, signal) {
+  let module;
+  try {
+    module = await load(file);
+  } catch (error) {
+    return fail(id, LOAD_ERROR, error);
+  }
+  try {
+    // 只调用模块自身导出的具名函数
+    const exported =
+      typeof tool === "string" &&
+      tool !== "default" &&
+      Object.prototype.hasOwnProperty.call(module, tool);
+    const fn = exported ? module[tool] : undefined;
+    if (typeof fn !== "function") {
+      throw new Error(`can't find function '${tool}' or it's not a function`);
+    }
+    const result = await fn(args, { signal });
+    reply({ id, result: result !== undefined ? result : null });
+  } catch (error) {
+    fail(id, TOOL_ERROR, error);
+  }
+}
+
+createInterface({ input: process.stdin })
+  .on("line", (line) => {
+    let request;
+    try {
+      request = JSON.parse(line);
+    } catch {
+      return;
+    }
+    if (request.method === "call") {
+      current
+        .run(request.id, () => call(request.id, request.params))
+        .catch((error) => fail(request.id, TOOL_ERROR, error));
+    } else if (request.method === "cancel") {
+      controllers.get(request.params?.id)?.abort();
+    }
+  })
+  .on("close", () => process.exit(0));
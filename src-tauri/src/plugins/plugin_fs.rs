@@ -1,27 +1,248 @@
-use crate::utils::file;
+use crate::plugins::node::manifest::{self, PluginManifest};
+use crate::utils::{diff, file};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 插件默认保留的修订数，超出时删除最早的修订，可以为每个插件单独设置
+const DEFAULT_MAX_REVISIONS: usize = 50;
+
+// 修订和回收站的目录，位于插件目录下
+const HISTORY_DIR: &str = ".history";
+const TRASH_DIR: &str = ".trash";
+const REVISIONS_FILE: &str = "revisions.json";
+const HISTORY_SETTINGS_FILE: &str = "settings.json";
+const TRASH_ENTRY_FILE: &str = "entry.json";
+
+// 修改修订记录和回收站时加锁，编辑器频繁保存时避免互相覆盖
+static HISTORY_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// 插件及其清单
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginEntry {
+    pub id: String,
+    #[serde(flatten)]
+    pub manifest: PluginManifest,
+}
+
+/// 插件的一个修订
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginRevision {
+    /// 修订 id，由保存时间生成
+    pub id: String,
+    /// 最后一次写入该修订的时间
+    pub time: String,
+    pub message: Option<String>,
+    /// 内容的字节数
+    pub size: usize,
+}
+
+/// 插件的修订设置，保存在修订目录中，随插件移入回收站
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HistorySettings {
+    max_revisions: usize,
+}
+
+/// 回收站中的插件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedPlugin {
+    pub id: String,
+    pub name: String,
+    pub deleted_at: String,
+    /// 随插件一起保留的修订数
+    #[serde(default)]
+    pub revisions: usize,
+}
 
 // 获取插件目录
 fn get_plugin_dir() -> Result<PathBuf, String> {
     file::get_plugins_dir().ok_or_else(|| "无法获取插件目录".to_string())
 }
 
+// 校验插件 id，id 会拼进文件路径，只允许字母、数字、- 和 _
+fn validate_id(id: &str) -> Result<(), String> {
+    let id_valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+    if id_valid {
+        Ok(())
+    } else {
+        Err(format!("插件 id 不合法: {}", id))
+    }
+}
+
 // 获取插件文件路径
 fn get_plugin_file_path(id: &str) -> Result<PathBuf, String> {
+    validate_id(id)?;
     let plugin_dir = get_plugin_dir()?;
     let file_path = plugin_dir.join(format!("{}.ts", id));
     Ok(file_path)
 }
 
-// 保存插件内容
-#[tauri::command]
-pub async fn plugin_save_content(id: String, content: String) -> Result<(), String> {
-    let file_path = get_plugin_file_path(&id)?;
+// 插件的修订目录
+fn get_history_dir(id: &str) -> Result<PathBuf, String> {
+    validate_id(id)?;
+    Ok(get_plugin_dir()?.join(HISTORY_DIR).join(id))
+}
+
+// 回收站目录
+fn get_trash_dir() -> Result<PathBuf, String> {
+    Ok(get_plugin_dir()?.join(TRASH_DIR))
+}
+
+// 回收站中某个插件的目录
+fn get_trashed_dir(id: &str) -> Result<PathBuf, String> {
+    validate_id(id)?;
+    Ok(get_trash_dir()?.join(id))
+}
+
+// 读取修订记录，从旧到新排列
+fn load_revisions(history_dir: &Path) -> Result<Vec<PluginRevision>, String> {
+    let path = history_dir.join(REVISIONS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取修订记录失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析修订记录失败: {}", e))
+}
+
+// 读取插件保留的修订数，没有设置时使用默认值
+fn load_max_revisions(history_dir: &Path) -> usize {
+    fs::read_to_string(history_dir.join(HISTORY_SETTINGS_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<HistorySettings>(&content).ok())
+        .map(|settings| settings.max_revisions)
+        .unwrap_or(DEFAULT_MAX_REVISIONS)
+}
+
+// 删除超出保留数的最早修订
+fn prune_revisions(history_dir: &Path, revisions: &mut Vec<PluginRevision>, max_revisions: usize) {
+    let excess = revisions.len().saturating_sub(max_revisions);
+    for old in revisions.drain(..excess) {
+        let _ = fs::remove_file(revision_file(history_dir, &old.id));
+    }
+}
+
+// 写入修订记录
+fn save_revisions(history_dir: &Path, revisions: &[PluginRevision]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(revisions)
+        .map_err(|e| format!("序列化修订记录失败: {}", e))?;
+    write_atomic(&history_dir.join(REVISIONS_FILE), &content)
+}
+
+// 先写临时文件再替换，避免中途失败留下不完整的文件
+fn write_atomic(path: &Path, content: &str) -> Result<(), String> {
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, content).map_err(|e| format!("写入文件失败: {}", e))?;
+    fs::rename(&temp, path).map_err(|e| format!("写入文件失败: {}", e))
+}
+
+// 修订内容的文件路径
+fn revision_file(history_dir: &Path, revision: &str) -> PathBuf {
+    history_dir.join(format!("{}.ts", revision))
+}
+
+// 读取修订内容，修订不存在时报错
+fn read_revision(id: &str, revision: &str) -> Result<String, String> {
+    let history_dir = get_history_dir(id)?;
+    validate_id(revision).map_err(|_| format!("修订 id 不合法: {}", revision))?;
+    if !load_revisions(&history_dir)?
+        .iter()
+        .any(|r| r.id == revision)
+    {
+        return Err(format!("插件 {} 没有修订 {}", id, revision));
+    }
+    fs::read_to_string(revision_file(&history_dir, revision))
+        .map_err(|e| format!("读取修订内容失败: {}", e))
+}
+
+// 记录一个修订
+//
+// 内容与最新修订相同时不记录，超出插件保留的修订数时删除最早的修订。
+fn record_revision(
+    history_dir: &Path,
+    content: &str,
+    message: Option<String>,
+    time: String,
+) -> Result<(), String> {
+    fs::create_dir_all(history_dir).map_err(|e| format!("创建修订目录失败: {}", e))?;
+    let mut revisions = load_revisions(history_dir)?;
+
+    if let Some(last) = revisions.last_mut() {
+        let last_file = revision_file(history_dir, &last.id);
+        if fs::read_to_string(&last_file).is_ok_and(|last_content| last_content == content) {
+            if last.message.is_none() && message.is_some() {
+                last.message = message;
+                save_revisions(history_dir, &revisions)?;
+            }
+            return Ok(());
+        }
+    }
+
+    let base = chrono::Local::now().format("%Y%m%d%H%M%S%3f").to_string();
+    let mut revision = base.clone();
+    let mut suffix = 1;
+    while revisions.iter().any(|r| r.id == revision) {
+        revision = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    write_atomic(&revision_file(history_dir, &revision), content)?;
+    revisions.push(PluginRevision {
+        id: revision,
+        time,
+        message,
+        size: content.len(),
+    });
+
+    prune_revisions(history_dir, &mut revisions, load_max_revisions(history_dir));
+    save_revisions(history_dir, &revisions)
+}
+
+// 写入插件文件并记录修订
+fn save_plugin(id: &str, content: &str, message: Option<String>) -> Result<(), String> {
+    let file_path = get_plugin_file_path(id)?;
+    let history_dir = get_history_dir(id)?;
+    let _lock = HISTORY_LOCK.lock().unwrap();
+
+    // 启用修订之前保存的插件，先把原有内容记为第一个修订
+    if load_revisions(&history_dir)?.is_empty() {
+        if let Ok(previous) = fs::read_to_string(&file_path) {
+            if previous != content {
+                let modified = fs::metadata(&file_path)
+                    .and_then(|metadata| metadata.modified())
+                    .map(chrono::DateTime::<chrono::Local>::from)
+                    .unwrap_or_else(|_| chrono::Local::now());
+                record_revision(
+                    &history_dir,
+                    &previous,
+                    Some("启用修订前的内容".to_string()),
+                    modified.to_rfc3339(),
+                )?;
+            }
+        }
+    }
 
     fs::write(&file_path, content).map_err(|e| format!("保存插件内容失败: {}", e))?;
+    let message = message.filter(|message| !message.trim().is_empty());
+    record_revision(
+        &history_dir,
+        content,
+        message,
+        chrono::Local::now().to_rfc3339(),
+    )
+}
 
-    Ok(())
+// 保存插件内容，每次保存记录一个修订，message 是可选的修订说明
+#[tauri::command]
+pub async fn plugin_save_content(
+    id: String,
+    content: String,
+    message: Option<String>,
+) -> Result<(), String> {
+    save_plugin(&id, &content, message)
 }
 
 // 获取插件内容
@@ -36,14 +257,39 @@ pub async fn plugin_get_content(id: String) -> Result<String, String> {
     fs::read_to_string(&file_path).map_err(|e| format!("读取插件内容失败: {}", e))
 }
 
-// 删除插件
+// 删除插件，插件和它的修订移入回收站
 #[tauri::command]
 pub async fn plugin_delete(id: String) -> Result<(), String> {
     let file_path = get_plugin_file_path(&id)?;
+    if !file_path.exists() {
+        return Ok(());
+    }
 
-    if file_path.exists() {
-        fs::remove_file(&file_path).map_err(|e| format!("删除插件文件失败: {}", e))?;
+    let content = fs::read_to_string(&file_path).map_err(|e| format!("读取插件内容失败: {}", e))?;
+    let history_dir = get_history_dir(&id)?;
+    let _lock = HISTORY_LOCK.lock().unwrap();
+    let entry = TrashedPlugin {
+        id: id.clone(),
+        name: manifest::parse_manifest(&content).name,
+        deleted_at: chrono::Local::now().to_rfc3339(),
+        revisions: load_revisions(&history_dir)?.len(),
+    };
+
+    // 同一插件再次删除时替换回收站中较早的版本
+    let trash_dir = get_trashed_dir(&id)?;
+    if trash_dir.exists() {
+        fs::remove_dir_all(&trash_dir).map_err(|e| format!("清理回收站失败: {}", e))?;
+    }
+    fs::create_dir_all(&trash_dir).map_err(|e| format!("创建回收站目录失败: {}", e))?;
+    if history_dir.exists() {
+        fs::rename(&history_dir, trash_dir.join(HISTORY_DIR))
+            .map_err(|e| format!("移动插件修订失败: {}", e))?;
     }
+    let entry = serde_json::to_string_pretty(&entry).map_err(|e| e.to_string())?;
+    fs::write(trash_dir.join(TRASH_ENTRY_FILE), entry)
+        .map_err(|e| format!("写入回收站记录失败: {}", e))?;
+    fs::rename(&file_path, trash_dir.join(format!("{}.ts", id)))
+        .map_err(|e| format!("删除插件文件失败: {}", e))?;
 
     Ok(())
 }
@@ -61,7 +307,8 @@ pub async fn plugin_list() -> Result<Vec<String>, String> {
 
         if path.is_file() && path.extension().map_or(false, |ext| ext == "ts") {
             if let Some(file_stem) = path.file_stem() {
-                if let Some(id) = file_stem.to_str() {
+                // 跳过 id 不合法的文件，它们无法通过其他命令访问
+                if let Some(id) = file_stem.to_str().filter(|id| validate_id(id).is_ok()) {
                     plugin_ids.push(id.to_string());
                 }
             }
@@ -70,3 +317,169 @@ pub async fn plugin_list() -> Result<Vec<String>, String> {
 
     Ok(plugin_ids)
 }
+
+// 解析插件清单，解析中发现的问题在 errors 中
+#[tauri::command]
+pub async fn plugin_manifest(id: String) -> Result<PluginManifest, String> {
+    let content = plugin_get_content(id).await?;
+    Ok(manifest::parse_manifest(&content))
+}
+
+// 解析未保存的插件源码的清单，如插件市场中的插件
+#[tauri::command]
+pub async fn plugin_parse_content(content: String) -> Result<PluginManifest, String> {
+    Ok(manifest::parse_manifest(&content))
+}
+
+// 列出所有插件及其清单
+#[tauri::command]
+pub async fn plugin_list_detailed() -> Result<Vec<PluginEntry>, String> {
+    let mut ids = plugin_list().await?;
+    ids.sort();
+
+    let mut plugins = Vec::new();
+    for id in ids {
+        let content = plugin_get_content(id.clone()).await?;
+        plugins.push(PluginEntry {
+            manifest: manifest::parse_manifest(&content),
+            id,
+        });
+    }
+    Ok(plugins)
+}
+
+// 列出插件的修订，最新的在前
+#[tauri::command]
+pub async fn plugin_revisions(id: String) -> Result<Vec<PluginRevision>, String> {
+    let mut revisions = load_revisions(&get_history_dir(&id)?)?;
+    revisions.reverse();
+    Ok(revisions)
+}
+
+// 获取修订的内容
+#[tauri::command]
+pub async fn plugin_revision_content(id: String, revision: String) -> Result<String, String> {
+    read_revision(&id, &revision)
+}
+
+// 比较两个修订，to 为空时与当前内容比较，返回统一格式的差异，相同时为空字符串
+#[tauri::command]
+pub async fn plugin_revision_diff(
+    id: String,
+    from: String,
+    to: Option<String>,
+) -> Result<String, String> {
+    let old = read_revision(&id, &from)?;
+    let (new, new_label) = match to {
+        Some(to) => (read_revision(&id, &to)?, format!("{}.ts@{}", id, to)),
+        None => (plugin_get_content(id.clone()).await?, format!("{}.ts", id)),
+    };
+    Ok(diff::unified(
+        &old,
+        &new,
+        &format!("{}.ts@{}", id, from),
+        &new_label,
+    ))
+}
+
+// 把插件恢复到某个修订，恢复本身也记为一个新的修订
+#[tauri::command]
+pub async fn plugin_revision_restore(id: String, revision: String) -> Result<String, String> {
+    let content = read_revision(&id, &revision)?;
+    save_plugin(&id, &content, Some(format!("恢复到修订 {}", revision)))?;
+    Ok(content)
+}
+
+// 获取插件保留的修订数
+#[tauri::command]
+pub async fn plugin_revision_limit(id: String) -> Result<usize, String> {
+    Ok(load_max_revisions(&get_history_dir(&id)?))
+}
+
+// 设置插件保留的修订数，超出的最早修订立即删除
+#[tauri::command]
+pub async fn plugin_revision_limit_set(id: String, limit: usize) -> Result<(), String> {
+    if limit == 0 {
+        return Err("至少保留一个修订".to_string());
+    }
+    let history_dir = get_history_dir(&id)?;
+    let _lock = HISTORY_LOCK.lock().unwrap();
+    fs::create_dir_all(&history_dir).map_err(|e| format!("创建修订目录失败: {}", e))?;
+    let settings = serde_json::to_string_pretty(&HistorySettings {
+        max_revisions: limit,
+    })
+    .map_err(|e| e.to_string())?;
+    write_atomic(&history_dir.join(HISTORY_SETTINGS_FILE), &settings)?;
+
+    let mut revisions = load_revisions(&history_dir)?;
+    if revisions.len() > limit {
+        prune_revisions(&history_dir, &mut revisions, limit);
+        save_revisions(&history_dir, &revisions)?;
+    }
+    Ok(())
+}
+
+// 列出回收站中的插件，最近删除的在前
+#[tauri::command]
+pub async fn plugin_trash_list() -> Result<Vec<TrashedPlugin>, String> {
+    let trash_dir = get_trash_dir()?;
+    if !trash_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut plugins = Vec::new();
+    for entry in fs::read_dir(trash_dir).map_err(|e| format!("读取回收站失败: {}", e))? {
+        let entry = entry.map_err(|e| format!("读取目录条目失败: {}", e))?;
+        let Ok(content) = fs::read_to_string(entry.path().join(TRASH_ENTRY_FILE)) else {
+            continue;
+        };
+        if let Ok(plugin) = serde_json::from_str::<TrashedPlugin>(&content) {
+            plugins.push(plugin);
+        }
+    }
+    plugins.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(plugins)
+}
+
+// 从回收站恢复插件及其修订，同 id 的插件已存在时报错
+#[tauri::command]
+pub async fn plugin_trash_restore(id: String) -> Result<(), String> {
+    let trash_dir = get_trashed_dir(&id)?;
+    let trashed_file = trash_dir.join(format!("{}.ts", id));
+    if !trashed_file.exists() {
+        return Err(format!("回收站中没有插件: {}", id));
+    }
+    let file_path = get_plugin_file_path(&id)?;
+    if file_path.exists() {
+        return Err(format!("插件已存在: {}", id));
+    }
+
+    let _lock = HISTORY_LOCK.lock().unwrap();
+    let history_dir = get_history_dir(&id)?;
+    let trashed_history = trash_dir.join(HISTORY_DIR);
+    if trashed_history.exists() {
+        if history_dir.exists() {
+            fs::remove_dir_all(&history_dir).map_err(|e| format!("清理插件修订失败: {}", e))?;
+        }
+        if let Some(parent) = history_dir.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建修订目录失败: {}", e))?;
+        }
+        fs::rename(&trashed_history, &history_dir)
+            .map_err(|e| format!("恢复插件修订失败: {}", e))?;
+    }
+    fs::rename(&trashed_file, &file_path).map_err(|e| format!("恢复插件文件失败: {}", e))?;
+    fs::remove_dir_all(&trash_dir).map_err(|e| format!("清理回收站失败: {}", e))?;
+
+    Ok(())
+}
+
+// 清空回收站，其中的插件和修订将被永久删除
+#[tauri::command]
+pub async fn plugin_trash_empty() -> Result<(), String> {
+    let trash_dir = get_trash_dir()?;
+    let _lock = HISTORY_LOCK.lock().unwrap();
+    if trash_dir.exists() {
+        fs::remove_dir_all(&trash_dir).map_err(|e| format!("清空回收站失败: {}", e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,257 @@
+/// 行的编辑操作，下标分别指向旧文本和新文本的行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// 差异上下文的行数
+const CONTEXT: usize = 3;
+
+/// Myers 算法求最短编辑序列
+///
+/// 先去掉相同的开头和结尾，每一步只保存用到的对角线，内存与差异大小的平方成正比。
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    let (n, m) = (a.len() as isize, b.len() as isize);
+
+    // trace[d + 1] 保存第 d 步后对角线 -d..=d 上的最远 x，trace[0] 只是占位
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut v = vec![0isize; 1];
+    'search: for d in 0..=(n + m) {
+        let mut next = vec![0isize; (2 * d + 1) as usize];
+        let get = |v: &[isize], k: isize| v[(k + d - 1) as usize];
+        for k in (-d..=d).step_by(2) {
+            let mut x = if d == 0 {
+                0
+            } else if k == -d || (k != d && get(&v, k - 1) < get(&v, k + 1)) {
+                get(&v, k + 1)
+            } else {
+                get(&v, k - 1) + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            next[(k + d) as usize] = x;
+            if x >= n && y >= m {
+                trace.push(v);
+                trace.push(next);
+                break 'search;
+            }
+        }
+        trace.push(std::mem::replace(&mut v, next));
+    }
+
+    // 从终点沿着每一步的选择倒推
+    let mut result = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (0..trace.len() as isize - 1).rev() {
+        let prev = &trace[d as usize];
+        let get = |k: isize| prev[(k + d - 1) as usize];
+        let k = x - y;
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) {
+                k + 1
+            } else {
+                k - 1
+            };
+            (get(prev_k), get(prev_k) - prev_k)
+        };
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            result.push(Edit::Equal(x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                y -= 1;
+                result.push(Edit::Insert(y as usize));
+            } else {
+                x -= 1;
+                result.push(Edit::Delete(x as usize));
+            }
+        }
+    }
+    result.reverse();
+
+    let shift = |edit: Edit| match edit {
+        Edit::Equal(i, j) => Edit::Equal(i + prefix, j + prefix),
+        Edit::Delete(i) => Edit::Delete(i + prefix),
+        Edit::Insert(j) => Edit::Insert(j + prefix),
+    };
+    (0..prefix)
+        .map(|i| Edit::Equal(i, i))
+        .chain(result.into_iter().map(shift))
+        .chain((0..suffix).map(|i| Edit::Equal(old.len() - suffix + i, new.len() - suffix + i)))
+        .collect()
+}
+
+/// 生成统一格式（unified diff）的差异，内容相同时返回空字符串
+pub fn unified(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let edits = edits(&old_lines, &new_lines);
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(..)))
+        .map(|(index, _)| index)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // 把间隔不超过两倍上下文的改动合并为一段
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &index in &changes {
+        let start = index.saturating_sub(CONTEXT);
+        let end = (index + CONTEXT + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut output = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks {
+        let hunk = &edits[start..end];
+        let (mut old_start, mut new_start) = (None, None);
+        let (mut old_count, mut new_count) = (0, 0);
+        let mut body = String::new();
+        for edit in hunk {
+            match *edit {
+                Edit::Equal(i, j) => {
+                    old_start.get_or_insert(i);
+                    new_start.get_or_insert(j);
+                    old_count += 1;
+                    new_count += 1;
+                    body.push_str(&format!(" {}\n", old_lines[i]));
+                }
+                Edit::Delete(i) => {
+                    old_start.get_or_insert(i);
+                    old_count += 1;
+                    body.push_str(&format!("-{}\n", old_lines[i]));
+                }
+                Edit::Insert(j) => {
+                    new_start.get_or_insert(j);
+                    new_count += 1;
+                    body.push_str(&format!("+{}\n", new_lines[j]));
+                }
+            }
+        }
+        let position = |start: Option<usize>, count: usize, before: usize| match start {
+            Some(start) => format!("{},{}", start + 1, count),
+            None => format!("{},0", before),
+        };
+        // 没有行的一边，起始行为段落之前已有的行数
+        let (old_before, new_before) =
+            edits[..start]
+                .iter()
+                .fold((0, 0), |(o, n), edit| match edit {
+                    Edit::Equal(..) => (o + 1, n + 1),
+                    Edit::Delete(_) => (o + 1, n),
+                    Edit::Insert(_) => (o, n + 1),
+                });
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            position(old_start, old_count, old_before),
+            position(new_start, new_count, new_before)
+        ));
+        output.push_str(&body);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_content_has_no_diff() {
+        assert_eq!(unified("a\nb\n", "a\nb\n", "old", "new"), "");
+    }
+
+    #[test]
+    fn finds_shortest_edit_script() {
+        // Myers 论文中的例子，最短编辑距离为 5
+        let old: Vec<&str> = "abcabba".split("").filter(|s| !s.is_empty()).collect();
+        let new: Vec<&str> = "cbabac".split("").filter(|s| !s.is_empty()).collect();
+        let edits = edits(&old, &new);
+        let changes = edits
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Equal(..)))
+            .count();
+        assert_eq!(changes, 5);
+
+        // 按编辑序列应用到旧文本上应得到新文本
+        let applied: Vec<&str> = edits
+            .iter()
+            .filter_map(|edit| match *edit {
+                Edit::Equal(i, _) => Some(old[i]),
+                Edit::Insert(j) => Some(new[j]),
+                Edit::Delete(_) => None,
+            })
+            .collect();
+        assert_eq!(applied, new);
+    }
+
+    #[test]
+    fn formats_changed_line_with_context() {
+        let old = "1\n2\n3\n4\n5\n";
+        let new = "1\n2\nthree\n4\n5\n";
+        assert_eq!(
+            unified(old, new, "a.ts", "b.ts"),
+            "--- a.ts\n+++ b.ts\n@@ -1,5 +1,5 @@\n 1\n 2\n-3\n+three\n 4\n 5\n"
+        );
+    }
+
+    #[test]
+    fn formats_added_and_removed_files() {
+        assert_eq!(
+            unified("", "a\nb\n", "old", "new"),
+            "--- old\n+++ new\n@@ -0,0 +1,2 @@\n+a\n+b\n"
+        );
+        assert_eq!(
+            unified("a\n", "", "old", "new"),
+            "--- old\n+++ new\n@@ -1,1 +0,0 @@\n-a\n"
+        );
+    }
+
+    #[test]
+    fn splits_distant_changes_into_hunks() {
+        let old: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let new: String = (1..=20)
+            .map(|i| match i {
+                2 => "two\n".to_string(),
+                19 => "nineteen\n".to_string(),
+                _ => format!("{}\n", i),
+            })
+            .collect();
+        let diff = unified(&old, &new, "old", "new");
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n"));
+        assert!(diff.contains("@@ -16,5 +16,5 @@\n 16\n 17\n 18\n-19\n+nineteen\n 20\n"));
+    }
+
+    #[test]
+    fn merges_nearby_changes_into_one_hunk() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let new = "1\nb\n3\n4\n5\n6\ng\n8\n";
+        let diff = unified(old, new, "old", "new");
+        assert_eq!(diff.matches("@@ -").count(), 1);
+        assert!(diff.contains("@@ -1,8 +1,8 @@\n"));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod diff;
 pub mod document;
 pub mod file;
 pub mod window;
@@ -1,5 +1,15 @@
 use super::file::get_config_dir;
+use once_cell::sync::Lazy;
 use open;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, Emitter, Manager, Runtime, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+    WindowEvent,
+};
 use tauri_plugin_notification::NotificationExt;
 use tauri_plugin_opener::OpenerExt;
 
@@ -27,3 +37,301 @@ pub async fn notify(app: tauri::AppHandle, msg: &str) -> Result<(), String> {
         .unwrap();
     Ok(())
 }
+
+/// 窗口几何信息持久化文件
+const WINDOW_STATE_FILE: &str = "windows.json";
+
+/// 窗口位置和大小（逻辑像素）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// 窗口创建参数
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// 宽度，0 表示使用默认值
+    pub width: f64,
+    /// 高度，0 表示使用默认值
+    pub height: f64,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub title: Option<String>,
+    /// 自定义页面地址，默认为 `index.html#/{name}`
+    pub url: Option<String>,
+    pub always_on_top: bool,
+    pub decorations: Option<bool>,
+    pub transparent: bool,
+    pub resizable: Option<bool>,
+    pub skip_taskbar: bool,
+    /// 是否记住窗口位置和大小，默认记住
+    pub remember: Option<bool>,
+}
+
+/// 窗口生命周期事件
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowLifecycleEvent {
+    pub label: String,
+    /// created / shown / hidden / focused / blurred / closed
+    pub state: &'static str,
+}
+
+/// 记录的窗口几何信息
+static WINDOW_GEOMETRY: Lazy<Mutex<HashMap<String, WindowGeometry>>> =
+    Lazy::new(|| Mutex::new(load_geometry()));
+
+fn geometry_file() -> Option<PathBuf> {
+    get_config_dir().map(|dir| dir.join(WINDOW_STATE_FILE))
+}
+
+/// 从配置目录读取窗口几何信息
+fn load_geometry() -> HashMap<String, WindowGeometry> {
+    geometry_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 将窗口几何信息写入配置目录
+fn save_geometry() {
+    let Some(path) = geometry_file() else {
+        return;
+    };
+    let geometry = WINDOW_GEOMETRY.lock().unwrap();
+    match serde_json::to_string_pretty(&*geometry) {
+        Ok(content) => {
+            if let Err(e) = fs::write(path, content) {
+                eprintln!("保存窗口状态失败: {}", e);
+            }
+        }
+        Err(e) => eprintln!("序列化窗口状态失败: {}", e),
+    }
+}
+
+/// 记录窗口当前的位置和大小
+fn record_geometry<R: Runtime>(window: &WebviewWindow<R>) {
+    // 最小化时位置和大小没有意义
+    if window.is_minimized().unwrap_or(false) {
+        return;
+    }
+    let (Ok(scale), Ok(position), Ok(size)) = (
+        window.scale_factor(),
+        window.outer_position(),
+        window.inner_size(),
+    ) else {
+        return;
+    };
+    let position = position.to_logical::<f64>(scale);
+    let size = size.to_logical::<f64>(scale);
+    WINDOW_GEOMETRY.lock().unwrap().insert(
+        window.label().to_string(),
+        WindowGeometry {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+        },
+    );
+}
+
+/// 发送窗口生命周期事件
+fn emit_lifecycle<R: Runtime>(app: &AppHandle<R>, label: &str, state: &'static str) {
+    let event = WindowLifecycleEvent {
+        label: label.to_string(),
+        state,
+    };
+    if let Err(e) = app.emit("window-lifecycle", event) {
+        eprintln!("Failed to emit window event: {}", e);
+    }
+}
+
+/// 将 query 转换为 URL 查询字符串
+fn query_string(query: &Option<serde_json::Value>) -> String {
+    let Some(serde_json::Value::Object(map)) = query else {
+        return String::new();
+    };
+    map.iter()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            format!(
+                "{}={}",
+                urlencoding::encode(key),
+                urlencoding::encode(&value)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// 打开窗口，窗口已存在时显示并聚焦
+///
+/// `name` 同时作为窗口的 label，已存在的窗口会收到 `window-query` 事件以便更新参数。
+#[tauri::command]
+pub async fn open_window<R: Runtime>(
+    app: AppHandle<R>,
+    name: String,
+    query: Option<serde_json::Value>,
+    config: Option<WindowConfig>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(&name) {
+        if query.is_some() {
+            let _ = window.emit("window-query", &query);
+        }
+        window.show().map_err(|e| e.to_string())?;
+        window.set_focus().map_err(|e| e.to_string())?;
+        emit_lifecycle(&app, &name, "shown");
+        return Ok(());
+    }
+
+    let config = config.unwrap_or_default();
+    let remember = config.remember.unwrap_or(true);
+
+    let url = match &config.url {
+        Some(url) => url.clone(),
+        None => {
+            let query = query_string(&query);
+            if query.is_empty() {
+                format!("index.html#/{}", name)
+            } else {
+                format!("index.html#/{}?{}", name, query)
+            }
+        }
+    };
+
+    let mut builder = WebviewWindowBuilder::new(&app, &name, WebviewUrl::App(url.into()))
+        .title(config.title.clone().unwrap_or_else(|| name.clone()))
+        .always_on_top(config.always_on_top)
+        .decorations(config.decorations.unwrap_or(false))
+        .transparent(config.transparent)
+        .resizable(config.resizable.unwrap_or(true))
+        .skip_taskbar(config.skip_taskbar)
+        .focused(true);
+
+    // 优先使用记住的位置和大小，其次是传入的参数
+    let saved = if remember {
+        WINDOW_GEOMETRY.lock().unwrap().get(&name).copied()
+    } else {
+        None
+    };
+    match saved {
+        Some(geometry) => {
+            builder = builder
+                .inner_size(geometry.width, geometry.height)
+                .position(geometry.x, geometry.y);
+        }
+        None => {
+            let width = if config.width > 0.0 {
+                config.width
+            } else {
+                800.0
+            };
+            let height = if config.height > 0.0 {
+                config.height
+            } else {
+                600.0
+            };
+            builder = builder.inner_size(width, height);
+            builder = match (config.x, config.y) {
+                (Some(x), Some(y)) => builder.position(x, y),
+                _ => builder.center(),
+            };
+        }
+    }
+
+    let window = builder
+        .build()
+        .map_err(|e| format!("创建窗口失败: {}", e))?;
+
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        let label = handle.label().to_string();
+        let app = handle.app_handle();
+        match event {
+            WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+                if remember {
+                    record_geometry(&handle);
+                }
+            }
+            WindowEvent::Focused(focused) => {
+                emit_lifecycle(app, &label, if *focused { "focused" } else { "blurred" });
+            }
+            WindowEvent::Destroyed => {
+                if remember {
+                    save_geometry();
+                }
+                emit_lifecycle(app, &label, "closed");
+            }
+            _ => {}
+        }
+    });
+
+    emit_lifecycle(&app, &name, "created");
+    Ok(())
+}
+
+/// 隐藏窗口，未指定 label 时隐藏调用的窗口
+#[tauri::command]
+pub async fn hide_window<R: Runtime>(
+    app: AppHandle<R>,
+    window: WebviewWindow<R>,
+    label: Option<String>,
+) -> Result<(), String> {
+    let target = match label {
+        Some(label) => app
+            .get_webview_window(&label)
+            .ok_or_else(|| format!("窗口不存在: {}", label))?,
+        None => window,
+    };
+    record_geometry(&target);
+    save_geometry();
+    target.hide().map_err(|e| e.to_string())?;
+    emit_lifecycle(&app, target.label(), "hidden");
+    Ok(())
+}
+
+/// 显示并聚焦窗口
+#[tauri::command]
+pub async fn show_window<R: Runtime>(app: AppHandle<R>, label: String) -> Result<(), String> {
+    let window = app
+        .get_webview_window(&label)
+        .ok_or_else(|| format!("窗口不存在: {}", label))?;
+    window.show().map_err(|e| e.to_string())?;
+    window.set_focus().map_err(|e| e.to_string())?;
+    emit_lifecycle(&app, &label, "shown");
+    Ok(())
+}
+
+/// 关闭窗口，主窗口只隐藏不关闭
+#[tauri::command]
+pub async fn close_window<R: Runtime>(app: AppHandle<R>, label: String) -> Result<(), String> {
+    let window = app
+        .get_webview_window(&label)
+        .ok_or_else(|| format!("窗口不存在: {}", label))?;
+    if label == "main" {
+        window.hide().map_err(|e| e.to_string())?;
+        emit_lifecycle(&app, &label, "hidden");
+        return Ok(());
+    }
+    record_geometry(&window);
+    window.close().map_err(|e| e.to_string())
+}
+
+/// 列出当前所有窗口的 label 及可见状态
+#[tauri::command]
+pub async fn list_windows<R: Runtime>(app: AppHandle<R>) -> Result<HashMap<String, bool>, String> {
+    Ok(app
+        .webview_windows()
+        .into_iter()
+        .map(|(label, window)| {
+            let visible = window.is_visible().unwrap_or(false);
+            (label, visible)
+        })
+        .collect())
+}
@@ -87,6 +87,13 @@ export interface SearchOptions {
   limit: number;
 }
 
+/* 本地 MCP 服务发起的知识库查询 */
+interface KnowledgeHostRequest {
+  request_id: string;
+  kind: "list" | "search";
+  params: { query?: string; knowledge_ids?: string[] } | null;
+}
+
 /* 进度回调接口 */
 export interface ProgressCallback {
   /* 当前进度（0-100） */
@@ -112,6 +119,35 @@ export class Knowledge {
     this.meta = meta;
   }
 
+  static {
+    /* 其他 MCP 客户端通过本地 MCP 服务查询知识库 */
+    cmd.listen("mcp-host-knowledge-request", async (event) => {
+      const request = event.payload as unknown as KnowledgeHostRequest;
+      try {
+        const result =
+          request.kind === "list"
+            ? Object.values(await KnowledgesStore.getCurrent()).map(
+                ({ id, name, description }) => ({ id, name, description }),
+              )
+            : await Knowledge.search(
+                request.params?.query || "",
+                request.params?.knowledge_ids || [],
+              );
+        await cmd.invoke("mcp_host_respond", {
+          requestId: request.request_id,
+          result,
+        });
+      } catch (error) {
+        await cmd
+          .invoke("mcp_host_respond", {
+            requestId: request.request_id,
+            error: error instanceof Error ? error.message : String(error),
+          })
+          .catch(console.error);
+      }
+    });
+  }
+
   static async get(id: string): Promise<Knowledge> {
     const meta = await KnowledgesStore.getCurrent();
     return new Knowledge(meta[id]);
@@ -1,8 +1,10 @@
 import { ModelItem } from "@/agent/types/agent";
 import {
+  AudioChunk,
   AudioModelInfo,
   AudioModelRequestBody,
   AudioModelResponse,
+  AudioStreamResult,
 } from "@/model/types/audioModel";
 import { gen } from "@/utils/generator";
 import { cmd } from "@/utils/shell";
@@ -88,23 +90,17 @@ export class AudioModel {
   }
 
   /**
-   * 解析响应体，处理不同提供商的响应格式差异
-   * @param payload 原始响应数据字符串
-   * @returns 解析后的内容
+   * 解码音频分片，允许子类重写以处理不同提供商的音频格式
+   * @param chunk 后端发送的音频分片，data 为 base64 编码
+   * @returns 音频数据
    */
-  protected parseResponseBody(payload: string): {
-    completion?: string;
-  } {
-    try {
-      // 默认OpenAI格式解析
-      const data = JSON.parse(payload.replace("data: ", ""));
-      const delta = data.choices?.[0]?.delta;
-      // 提取内容
-      const completion = delta?.content;
-      return { completion };
-    } catch (error) {
-      return {};
+  protected decodeChunk(chunk: AudioChunk): Uint8Array {
+    const binary = atob(chunk.data);
+    const bytes = new Uint8Array(binary.length);
+    for (let i = 0; i < binary.length; i++) {
+      bytes[i] = binary.charCodeAt(i);
     }
+    return bytes;
   }
 
   /** 生成音频
@@ -162,8 +158,10 @@ export class AudioModel {
 
     /* 生成请求ID */
     this.currentRequestId = gen.id();
-    /* 内容 */
-    let completionContent = "";
+    /* 按序号收集的音频分片 */
+    const chunks: Uint8Array[] = [];
+    /* 合成的音频地址 */
+    let audioUrl = "";
 
     /* 消息 */
     let messages = this.Message.listWithOutType();
@@ -191,24 +189,32 @@ export class AudioModel {
 
       console.log(requestBody);
 
-      // 监听流式响应事件
+      // 监听音频分片
       const unlistenStream = await cmd.listen(
         `audio-stream-${this.currentRequestId}`,
         (event) => {
           if (!event.payload) return;
-          /* 适配子类不同的相应格式 */
-          const { completion } = this.parseResponseBody(event.payload);
-
-          /* 如果返回的是正文 */
-          if (completion) {
-            completionContent += completion;
-            this.Message.updateLastMessage({
-              content: completionContent,
-            });
-          }
+          const chunk = event.payload as unknown as AudioChunk;
+          chunks[chunk.index] = this.decodeChunk(chunk);
         },
       );
 
+      // 音频接收完成后合成为一个可播放的地址
+      const finish = (result: AudioStreamResult) => {
+        if (audioUrl || result.cancelled) return;
+        const blob = new Blob(chunks.filter(Boolean), {
+          type: result.content_type || "audio/mpeg",
+        });
+        audioUrl = URL.createObjectURL(blob);
+        this.Message.updateLastMessage({
+          content: audioUrl,
+        });
+      };
+      const unlistenDone = await cmd.listen(
+        `audio-stream-done-${this.currentRequestId}`,
+        (event) => finish(event.payload as unknown as AudioStreamResult),
+      );
+
       // 监听错误事件
       const unlistenError = await cmd.listen(
         `audio-stream-error-${this.currentRequestId}`,
@@ -221,15 +227,17 @@ export class AudioModel {
       );
 
       // 发起流式请求
-      await cmd.invoke("audio_stream", {
+      const result = await cmd.invoke<AudioStreamResult>("audio_stream", {
         apiUrl: this.info.api_url,
         apiKey: this.info.api_key,
         requestId: this.currentRequestId,
         requestBody,
       });
+      finish(result);
 
       // 清理事件监听器
       unlistenStream();
+      unlistenDone();
       unlistenError();
 
       this.Message.updateLastMessage({
@@ -237,7 +245,7 @@ export class AudioModel {
       });
 
       return {
-        content: completionContent,
+        content: audioUrl,
         stop: () => this.stop(),
       };
     } catch (error) {
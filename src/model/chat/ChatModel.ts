@@ -3,6 +3,8 @@ import { ModelItem } from "@/agent/types/agent";
 import {
   ChatModelRequestBody,
   ChatModelResponse,
+  ChatProviderKind,
+  ChatStreamDelta,
   CompletionMessage,
   ToolCallReply,
   ToolRequestBody,
@@ -31,6 +33,8 @@ export class ChatModel {
   protected currentRequestId: string | undefined;
   /** 温度 */
   protected temperature: number = 1;
+  /** 后端使用的服务商适配器，默认为 OpenAI 兼容接口 */
+  protected provider: ChatProviderKind = "openai";
 
   /** 构造函数
    * @param config 模型配置
@@ -88,87 +92,27 @@ export class ChatModel {
   }
 
   /**
-   * 解析响应体，处理不同提供商的响应格式差异
-   * @param payload 原始响应数据字符串
-   * @returns 解析后的内容、推理和工具调用
+   * 把工具调用增量合并到对应下标的调用中
+   * @param calls 已收到的工具调用，以下标为键
+   * @param delta 工具调用增量
    */
-  protected ResponseBodyAdapter(payload: string): {
-    completion?: string;
-    reasoner?: string;
-    tool_call?: ToolCallReply;
-  } {
-    try {
-      // 默认OpenAI格式解析
-      const data = JSON.parse(payload.replace("data: ", ""));
-      const delta = data.choices?.[0]?.delta;
-      // 提取内容
-      const completion = delta?.content;
-
-      // 提取工具调用
-      let tool_call;
-      if (delta?.tool_calls?.[0]) {
-        tool_call = delta.tool_calls[0] as ToolCallReply;
-      }
-
-      // 提取推理内容（如果有）
-      const reasoner = delta?.reasoning_content;
-
-      return {
-        completion,
-        reasoner,
-        tool_call,
-      };
-    } catch (error) {
-      return {};
-    }
-  }
-
-  /**
-   * 工具调用适配器，处理工具调用的解析和合并
-   * @param rawToolCalls 收集到的原始工具调用数组
-   * @returns 处理后的工具调用数组
-   */
-  protected ToolCallAdapter(rawToolCalls: ToolCallReply[]): ToolCallReply[] {
-    if (!rawToolCalls.length) return [];
-
-    const callsMap = new Map<string, ToolCallReply>();
-
-    for (const chunk of rawToolCalls) {
-      // 如果有ID，说明是新的工具调用或者已存在调用的更新
-      if (chunk.id) {
-        const key = `${chunk.id}-${chunk.index}`;
-        const existingCall = callsMap.get(key);
-
-        if (existingCall) {
-          // 更新已存在的调用
-          existingCall.function.arguments += chunk.function.arguments || "";
-        } else {
-          // 创建新的调用
-          const newCall: ToolCallReply = {
-            ...chunk,
-            function: {
-              ...chunk.function,
-              arguments: chunk.function.arguments || "",
-            },
-          };
-          callsMap.set(key, newCall);
-        }
-      }
-      // 没有ID但有参数内容，将参数追加到最后一个处理的工具调用
-      else if (chunk.function.arguments) {
-        // 获取最后一个添加的工具调用
-        const lastAddedKey = Array.from(callsMap.keys()).pop();
-        if (lastAddedKey) {
-          const lastCall = callsMap.get(lastAddedKey);
-          if (lastCall) {
-            lastCall.function.arguments += chunk.function.arguments;
-          }
-        }
-      }
+  protected static mergeToolCall(
+    calls: Map<number, ToolCallReply>,
+    delta: Extract<ChatStreamDelta, { type: "tool_call" }>,
+  ) {
+    const call = calls.get(delta.index);
+    if (call) {
+      call.id ||= delta.id || "";
+      call.function.name ||= delta.name || "";
+      call.function.arguments += delta.arguments;
+      return;
     }
-
-    // 将Map转换为数组，并按index排序
-    return Array.from(callsMap.values()).sort((a, b) => a.index - b.index);
+    calls.set(delta.index, {
+      id: delta.id || "",
+      index: delta.index,
+      type: "function",
+      function: { name: delta.name || "", arguments: delta.arguments },
+    });
   }
 
   /** 流式生成
@@ -188,8 +132,8 @@ export class ChatModel {
     this.currentRequestId = gen.id();
     /* 消息 */
     let messages: CompletionMessage[] = message;
-    /* 工具调用收集 */
-    let rawToolCalls: ToolCallReply[] = [];
+    /* 工具调用收集，以下标为键 */
+    const toolCalls = new Map<number, ToolCallReply>();
     let completionContent = "";
 
     try {
@@ -207,21 +151,22 @@ export class ChatModel {
 
       console.log("requestBody", requestBody);
 
-      // 监听流式响应事件
+      // 监听后端解析后的流式事件
       const unlistenStream = await cmd.listen(
-        `chat-stream-${this.currentRequestId}`,
+        `chat-delta-${this.currentRequestId}`,
         (event) => {
-          if (!event.payload) return;
-          /* 适配子类不同的相应格式 */
-          const chunk = this.ResponseBodyAdapter(event.payload);
-
-          /* 内容 */
-          completionContent += chunk.completion || "";
-          onChunk?.({ completion: chunk.completion, reasoner: chunk.reasoner });
-
-          /* 收集工具调用 */
-          if (chunk.tool_call) {
-            rawToolCalls.push(chunk.tool_call);
+          const delta = event.payload as unknown as ChatStreamDelta;
+          switch (delta.type) {
+            case "content":
+              completionContent += delta.content;
+              onChunk?.({ completion: delta.content });
+              break;
+            case "reasoning":
+              onChunk?.({ reasoner: delta.content });
+              break;
+            case "tool_call":
+              ChatModel.mergeToolCall(toolCalls, delta);
+              break;
           }
         },
       );
@@ -239,6 +184,7 @@ export class ChatModel {
         apiKey: this.info.api_key,
         requestId: this.currentRequestId,
         requestBody,
+        provider: this.provider,
       };
 
       // 发起流式请求
@@ -248,13 +194,10 @@ export class ChatModel {
       unlistenStream();
       unlistenError();
 
-      // 直接处理所有收集到的工具调用
-      const tool_calls = this.ToolCallAdapter(rawToolCalls);
-
       return {
         body: completionContent,
         stop: () => this.stop(),
-        tool: tool_calls,
+        tool: [...toolCalls.values()].sort((a, b) => a.index - b.index),
       };
     } catch (error) {
       this.currentRequestId = undefined;
@@ -262,7 +205,7 @@ export class ChatModel {
         body: completionContent,
         error: String(error),
         stop: () => this.stop(),
-        tool: rawToolCalls,
+        tool: [...toolCalls.values()],
       };
     }
   }
@@ -296,6 +239,7 @@ export class ChatModel {
         temperature: this.temperature,
         tools: this.tools,
       },
+      provider: this.provider,
     };
     try {
       // 假设 tauri 命令返回字符串
@@ -9,6 +9,7 @@ import {
   WORKFLOW_TOOL_NAME_PREFIX,
 } from "@/assets/const";
 import { Knowledge } from "@/knowledge/Knowledge";
+import "@/toolkit/Approval";
 import { MCP, MCP_Actived } from "@/toolkit/MCP";
 import { StartNodeConfig, WorkflowBody } from "@/page/workflow/types/nodes";
 import { ToolkitStore, Toolkit } from "@/toolkit/Toolkit";
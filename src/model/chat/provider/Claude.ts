@@ -1,8 +1,13 @@
-import { ChatModelRequestBody, ToolCallReply } from "@/model/types/chatModel";
+import {
+  ChatModelRequestBody,
+  ChatProviderKind,
+} from "@/model/types/chatModel";
 import { ChatModel } from "../ChatModel";
 import { ChatModelManager, ChatModelProvider } from "../ChatModelManager";
 
 export class Claude extends ChatModel {
+  protected provider: ChatProviderKind = "anthropic";
+
   constructor(model: string) {
     const api_key = ChatModelManager.getApiKey(ClaudeProvider.name);
     const configWithDefaults = {
@@ -30,54 +35,6 @@ export class Claude extends ChatModel {
 
     return claudeBody;
   }
-
-  /**
-   * 解析Claude响应体格式
-   * @param payload 原始响应数据字符串
-   * @returns 统一格式的解析结果
-   */
-  protected ResponseBodyAdapter(payload: string): {
-    content?: string;
-    reasoner?: string;
-    tool_call?: ToolCallReply;
-  } {
-    try {
-      // 解析Claude的响应格式
-      const data = JSON.parse(payload.replace("data: ", ""));
-
-      let content = undefined;
-      let reasoner = undefined;
-      let tool_call = undefined;
-
-      // 处理文本内容
-      if (data.type === "content_block_delta" && data.delta?.text) {
-        content = data.delta.text;
-      }
-
-      // 处理工具调用
-      if (data.type === "tool_use") {
-        // Claude工具格式与OpenAI不同，需要适配
-        tool_call = {
-          id: data.id || `tool_${Date.now()}`,
-          index: 0,
-          type: "function" as const,
-          function: {
-            name: data.name,
-            arguments: JSON.stringify(data.input || {}),
-          },
-        };
-      }
-
-      return {
-        content,
-        reasoner,
-        tool_call,
-      };
-    } catch (error) {
-      console.error("Error parsing Claude response:", error);
-      return {};
-    }
-  }
 }
 
 // 注册Claude提供商
@@ -1,5 +1,5 @@
 import { ChatModel } from "../ChatModel";
-import { ChatModelRequestBody, ToolCallReply } from "@/model/types/chatModel";
+import { ChatModelRequestBody } from "@/model/types/chatModel";
 import { ChatModelManager, ChatModelProvider } from "../ChatModelManager";
 
 export class Gemini extends ChatModel {
@@ -31,65 +31,6 @@ export class Gemini extends ChatModel {
 
     return geminiBody;
   }
-
-  /**
-   * 解析Gemini响应体格式
-   * @param payload 原始响应数据字符串
-   * @returns 统一格式的解析结果
-   */
-  protected ResponseBodyAdapter(payload: string): {
-    content?: string;
-    reasoner?: string;
-    tool_call?: ToolCallReply;
-  } {
-    try {
-      // 解析Gemini的响应格式
-      const data = JSON.parse(payload.replace("data: ", ""));
-
-      let content = undefined;
-      let reasoner = undefined;
-      let tool_call = undefined;
-
-      // 处理文本内容
-      if (data.candidates?.[0]?.content?.parts) {
-        const textPart = data.candidates[0].content.parts.find(
-          (part: any) => part.text,
-        );
-
-        if (textPart) {
-          content = textPart.text;
-        }
-      }
-
-      // 处理工具调用
-      if (data.candidates?.[0]?.content?.parts) {
-        const functionPart = data.candidates[0].content.parts.find(
-          (part: any) => part.functionCall,
-        );
-
-        if (functionPart?.functionCall) {
-          tool_call = {
-            id: functionPart.functionCall.name || "function_call_id",
-            index: 0,
-            type: "function" as const,
-            function: {
-              name: functionPart.functionCall.name,
-              arguments: JSON.stringify(functionPart.functionCall.args || {}),
-            },
-          };
-        }
-      }
-
-      return {
-        content,
-        reasoner,
-        tool_call,
-      };
-    } catch (error) {
-      console.error("Error parsing Gemini response:", error);
-      return {};
-    }
-  }
 }
 
 // 注册Gemini提供商
@@ -1,5 +1,5 @@
 import { ChatModel } from "../ChatModel";
-import { ChatModelRequestBody, ToolCallReply } from "@/model/types/chatModel";
+import { ChatModelRequestBody } from "@/model/types/chatModel";
 import { ChatModelManager, ChatModelProvider } from "../ChatModelManager";
 
 export class Hunyuan extends ChatModel {
@@ -30,56 +30,6 @@ export class Hunyuan extends ChatModel {
 
     return hunyuanBody;
   }
-
-  /**
-   * 解析混元响应体格式
-   * @param payload 原始响应数据字符串
-   * @returns 统一格式的解析结果
-   */
-  protected ResponseBodyAdapter(payload: string): {
-    content?: string;
-    reasoner?: string;
-    tool_call?: ToolCallReply;
-  } {
-    try {
-      // 解析混元的响应格式
-      const data = JSON.parse(payload.replace("data: ", ""));
-
-      let content = undefined;
-      let reasoner = undefined;
-      let tool_call = undefined;
-
-      // 处理文本内容
-      // 混元的流式响应格式可能是 data.Choices[0].Delta.Content
-      if (data.Choices?.[0]?.Delta?.Content) {
-        content = data.Choices[0].Delta.Content;
-      }
-
-      // 处理工具调用
-      // 混元的工具调用格式可能是 data.Choices[0].Delta.ToolCalls
-      if (data.Choices?.[0]?.Delta?.ToolCalls?.[0]) {
-        const toolCall = data.Choices[0].Delta.ToolCalls[0];
-        tool_call = {
-          id: toolCall.Id || `tool_${Date.now()}`,
-          index: 0,
-          type: "function" as const,
-          function: {
-            name: toolCall.Function?.Name,
-            arguments: toolCall.Function?.Arguments || "{}",
-          },
-        };
-      }
-
-      return {
-        content,
-        reasoner,
-        tool_call,
-      };
-    } catch (error) {
-      console.error("Error parsing Hunyuan response:", error);
-      return {};
-    }
-  }
 }
 
 // 注册混元提供商
@@ -29,3 +29,20 @@ export interface AudioMessage {
   loading?: boolean;
   error?: string;
 }
+
+/** 后端发送的音频分片 */
+export interface AudioChunk {
+  /** 分片序号，从 0 开始 */
+  index: number;
+  /** base64 编码的音频数据 */
+  data: string;
+}
+
+/** 音频流结束时的汇总信息 */
+export interface AudioStreamResult {
+  content_type: string | null;
+  total_bytes: number;
+  /** 保存的文件路径（如果启用了保存） */
+  path: string | null;
+  cancelled: boolean;
+}
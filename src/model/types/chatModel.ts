@@ -112,3 +112,26 @@ export interface ChatModelResponse<T = string> {
   /* 错误 */
   error?: string;
 }
+
+/** 后端适配的服务商类型，决定请求和流式响应的格式 */
+export type ChatProviderKind = "openai" | "anthropic" | "gemini" | "ollama";
+
+/** 后端解析后的流式事件，对应 `chat-delta-{id}` */
+export type ChatStreamDelta =
+  | { type: "content"; content: string }
+  | { type: "reasoning"; content: string }
+  | {
+      type: "tool_call";
+      index: number;
+      id?: string;
+      name?: string;
+      arguments: string;
+    }
+  | {
+      type: "usage";
+      prompt_tokens: number;
+      completion_tokens: number;
+      total_tokens: number;
+    }
+  | { type: "finish"; reason: string }
+  | { type: "done" };
@@ -6,6 +6,7 @@ import {
   VisionModelRequestBody,
   VisionModelResponse,
 } from "@/model/types/visionModel";
+import { ChatStreamDelta } from "@/model/types/chatModel";
 import { ImageManager } from "@/resources/Image";
 import { gen } from "@/utils/generator";
 import { cmd } from "@/utils/shell";
@@ -65,25 +66,6 @@ export class VisionModel {
     return body;
   }
 
-  /**
-   * 解析响应体，处理不同提供商的响应格式差异
-   * @param payload 原始响应数据字符串
-   * @returns 解析后的内容
-   */
-  protected parseResponseBody(payload: string): {
-    completion?: string;
-    reasoner?: string;
-  } {
-    try {
-      const data = JSON.parse(payload.replace("data: ", ""));
-      const delta = data.choices?.[0]?.delta;
-      const completion = delta?.content;
-      return { completion };
-    } catch (error) {
-      return {};
-    }
-  }
-
   public async execute(image: string, query: string): Promise<string> {
     this.Message.setSystem(
       `你是一个专业的视觉模型，请根据用户的问题和图片内容，给出详细的回答。`,
@@ -149,17 +131,14 @@ export class VisionModel {
 
       console.log(requestBody);
 
-      // 监听流式响应事件
+      // 监听后端解析后的流式事件
       const unlistenStream = await cmd.listen(
-        `chat-stream-${this.currentRequestId}`,
+        `chat-delta-${this.currentRequestId}`,
         (event) => {
-          if (!event.payload) return;
-          /* 适配子类不同的相应格式 */
-          const { completion } = this.parseResponseBody(event.payload);
-
+          const delta = event.payload as unknown as ChatStreamDelta;
           /* 如果返回的是正文 */
-          if (completion) {
-            completionContent += completion;
+          if (delta.type === "content") {
+            completionContent += delta.content;
             this.Message.updateLastMessage({
               content: completionContent,
             });
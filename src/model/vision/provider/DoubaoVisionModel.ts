@@ -29,25 +29,6 @@ export class DoubaoVisionModel extends VisionModel {
       max_tokens: 32768,
     };
   }
-
-  /**
-   * 解析响应体，处理豆包的响应格式
-   * @param payload 原始响应数据字符串
-   * @returns 解析后的内容
-   */
-  protected parseResponseBody(payload: string): {
-    completion?: string;
-    reasoner?: string;
-  } {
-    try {
-      const data = JSON.parse(payload.replace("data: ", ""));
-      const delta = data.choices?.[0]?.delta;
-      const completion = delta?.content;
-      return { completion };
-    } catch (error) {
-      return {};
-    }
-  }
 }
 
 /** 豆包视觉模型提供商 */
@@ -33,25 +33,6 @@ export class QianwenVisionModel extends VisionModel {
       max_tokens: 4096,
     };
   }
-
-  /**
-   * 解析响应体，处理通义千问的响应格式
-   * @param payload 原始响应数据字符串
-   * @returns 解析后的内容
-   */
-  protected parseResponseBody(payload: string): {
-    completion?: string;
-    reasoner?: string;
-  } {
-    try {
-      const data = JSON.parse(payload.replace("data: ", ""));
-      const delta = data.choices?.[0]?.delta;
-      const completion = delta?.content;
-      return { completion };
-    } catch (error) {
-      return {};
-    }
-  }
 }
 
 /** 通义千问视觉模型提供商 */
@@ -3,9 +3,8 @@ import { dialog } from "@/components/custom/DialogModal";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { UserMananger } from "@/services/user/User";
-import { parsePluginFromString } from "@/toolkit/parser";
-import { ToolkitStore } from "@/toolkit/Toolkit";
-import { ToolkitMarketProps } from "@/toolkit/types";
+import { Toolkit, ToolkitStore } from "@/toolkit/Toolkit";
+import { FunctionCallProps, ToolkitMarketProps } from "@/toolkit/types";
 import { cmd } from "@/utils/shell";
 import { useEffect, useState } from "react";
 import {
@@ -42,6 +41,14 @@ const PluginDetailsPanel = ({
   isInstalling,
   isOwner,
 }: PluginDetailsPanelProps) => {
+  const [tools, setTools] = useState<FunctionCallProps[]>([]);
+
+  useEffect(() => {
+    Toolkit.parse(plugin.content)
+      .then((manifest) => setTools(manifest.tools))
+      .catch(console.error);
+  }, [plugin.content]);
+
   return (
     <div className="flex flex-col gap-6 py-1 px-1">
       {/* 描述部分 */}
@@ -54,21 +61,19 @@ const PluginDetailsPanel = ({
       {/* 工具列表 */}
       <div className="bg-muted rounded-xl p-3 shadow-sm">
         <div className="max-h-[250px] overflow-y-auto pr-1 space-y-1">
-          {Object.values(parsePluginFromString(plugin.content).tools).map(
-            (tool) => (
-              <div
-                key={tool.name}
-                className="flex gap-3 p-2 bg-muted/40 rounded-lg hover:bg-muted/60 transition-colors"
-              >
-                <div className="font-medium text-sm min-w-[130px] text-primary">
-                  {tool.name}
-                </div>
-                <div className="text-sm text-muted-foreground">
-                  {tool.description}
-                </div>
+          {tools.map((tool) => (
+            <div
+              key={tool.name}
+              className="flex gap-3 p-2 bg-muted/40 rounded-lg hover:bg-muted/60 transition-colors"
+            >
+              <div className="font-medium text-sm min-w-[130px] text-primary">
+                {tool.name}
               </div>
-            ),
-          )}
+              <div className="text-sm text-muted-foreground">
+                {tool.description}
+              </div>
+            </div>
+          ))}
         </div>
       </div>
 
@@ -35,7 +35,7 @@ const MCP_TYPES = [
   {
     label: "SSE",
     value: "sse",
-    description: "使用 SSE 的 MCP 服务",
+    description: "通过 SSE 或 Streamable HTTP 连接的远程 MCP 服务",
     type: "运行时",
   },
 ];
@@ -163,7 +163,7 @@ export function MCPTab() {
           <div className="flex flex-col gap-2">
             <label className="text-sm font-medium">服务设置</label>
             <small className="text-xs text-muted-foreground pl-4">
-              mcp目前仅支持npx环境的stdio方式的mcp服务
+              Node.js 服务通过 npx 启动，Python 服务通过 uvx 启动，SSE 服务填写服务地址
             </small>
             <DrawerSelector
               title="运行时类型"
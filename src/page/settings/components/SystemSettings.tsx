@@ -6,6 +6,7 @@ import { Progress } from "@/components/ui/progress";
 import { Switch } from "@/components/ui/switch";
 import { cmd } from "@/utils/shell";
 import { disable, enable, isEnabled } from "@tauri-apps/plugin-autostart";
+import { open } from "@tauri-apps/plugin-dialog";
 import { relaunch } from "@tauri-apps/plugin-process";
 import { check } from "@tauri-apps/plugin-updater";
 import { useEffect, useState } from "react";
@@ -169,6 +170,9 @@ export function DenoSettings() {
   const [isInstalled, setIsInstalled] = useState(false);
   const [isChecking, setIsChecking] = useState(true);
   const [version, setVersion] = useState<string>("");
+  const [path, setPath] = useState<string>("");
+  const [pinned, setPinned] = useState<string>("");
+  const [sandbox, setSandbox] = useState(true);
 
   useEffect(() => {
     checkNode();
@@ -179,20 +183,42 @@ export function DenoSettings() {
       setIsChecking(true);
       const result = await cmd.invoke<Record<string, string>>("node_check");
       setIsInstalled(result.installed === "true");
+      setPinned(result.pinned || "");
+      setSandbox(result.sandbox !== "false");
       if (result.installed === "true") {
         setVersion(result.version || "");
+        setPath(result.path || "");
       } else {
         setVersion("");
+        setPath("");
       }
     } catch (error) {
       console.error("Check Node environment failed:", error);
       setIsInstalled(false);
       setVersion("");
+      setPath("");
     } finally {
       setIsChecking(false);
     }
   };
 
+  /* 指定 Node 可执行文件，为空时恢复自动检测 */
+  const setNodePath = async (nodePath: string | null) => {
+    try {
+      await cmd.invoke("node_set_path", { path: nodePath });
+      await checkNode();
+    } catch (error) {
+      toast.error(`Set Node path failed: ${error}`);
+    }
+  };
+
+  const chooseNode = async () => {
+    const selected = await open({ multiple: false, directory: false });
+    if (typeof selected === "string") {
+      await setNodePath(selected);
+    }
+  };
+
   const showInstallDialog = () => {
     dialog({
       title: "Install",
@@ -208,13 +234,28 @@ export function DenoSettings() {
       return "checking...";
     }
     if (!isInstalled) {
+      if (pinned) {
+        return (
+          <span className="text-yellow-600">
+            ⚠️ {pinned} is not a usable Node - Cannot use plugin features
+          </span>
+        );
+      }
       return (
         <span className="text-yellow-600">
           ⚠️ Not installed - Cannot use plugin features
         </span>
       );
     }
-    return `installed (${version})`;
+    if (!sandbox) {
+      return (
+        <span className="text-yellow-600">
+          ⚠️ Node {version} is too old for the plugin sandbox - Node 20+ is
+          required to run plugins ({path})
+        </span>
+      );
+    }
+    return `installed (${version}) ${path}`;
   };
 
   return (
@@ -230,7 +271,16 @@ export function DenoSettings() {
             />
             re-check
           </Button>
-          {!isInstalled && (
+          <Button variant="ghost" size="sm" onClick={chooseNode}>
+            <TbFolder className="w-4 h-4" />
+            choose
+          </Button>
+          {pinned && (
+            <Button variant="ghost" size="sm" onClick={() => setNodePath(null)}>
+              auto
+            </Button>
+          )}
+          {!isInstalled && !pinned && (
             <Button size="sm" onClick={showInstallDialog}>
               install
             </Button>
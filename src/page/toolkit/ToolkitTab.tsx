@@ -9,6 +9,7 @@ import {
   DropdownMenuTrigger,
 } from "@/components/ui/dropdown-menu";
 import { cn } from "@/lib/utils";
+import { gen } from "@/utils/generator";
 import { cmd } from "@/utils/shell";
 import { EditorView } from "@codemirror/view";
 import { tags as t } from "@lezer/highlight";
@@ -23,6 +24,8 @@ import {
   TbMaximize,
   TbMinimize,
   TbPackage,
+  TbPackageExport,
+  TbPackageImport,
   TbPlayerPlay,
   TbPlug,
   TbPlus,
@@ -35,6 +38,7 @@ import { TestDrawer } from "./components/TestDrawer";
 import { dialog } from "@/components/custom/DialogModal";
 import { Echoi } from "@/lib/echo/Echo";
 import { ToolkitStore, Toolkit } from "@/toolkit/Toolkit";
+import { ManifestError, PluginLog, PluginProgress } from "@/toolkit/types";
 import { javascript } from "@codemirror/lang-javascript";
 import { githubDarkInit } from "@uiw/codemirror-theme-github";
 import CodeMirror, { ReactCodeMirrorRef } from "@uiw/react-codemirror";
@@ -44,6 +48,7 @@ import * as prettierPluginEstree from "prettier/plugins/estree";
 import { format } from "prettier/standalone";
 import { toast } from "sonner";
 import { NodeDeps } from "./NodeDeps";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { ToolkitCloudManager } from "@/cloud/ToolkitCloudManager";
 
 const CurrentPlugin = new Echoi<Toolkit>(new Toolkit());
@@ -59,6 +64,12 @@ export function ToolkitTab() {
   const [isTestDrawerOpen, setIsTestDrawerOpen] = useState(false);
   /* 测试结果 */
   const [result, setResult] = useState<any>(null);
+  const [logs, setLogs] = useState<PluginLog[]>([]);
+  const [progress, setProgress] = useState<PluginProgress | null>(null);
+  /* 解析插件时发现的问题 */
+  const [manifestErrors, setManifestErrors] = useState<ManifestError[]>([]);
+  // 进行中的测试调用
+  const testCallId = useRef<string>("");
   const [isFullscreen, setIsFullscreen] = useState(false);
   /* 插件是否已在市场中 */
   const [isPluginInMarket, setIsPluginInMarket] = useState(false);
@@ -69,6 +80,11 @@ export function ToolkitTab() {
   const props = plugins[plugin.props.id];
   const content = plugin.content || "";
 
+  // 切换插件时清除上一个插件的解析问题
+  useEffect(() => {
+    setManifestErrors([]);
+  }, [props?.id]);
+
   // 检查插件是否已在市场中
   useEffect(() => {
     if (props?.id) {
@@ -96,16 +112,34 @@ export function ToolkitTab() {
     try {
       if (!props.id) return;
       setIsSubmitting(true);
-      const result = await plugin.execute(tool, testArgs);
+      setLogs([]);
+      setProgress(null);
+      testCallId.current = gen.id();
+      const result = await plugin.execute(tool, testArgs, {
+        callId: testCallId.current,
+        onLog: (log) => setLogs((logs) => [...logs, log]),
+        onProgress: setProgress,
+      });
       setResult(result);
     } catch (error) {
       console.error(error);
       toast.error(`test plugin error: ${error}`);
     } finally {
+      testCallId.current = "";
       setIsSubmitting(false);
     }
   };
 
+  // 取消测试
+  const handleCancelTest = async () => {
+    if (!testCallId.current) return;
+    try {
+      await Toolkit.cancel(testCallId.current);
+    } catch (error) {
+      console.error(error);
+    }
+  };
+
   const handleCreate = useCallback(async () => {
     const plugin = await Toolkit.create();
     /* 保存到插件存储 */
@@ -152,6 +186,73 @@ export function ToolkitTab() {
     });
   }, [props, plugin, isPluginInMarket]);
 
+  // 导入插件包，先预览再确认
+  const handleImportBundle = useCallback(async () => {
+    const path = await open({
+      multiple: false,
+      directory: false,
+      filters: [{ name: "Ghostie Plugin", extensions: ["ghostie-plugin"] }],
+    });
+    if (typeof path !== "string") return;
+
+    try {
+      const preview = await Toolkit.previewBundle(path);
+      const { manifest } = preview;
+      dialog.confirm({
+        title: `导入插件 ${manifest.name} v${manifest.version}`,
+        content: (
+          <div className="space-y-1 text-sm">
+            <p>{manifest.description || "No description"}</p>
+            <p>工具: {preview.tools.map((tool) => tool.name).join(", ")}</p>
+            {preview.missing_dependencies.length > 0 && (
+              <p>将安装依赖: {preview.missing_dependencies.join(", ")}</p>
+            )}
+            {preview.missing_env.length > 0 && (
+              <p>需要设置环境变量: {preview.missing_env.join(", ")}</p>
+            )}
+            {preview.conflict && (
+              <p className="text-destructive">
+                已存在同一插件，导入将覆盖现有内容，之前的内容保留在修订中
+              </p>
+            )}
+          </div>
+        ),
+        okText: preview.conflict ? "覆盖导入" : "导入",
+        onOk: async () => {
+          try {
+            const imported = await Toolkit.importBundle(
+              path,
+              preview.conflict ? "replace" : undefined,
+            );
+            CurrentPlugin.set(imported, { replace: true });
+            toast.success(`插件 ${manifest.name} 已导入`);
+          } catch (error) {
+            toast.error(`导入插件失败: ${String(error)}`);
+          }
+        },
+      });
+    } catch (error) {
+      toast.error(`读取插件包失败: ${String(error)}`);
+    }
+  }, []);
+
+  // 导出当前插件为插件包
+  const handleExportBundle = useCallback(async () => {
+    if (!props?.id) return;
+    const path = await save({
+      defaultPath: `${plugin.props.name || props.id}.ghostie-plugin`,
+      filters: [{ name: "Ghostie Plugin", extensions: ["ghostie-plugin"] }],
+    });
+    if (!path) return;
+
+    try {
+      await plugin.exportBundle(path);
+      toast.success("插件包已导出");
+    } catch (error) {
+      toast.error(`导出插件失败: ${String(error)}`);
+    }
+  }, [props, plugin]);
+
   // 处理代码格式化
   const handleFormatCode = useCallback(async () => {
     const view = editorRef.current?.view;
@@ -216,6 +317,10 @@ export function ToolkitTab() {
                   <TbCode className="w-4 h-4" />
                   代码打开插件位置
                 </DropdownMenuItem>
+                <DropdownMenuItem onClick={handleImportBundle}>
+                  <TbPackageImport className="w-4 h-4" />
+                  导入插件包
+                </DropdownMenuItem>
               </DropdownMenuContent>
             </DropdownMenu>
           </>
@@ -338,6 +443,10 @@ export function ToolkitTab() {
                       <TbUpload className="w-4 h-4" />
                       {isPluginInMarket ? "更新到市场" : "上传到市场"}
                     </DropdownMenuItem>
+                    <DropdownMenuItem onClick={handleExportBundle}>
+                      <TbPackageExport className="w-4 h-4" />
+                      导出插件包
+                    </DropdownMenuItem>
                     <DropdownMenuItem
                       variant={"destructive"}
                       onClick={async () => {
@@ -358,6 +467,15 @@ export function ToolkitTab() {
             </div>
           }
         >
+          {manifestErrors.length > 0 && (
+            <div className="mb-2 space-y-1 text-xs text-destructive">
+              {manifestErrors.map((error, index) => (
+                <div key={index}>
+                  第 {error.line} 行: {error.message}
+                </div>
+              ))}
+            </div>
+          )}
           <CodeMirror
             ref={editorRef}
             className={cn("h-full overflow-y-auto rounded-xl", {
@@ -402,7 +520,10 @@ export function ToolkitTab() {
               EditorView.lineWrapping,
             ]}
             onChange={(value) => {
-              plugin.updateContent(value.toString());
+              plugin
+                .updateContent(value.toString())
+                .then((plugin) => setManifestErrors(plugin.errors))
+                .catch(console.error);
             }}
             placeholder={`Write your plugin code, refer to the development documentation`}
             basicSetup={{
@@ -428,10 +549,13 @@ export function ToolkitTab() {
         testTool={testTool}
         testArgs={testArgs}
         result={result}
+        logs={logs}
+        progress={progress}
         isSubmitting={isSubmitting}
         onTestToolChange={handleTestToolChange}
         onTestArgsChange={setTestArgs}
         onTest={handleTest}
+        onCancel={handleCancelTest}
       />
     </PreferenceLayout>
   );
@@ -1,8 +1,9 @@
-import { ToolkitProps } from "@/toolkit/types";
+import { PluginLog, PluginProgress, ToolkitProps } from "@/toolkit/types";
 import JsonViewer from "@/components/custom/JsonViewer";
 import { Button } from "@/components/ui/button";
 import { Drawer } from "@/components/ui/drawer";
-import { TbLoader2, TbPlayerPlay } from "react-icons/tb";
+import { Progress } from "@/components/ui/progress";
+import { TbLoader2, TbPlayerPlay, TbPlayerStop } from "react-icons/tb";
 import { ParamInput } from "./ParamInput";
 import { CustomSelect } from "@/components/ui/custom-select";
 
@@ -13,10 +14,13 @@ interface TestDrawerProps {
   testTool: string;
   testArgs: Record<string, unknown>;
   result: any;
+  logs: PluginLog[];
+  progress: PluginProgress | null;
   isSubmitting: boolean;
   onTestToolChange: (value: string) => void;
   onTestArgsChange: (args: Record<string, unknown>) => void;
   onTest: (tool: string) => void;
+  onCancel: () => void;
 }
 
 export function TestDrawer({
@@ -26,10 +30,13 @@ export function TestDrawer({
   testTool,
   testArgs,
   result,
+  logs,
+  progress,
   isSubmitting,
   onTestToolChange,
   onTestArgsChange,
   onTest,
+  onCancel,
 }: TestDrawerProps) {
   const parameters = selectedPlugin?.tools.find(
     (tool) => tool.name === testTool,
@@ -45,24 +52,34 @@ export function TestDrawer({
         <div className="p-2 w-full">
           <div className="flex items-center justify-between w-full">
             <h3 className="text-lg font-semibold">{selectedPlugin?.name}</h3>{" "}
-            <Button
-              disabled={!testTool || isSubmitting}
-              onClick={() => onTest(testTool)}
-              variant="default"
-              size="sm"
-            >
-              {isSubmitting ? (
-                <span className="flex items-center gap-1.5">
-                  <TbLoader2 className="w-3.5 h-3.5 animate-spin" />
-                  Testing...
-                </span>
-              ) : (
-                <span className="flex items-center gap-1.5">
-                  <TbPlayerPlay className="w-3.5 h-3.5" />
-                  Run Test
-                </span>
+            <div className="flex items-center gap-2">
+              {isSubmitting && (
+                <Button onClick={onCancel} variant="ghost" size="sm">
+                  <span className="flex items-center gap-1.5">
+                    <TbPlayerStop className="w-3.5 h-3.5" />
+                    Cancel
+                  </span>
+                </Button>
               )}
-            </Button>
+              <Button
+                disabled={!testTool || isSubmitting}
+                onClick={() => onTest(testTool)}
+                variant="default"
+                size="sm"
+              >
+                {isSubmitting ? (
+                  <span className="flex items-center gap-1.5">
+                    <TbLoader2 className="w-3.5 h-3.5 animate-spin" />
+                    Testing...
+                  </span>
+                ) : (
+                  <span className="flex items-center gap-1.5">
+                    <TbPlayerPlay className="w-3.5 h-3.5" />
+                    Run Test
+                  </span>
+                )}
+              </Button>
+            </div>
           </div>
           <p className="text-sm text-muted-foreground mb-2">
             {selectedPlugin?.description}
@@ -112,6 +129,44 @@ export function TestDrawer({
             </div>
           )}
 
+          {isSubmitting && progress && (
+            <div className="space-y-1">
+              <Progress
+                value={
+                  progress.total
+                    ? (progress.progress / progress.total) * 100
+                    : progress.progress
+                }
+              />
+              {progress.message && (
+                <p className="text-xs text-muted-foreground">
+                  {progress.message}
+                </p>
+              )}
+            </div>
+          )}
+
+          {logs.length > 0 && (
+            <div className="bg-muted/30 rounded-lg p-3">
+              <h4 className="text-sm font-medium mb-3 flex items-center gap-2">
+                <span className="h-1.5 w-1.5 rounded-full bg-primary"></span>
+                Logs
+              </h4>
+              <pre className="text-xs font-mono whitespace-pre-wrap break-all">
+                {logs.map((log, index) => (
+                  <div
+                    key={index}
+                    className={
+                      log.stream === "stderr" ? "text-destructive" : undefined
+                    }
+                  >
+                    {log.message}
+                  </div>
+                ))}
+              </pre>
+            </div>
+          )}
+
           {result && (
             <div className="bg-muted/30 rounded-lg p-3">
               <h4 className="text-sm font-medium mb-3 flex items-center gap-2">
@@ -0,0 +1,101 @@
+import { cmd } from "@/utils/shell";
+import { toast } from "sonner";
+
+export type ApprovalAction = "allow" | "ask" | "deny";
+
+/* 参数条件 */
+export interface ApprovalArgPattern {
+  /* 参数路径，嵌套字段用 . 分隔 */
+  arg: string;
+  kind: "equals" | "prefix" | "path_prefix";
+  value: string;
+}
+
+/* 审批规则，tool 为 mcp:{服务}/{工具} 或 plugin:{插件}/{函数}，可用 * 通配 */
+export interface ApprovalRule {
+  tool: string;
+  action: ApprovalAction;
+  args?: ApprovalArgPattern[];
+}
+
+export interface ApprovalPolicy {
+  defaultAction: ApprovalAction;
+  rules: ApprovalRule[];
+}
+
+/* 插件申请的权限 */
+export interface PluginPermissions {
+  fsRead?: string[];
+  fsWrite?: string[];
+  net?: string[];
+  process?: boolean;
+  env?: string[];
+}
+
+/* 后端发送的审批请求，kind 为 permission 时 tool 是插件 id，args 是申请的权限 */
+export interface ApprovalRequest {
+  request_id: string;
+  kind: "tool" | "permission";
+  tool: string;
+  args: Record<string, unknown>;
+  timeout_ms: number;
+}
+
+/* 审计记录 */
+export interface ApprovalAuditEntry {
+  time: string;
+  tool: string;
+  args: Record<string, unknown>;
+  decision: "allowed" | "denied";
+  decidedBy: "policy" | "user" | "timeout" | "headless";
+  rule?: number;
+}
+
+export class ToolApproval {
+  static {
+    /* 工具调用需要确认时提示用户 */
+    cmd.listen("tool-approval-request", (event) => {
+      const request = event.payload as unknown as ApprovalRequest;
+      const respond = (approved: boolean) =>
+        cmd
+          .invoke("approval_respond", {
+            requestId: request.request_id,
+            approved,
+          })
+          .catch(console.error);
+      const title =
+        request.kind === "permission"
+          ? `插件 ${request.tool} 申请以下权限，是否授予？`
+          : `是否允许调用 ${request.tool}？`;
+      toast(title, {
+        description: JSON.stringify(request.args),
+        duration: request.timeout_ms,
+        action: { label: "允许", onClick: () => respond(true) },
+        cancel: { label: "拒绝", onClick: () => respond(false) },
+        onDismiss: () => respond(false),
+      });
+    });
+  }
+
+  /* 获取审批策略 */
+  static getPolicy() {
+    return cmd.invoke<ApprovalPolicy>("approval_policy_get");
+  }
+
+  /* 保存审批策略 */
+  static savePolicy(policy: ApprovalPolicy) {
+    return cmd.invoke("approval_policy_save", { policy });
+  }
+
+  /* 查询审计记录，最新的在前 */
+  static audit(
+    query: {
+      tool?: string;
+      decision?: "allowed" | "denied";
+      since?: string;
+      limit?: number;
+    } = {},
+  ) {
+    return cmd.invoke<ApprovalAuditEntry[]>("approval_audit", { query });
+  }
+}
@@ -1,6 +1,7 @@
 import { Echoi } from "@/lib/echo/Echo";
 import { gen } from "@/utils/generator";
 import { cmd } from "@/utils/shell";
+import { convertFileSrc } from "@tauri-apps/api/core";
 import { toast } from "sonner";
 export const MCP_DATABASE = "mcp";
 
@@ -25,12 +26,89 @@ export interface MCPProps {
   type: "node" | "python" | "sse";
   server: string;
   env: Record<string, string>;
+  /* 自定义启动命令，为空时根据 type 和 server 推断 */
+  command?: string;
+  args?: string[];
+  cwd?: string;
+  /* 远程服务的传输方式与请求头，type 为 sse 时使用 server 作为地址 */
+  transport?: MCPTransport;
+  headers?: Record<string, string>;
   name: string;
   description: string;
   opened: boolean;
   error?: string;
 }
 
+export type MCPTransport = "stdio" | "sse" | "streamable_http";
+
+/* 后端启动MCP服务所需的配置 */
+export interface MCPServerConfig {
+  command: string;
+  args: string[];
+  cwd?: string;
+  env: Record<string, string>;
+  transport: MCPTransport;
+  url?: string;
+  headers?: Record<string, string>;
+}
+
+/* 后端推送的服务状态 */
+export interface MCPServiceStatus {
+  id: string;
+  status: "starting" | "running" | "crashed" | "stopped";
+  message?: string;
+  restarts: number;
+}
+
+/* 后端转发的服务端通知 */
+export interface MCPNotification {
+  service_id: string;
+  kind:
+    | "tools_list_changed"
+    | "resources_list_changed"
+    | "prompts_list_changed"
+    | "resource_updated"
+    | "logging";
+  params?: Record<string, any>;
+}
+
+/* 工具调用进度 */
+export interface MCPToolProgress {
+  service_id: string;
+  call_id: string;
+  progress: number;
+  total?: number;
+}
+
+/* 保存在本地的二进制内容引用 */
+export interface MCPBlobRef {
+  id: string;
+  mimeType: string;
+  size: number;
+}
+
+/* 工具调用结果，图片和二进制资源以引用代替原始内容 */
+export interface MCPToolResult {
+  content: (
+    | { type: "text"; text: string }
+    | { type: "image"; blob: MCPBlobRef }
+    | {
+        type: "resource";
+        uri: string;
+        mimeType?: string;
+        text?: string;
+        blob?: MCPBlobRef;
+      }
+  )[];
+  isError?: boolean;
+}
+
+/* 解析后的内容引用 */
+export interface MCPResolvedBlob extends MCPBlobRef {
+  path: string;
+  data?: string;
+}
+
 /* 当前激活的MCP服务 */
 export const MCP_Actived = new Echoi<Record<string, MCPTool[]>>({});
 
@@ -46,6 +124,26 @@ export class MCP {
   }
 
   static {
+    /* 服务崩溃、重启时同步状态 */
+    cmd.listen("mcp-service-status", async (event) => {
+      const { id, status, message } = event.payload as unknown as MCPServiceStatus;
+      const mcp = (await MCPStore.getCurrent())[id];
+      if (!mcp) return;
+      if (status === "running") {
+        new MCP(mcp).getInfo();
+        MCPStore.set({ [id]: { ...mcp, error: undefined } });
+      } else if (status === "crashed") {
+        MCP_Actived.delete(id);
+        MCPStore.set({ [id]: { ...mcp, error: message } });
+      }
+    });
+    /* 服务端工具列表变化时刷新 */
+    cmd.listen("mcp-notification", async (event) => {
+      const { service_id, kind } = event.payload as unknown as MCPNotification;
+      if (kind !== "tools_list_changed") return;
+      const mcp = (await MCPStore.getCurrent())[service_id];
+      if (mcp) new MCP(mcp).getInfo();
+    });
     MCPStore.getCurrent().then((mcps) => {
       for (const mcp of Object.values(mcps)) {
         const m = new MCP(mcp);
@@ -128,15 +226,72 @@ export class MCP {
     MCPStore.delete(id);
   }
 
+  /**
+   * 生成启动配置
+   * 未指定命令时，node 类型使用 npx，python 类型使用 uvx，sse 类型连接远程服务
+   */
+  config(): MCPServerConfig {
+    const { command, args, cwd, server, type, transport, headers } = this.props;
+    if (type === "sse") {
+      return {
+        command: "",
+        args: [],
+        env: {},
+        url: server,
+        headers: headers || {},
+        /* 旧版 SSE 服务的地址通常以 /sse 结尾，其余按 Streamable HTTP 连接 */
+        transport:
+          transport || (/\/sse\/?$/.test(server) ? "sse" : "streamable_http"),
+      };
+    }
+    if (command) {
+      return { command, args: args || [], cwd, env: {}, transport: "stdio" };
+    }
+    if (type === "python") {
+      return { command: "uvx", args: [server], env: {}, transport: "stdio" };
+    }
+    return { command: "npx", args: ["-y", server], env: {}, transport: "stdio" };
+  }
+
   /**
    * 调用工具
    * 执行指定MCP服务中的特定工具
    */
-  run(tool: string, args: Record<string, unknown>) {
-    return cmd.invoke("call_tool", {
-      id: this.props.server,
+  run(
+    tool: string,
+    args: Record<string, unknown>,
+    options: { callId?: string; timeoutMs?: number } = {},
+  ) {
+    return cmd.invoke<MCPToolResult>("call_tool", {
+      id: this.props.id,
       name: tool,
       args,
+      callId: options.callId,
+      timeoutMs: options.timeoutMs,
+    });
+  }
+
+  /**
+   * 解析内容引用
+   * 返回可用于 img 等标签的资源地址，withData 为 true 时同时返回 base64 内容
+   */
+  static async resolveBlob(id: string, withData = false) {
+    const blob = await cmd.invoke<MCPResolvedBlob>("resolve_blob", {
+      id,
+      includeData: withData,
+    });
+    return { ...blob, url: convertFileSrc(blob.path) };
+  }
+
+  /**
+   * 取消调用
+   * 取消 run 时指定 callId 的调用
+   */
+  cancel(callId: string, reason?: string) {
+    return cmd.invoke("cancel_tool_call", {
+      id: this.props.id,
+      callId,
+      reason,
     });
   }
 
@@ -147,8 +302,9 @@ export class MCP {
   async start() {
     try {
       await cmd.invoke("start_service", {
-        id: this.props.server,
+        id: this.props.id,
         env: this.props.env,
+        config: this.config(),
       });
       this.getInfo();
       this.update({
@@ -170,7 +326,7 @@ export class MCP {
    */
   async stop() {
     cmd.invoke("stop_service", {
-      id: this.props.server,
+      id: this.props.id,
     });
     this.update({ opened: false });
     MCP_Actived.delete(this.props.id);
@@ -183,7 +339,7 @@ export class MCP {
   async getInfo() {
     try {
       const result = await cmd.invoke<[string, MCPTool[]]>("get_service_info", {
-        id: this.props.server,
+        id: this.props.id,
       });
       MCP_Actived.set({
         [this.props.id]: result[1],
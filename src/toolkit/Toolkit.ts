@@ -1,13 +1,24 @@
 import { TOOLKIT_DATABASE_INDEX } from "@/assets/const";
 import { Echoi } from "@/lib/echo/Echo";
 import { ImageManager } from "@/resources/Image";
-import { ToolkitProps } from "@/toolkit/types";
+import {
+  BundleManifest,
+  BundlePreview,
+  ConflictStrategy,
+  ManifestError,
+  PluginExecuteOptions,
+  PluginLog,
+  PluginManifest,
+  PluginProgress,
+  PluginRevision,
+  ToolkitProps,
+  TrashedPlugin,
+} from "@/toolkit/types";
 import { gen } from "@/utils/generator";
 import { cmd } from "@/utils/shell";
 import { Echo } from "echo-state";
 import { makeAutoObservable, toJS } from "mobx";
 import ts from "typescript";
-import { parsePluginFromString } from "./parser";
 
 /* 默认 */
 export const DEFAULT_TOOLKIT: ToolkitProps = {
@@ -32,6 +43,8 @@ export const CurrentToolkitId = new Echoi<string>("");
 export class Toolkit {
   props: ToolkitProps = DEFAULT_TOOLKIT;
   content: string = "";
+  /* 最近一次解析插件时发现的问题 */
+  errors: ManifestError[] = [];
 
   /** 构造函数 */
   constructor(plugin?: Partial<ToolkitProps>) {
@@ -114,30 +127,45 @@ export class Toolkit {
   }
 
   /** 处理插件内容
-   * 解析插件内容并更新插件信息
+   * 由后端解析已保存的插件内容，返回插件信息
    */
-  async processContent(
-    content: string,
-  ): Promise<Pick<ToolkitProps, "name" | "description" | "tools" | "version">> {
-    try {
-      const parsedFunctions = parsePluginFromString(content);
-      return {
-        name: parsedFunctions.meta.name || "undefined",
-        description: parsedFunctions.meta.description || "",
-        version: parsedFunctions.meta.version || "0.0.1",
-        tools: Object.values(parsedFunctions.tools).map((tool) => ({
-          ...tool,
-          plugin: this.props.id,
-        })),
-      };
-    } catch (error) {
-      console.error("处理插件内容时出错:", error);
-      throw new Error("插件内容处理失败");
-    }
+  async processContent(): Promise<
+    Pick<ToolkitProps, "name" | "description" | "tools" | "version">
+  > {
+    const manifest = await Toolkit.manifest(this.props.id);
+    this.errors = manifest.errors;
+    return {
+      name: manifest.name || "undefined",
+      description: manifest.description,
+      version: manifest.version,
+      tools: manifest.tools.map((tool) => ({
+        ...tool,
+        plugin: this.props.id,
+      })),
+    };
+  }
+
+  /** 获取插件清单 */
+  static manifest(id: string) {
+    return cmd.invoke<PluginManifest>("plugin_manifest", { id });
+  }
+
+  /** 解析未保存的插件源码的清单 */
+  static parse(content: string) {
+    return cmd.invoke<PluginManifest>("plugin_parse_content", { content });
+  }
+
+  /** 列出所有插件及其清单 */
+  static listDetailed() {
+    return cmd.invoke<(PluginManifest & { id: string })[]>(
+      "plugin_list_detailed",
+    );
   }
 
-  /** 更新插件内容 */
-  async updateContent(content: string) {
+  /** 更新插件内容
+   * 每次保存记录一个修订，message 是可选的修订说明
+   */
+  async updateContent(content: string, message?: string) {
     if (!this.props.id) return this;
 
     try {
@@ -145,12 +173,13 @@ export class Toolkit {
       await cmd.invoke("plugin_save_content", {
         id: this.props.id,
         content: content,
+        message,
       });
 
       this.content = content;
 
       // 处理插件内容
-      const pluginInfo = await this.processContent(content);
+      const pluginInfo = await this.processContent();
 
       // 更新插件信息
       await this.update({
@@ -170,13 +199,97 @@ export class Toolkit {
     }
   }
 
-  /** 删除插件 */
+  /** 导出为插件包
+   * 包含源码、清单、需要的环境变量名和 npm 依赖
+   */
+  exportBundle(path: string) {
+    return cmd.invoke<BundleManifest>("plugin_export", {
+      id: this.props.id,
+      path,
+    });
+  }
+
+  /** 预览插件包，不做任何修改 */
+  static previewBundle(path: string) {
+    return cmd.invoke<BundlePreview>("plugin_import_preview", { path });
+  }
+
+  /** 导入插件包
+   * 安装缺少的依赖后保存插件并加入插件存储，conflict 为插件 id 已存在时的处理方式
+   */
+  static async importBundle(path: string, conflict?: ConflictStrategy) {
+    const id = await cmd.invoke<string>("plugin_import", { path, conflict });
+    const plugin = new Toolkit({ id });
+    plugin.content = await cmd.invoke<string>("plugin_get_content", { id });
+    await plugin.update(await plugin.processContent());
+    return plugin;
+  }
+
+  /** 列出插件的修订，最新的在前 */
+  static revisions(id: string) {
+    return cmd.invoke<PluginRevision[]>("plugin_revisions", { id });
+  }
+
+  /** 获取修订的内容 */
+  static revisionContent(id: string, revision: string) {
+    return cmd.invoke<string>("plugin_revision_content", { id, revision });
+  }
+
+  /** 比较两个修订，to 为空时与当前内容比较 */
+  static revisionDiff(id: string, from: string, to?: string) {
+    return cmd.invoke<string>("plugin_revision_diff", { id, from, to });
+  }
+
+  /** 恢复到某个修订 */
+  async restoreRevision(revision: string) {
+    const content = await cmd.invoke<string>("plugin_revision_restore", {
+      id: this.props.id,
+      revision,
+    });
+    this.content = content;
+    const pluginInfo = await this.processContent();
+    await this.update(pluginInfo);
+    return this;
+  }
+
+  /** 获取插件保留的修订数 */
+  static revisionLimit(id: string) {
+    return cmd.invoke<number>("plugin_revision_limit", { id });
+  }
+
+  /** 设置插件保留的修订数，超出的最早修订会被删除 */
+  static async setRevisionLimit(id: string, limit: number) {
+    await cmd.invoke("plugin_revision_limit_set", { id, limit });
+  }
+
+  /** 列出回收站中的插件 */
+  static trash() {
+    return cmd.invoke<TrashedPlugin[]>("plugin_trash_list");
+  }
+
+  /** 从回收站恢复插件 */
+  static async restoreFromTrash(id: string) {
+    await cmd.invoke("plugin_trash_restore", { id });
+    const plugin = new Toolkit({ id });
+    plugin.content = await cmd.invoke<string>("plugin_get_content", { id });
+    await plugin.update(await plugin.processContent());
+    return plugin;
+  }
+
+  /** 清空回收站 */
+  static async emptyTrash() {
+    await cmd.invoke("plugin_trash_empty");
+  }
+
+  /** 删除插件
+   * 插件和它的修订移入回收站，可以恢复
+   */
   static async delete(id: string) {
     try {
       // 删除插件存储
       ToolkitStore.delete(id);
 
-      // 删除插件文件
+      // 插件文件移入回收站
       await cmd.invoke("plugin_delete", { id });
 
       // 如果是当前插件，重置当前插件ID
@@ -191,9 +304,27 @@ export class Toolkit {
   }
 
   /** 执行插件
-   * 执行一个插件
+   * 执行一个插件，可通过 options.callId 取消，onLog 和 onProgress 接收执行期间的输出和进度
    */
-  async execute(tool: string, args: Record<string, unknown>) {
+  async execute(
+    tool: string,
+    args: Record<string, unknown>,
+    options: PluginExecuteOptions = {},
+  ) {
+    const callId = options.callId || gen.id();
+    const { onLog, onProgress } = options;
+    const unlistenLog = onLog
+      ? await cmd.listen(`plugin-log-${this.props.id}`, (event) => {
+          const log = event.payload as unknown as PluginLog;
+          if (log.call_id === callId) onLog(log);
+        })
+      : undefined;
+    const unlistenProgress = onProgress
+      ? await cmd.listen("plugin-tool-progress", (event) => {
+          const progress = event.payload as unknown as PluginProgress;
+          if (progress.call_id === callId) onProgress(progress);
+        })
+      : undefined;
     try {
       // 获取插件内容（从后端获取最新内容）
       const tsContent = await cmd.invoke<string>("plugin_get_content", {
@@ -205,14 +336,20 @@ export class Toolkit {
       // 替换__IMAGE__表达式
       processedContent = await this.replaceImageExpressions(processedContent);
 
-      // 使用TypeScript编译器将TypeScript代码编译成JavaScript代码
-      const jsContent = this.compileTypeScriptToJavaScript(processedContent);
+      // 未经改写的源码交给后端编译，后端据此确认插件身份；改写过的在前端编译成JavaScript
+      const content =
+        processedContent === tsContent
+          ? tsContent
+          : this.compileTypeScriptToJavaScript(processedContent);
 
-      // 调用后端执行JavaScript代码
+      // 调用后端执行
       const result = await cmd.invoke("plugin_execute", {
-        content: jsContent,
+        content,
         tool: tool,
         args: args,
+        id: this.props.id,
+        callId,
+        timeoutMs: options.timeoutMs,
       });
       return result;
     } catch (error) {
@@ -220,9 +357,17 @@ export class Toolkit {
       throw new Error(
         `执行插件时出错:${typeof error === "object" ? JSON.stringify(error) : error}`,
       );
+    } finally {
+      unlistenLog?.();
+      unlistenProgress?.();
     }
   }
 
+  /** 取消进行中的插件调用 */
+  static async cancel(callId: string) {
+    await cmd.invoke("plugin_cancel", { callId });
+  }
+
   /** 替换__DB__表达式
    * 替换代码中的__DB__("数据表ID", (item)=>{...})表达式为实际值
    */
@@ -1,3 +1,5 @@
+import { PluginPermissions } from "@/toolkit/Approval";
+
 /** 工具函数参数类型 */
 export type ToolPropertyType =
   | "string"
@@ -77,3 +79,98 @@ export interface ToolkitMarketProps extends Omit<ToolkitProps, "tools"> {
   /* 插件作者 */
   user_id: string;
 }
+
+/* 插件执行期间的输出 */
+export interface PluginLog {
+  call_id: string;
+  tool: string;
+  stream: "stdout" | "stderr";
+  message: string;
+}
+
+/* 插件通过 progress() 报告的进度 */
+export interface PluginProgress {
+  plugin_id?: string;
+  call_id: string;
+  tool: string;
+  progress: number;
+  total?: number;
+  message?: string;
+}
+
+/* 插件执行选项 */
+export interface PluginExecuteOptions {
+  /* 调用 id，用于取消，为空时自动生成 */
+  callId?: string;
+  /* 超时毫秒数，为空时使用插件头部的 @timeout */
+  timeoutMs?: number;
+  onLog?: (log: PluginLog) => void;
+  onProgress?: (progress: PluginProgress) => void;
+}
+
+/* 解析插件时发现的问题 */
+export interface ManifestError {
+  /* 所在行，从 1 开始 */
+  line: number;
+  message: string;
+}
+
+/* 后端解析的插件清单 */
+export interface PluginManifest {
+  name: string;
+  description: string;
+  version: string;
+  author: string | null;
+  tools: FunctionCallProps[];
+  errors: ManifestError[];
+}
+
+/* 插件的一个修订 */
+export interface PluginRevision {
+  id: string;
+  time: string;
+  message: string | null;
+  size: number;
+}
+
+/* 回收站中的插件 */
+export interface TrashedPlugin {
+  id: string;
+  name: string;
+  deleted_at: string;
+  revisions: number;
+}
+
+/* 插件包的清单 */
+export interface BundleManifest {
+  format: number;
+  id: string;
+  name: string;
+  description: string;
+  version: string;
+  author: string | null;
+  /* 插件需要的环境变量名 */
+  env: string[];
+  /* npm 依赖，包名 -> 版本 */
+  dependencies: Record<string, string>;
+  permissions: PluginPermissions;
+  checksum: string;
+  exported_at: string;
+}
+
+/* 导入插件包前的预览 */
+export interface BundlePreview {
+  manifest: BundleManifest;
+  source: string;
+  tools: FunctionCallProps[];
+  errors: ManifestError[];
+  /* 已存在同 id 的插件 */
+  conflict: boolean;
+  /* 尚未安装的依赖，形如 name@version */
+  missing_dependencies: string[];
+  /* 尚未设置的环境变量 */
+  missing_env: string[];
+}
+
+/* 导入时插件 id 已存在的处理方式：报错、覆盖或导入为副本 */
+export type ConflictStrategy = "fail" | "replace" | "copy";